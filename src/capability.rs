@@ -1,6 +1,8 @@
 use crate::cli::SandboxArgs;
+use crate::config::CommandRule;
 use crate::error::{NonoError, Result};
-use crate::profile::{self, Profile};
+use crate::limits::ResourceLimits;
+use crate::profile::{self, NetEnforcement, Profile, WorkdirAccess};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -25,6 +27,59 @@ impl std::fmt::Display for FsAccess {
     }
 }
 
+/// Network namespace for the command (`--net`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NetMode {
+    /// Share the host's network (no network namespace)
+    #[default]
+    Host,
+    /// New network namespace without any interface up
+    None,
+    /// New network namespace with only the loopback interface up
+    Loopback,
+}
+
+impl std::fmt::Display for NetMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetMode::Host => write!(f, "host"),
+            NetMode::None => write!(f, "none"),
+            NetMode::Loopback => write!(f, "loopback"),
+        }
+    }
+}
+
+/// IPC that Landlock can scope to the sandbox (ABI V6+)
+///
+/// A scoped command cannot reach the matching resources created outside
+/// the sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcScope {
+    /// Connecting to abstract Unix sockets (D-Bus, X11, forwarded agents)
+    AbstractUnixSocket,
+    /// Sending signals
+    Signal,
+}
+
+impl IpcScope {
+    /// Every scope, applied by default
+    pub const ALL: [IpcScope; 2] = [IpcScope::AbstractUnixSocket, IpcScope::Signal];
+}
+
+impl std::fmt::Display for IpcScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpcScope::AbstractUnixSocket => write!(f, "abstract Unix sockets"),
+            IpcScope::Signal => write!(f, "signals"),
+        }
+    }
+}
+
+/// Terminal devices whose ioctls interactive commands need
+pub const TTY_DEVICES: &[&str] = &["/dev/tty", "/dev/pts"];
+
 /// A filesystem capability - grants access to a specific path
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsCapability {
@@ -36,6 +91,56 @@ pub struct FsCapability {
     pub access: FsAccess,
     /// True if this is a single file, false if directory (recursive)
     pub is_file: bool,
+    /// Programs under this path may not be executed (`--read-noexec`)
+    #[serde(default)]
+    pub noexec: bool,
+    /// Where the capability was granted
+    #[serde(default)]
+    pub source: CapabilitySource,
+}
+
+/// Where a filesystem capability was granted, for `nono why --explain`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CapabilitySource {
+    /// A command-line flag
+    #[default]
+    Cli,
+    /// An entry in a profile's `[filesystem]` section
+    Profile {
+        name: String,
+        /// The profile file (`None` for built-in profiles)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<PathBuf>,
+        /// Line of the entry in the profile file
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<usize>,
+    },
+    /// The working directory, shared by the profile's `[workdir]` section
+    /// (or read-only without a profile)
+    Workdir,
+    /// The session's private temporary directory
+    SessionTmp,
+    /// The capability state file `nono why --self` reads
+    StateFile,
+}
+
+impl std::fmt::Display for CapabilitySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapabilitySource::Cli => write!(f, "command line"),
+            CapabilitySource::Profile { name, file, line } => match (file, line) {
+                (Some(file), Some(line)) => {
+                    write!(f, "profile '{}' at {}:{}", name, file.display(), line)
+                }
+                (Some(file), None) => write!(f, "profile '{}' at {}", name, file.display()),
+                (None, _) => write!(f, "built-in profile '{}'", name),
+            },
+            CapabilitySource::Workdir => write!(f, "working directory"),
+            CapabilitySource::SessionTmp => write!(f, "session temp directory"),
+            CapabilitySource::StateFile => write!(f, "capability state file"),
+        }
+    }
 }
 
 impl FsCapability {
@@ -64,6 +169,8 @@ impl FsCapability {
             resolved,
             access,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         })
     }
 
@@ -97,11 +204,41 @@ impl FsCapability {
             resolved,
             access,
             is_file: true,
+            noexec: false,
+            source: CapabilitySource::Cli,
         })
     }
 }
 
 impl FsCapability {
+    /// The working directory capability a profile's `[workdir]` section grants, if any
+    pub fn for_workdir(profile: &Profile, workdir: &Path) -> Result<Option<Self>> {
+        let access = match profile.workdir.access {
+            WorkdirAccess::Read => FsAccess::Read,
+            WorkdirAccess::Write => FsAccess::Write,
+            WorkdirAccess::ReadWrite => FsAccess::ReadWrite,
+            WorkdirAccess::None => return Ok(None),
+        };
+        let mut cap = Self::new_dir(workdir.to_path_buf(), access)?;
+        cap.noexec = profile.workdir.noexec;
+        cap.source = CapabilitySource::Workdir;
+        Ok(Some(cap))
+    }
+
+    /// Deny executing programs under this path, leaving read access alone
+    pub fn without_exec(mut self) -> Self {
+        self.noexec = true;
+        self
+    }
+
+    /// Whether programs under this path may be executed
+    ///
+    /// Readable paths are executable unless marked `noexec`; write-only
+    /// paths never are.
+    pub fn executable(&self) -> bool {
+        self.access != FsAccess::Write && !self.noexec
+    }
+
     /// Human-readable label for the filesystem entry type.
     ///
     /// Inspects the resolved path to distinguish regular files, sockets,
@@ -143,10 +280,32 @@ pub struct CapabilitySet {
     pub fs: Vec<FsCapability>,
     /// Network access blocked (network allowed by default; true = blocked)
     pub net_block: bool,
+    /// Fail instead of running unblocked when `net_block` can't be enforced
+    pub net_block_required: bool,
+    /// `net_block` was requested but the kernel can't enforce it
+    pub net_block_degraded: bool,
+    /// Network namespace for the command (`--net`)
+    pub net_mode: NetMode,
+    /// IPC scoped to the sandbox
+    pub scopes: Vec<IpcScope>,
+    /// Device files and directories of devices the command may issue ioctls on
+    pub ioctl_devices: Vec<PathBuf>,
     /// Commands explicitly allowed (overrides default blocklist)
     pub allowed_commands: Vec<String>,
     /// Additional commands to block (extends default blocklist)
     pub blocked_commands: Vec<String>,
+    /// Additional command rules (extends the default rules)
+    pub command_rules: Vec<CommandRule>,
+    /// Check every exec in the process tree, not just the command
+    /// (`--exec-guard`)
+    pub exec_guard: bool,
+    /// The only executables the command may run (`[commands] allow_only`),
+    /// with the interpreters they need; `None` allows any readable file
+    pub exec_allowlist: Option<Vec<PathBuf>>,
+    /// Resource limits applied to the command
+    pub limits: ResourceLimits,
+    /// Run in a minimal root of system and granted paths (`--isolate`)
+    pub isolate: bool,
 }
 
 impl CapabilitySet {
@@ -161,7 +320,8 @@ impl CapabilitySet {
     }
 
     /// Deduplicate filesystem capabilities by resolved path
-    /// For duplicates, keeps the highest access level (ReadWrite > Read/Write)
+    /// For duplicates, keeps the highest access level (ReadWrite > Read/Write),
+    /// executable if any of them is
     pub fn deduplicate(&mut self) {
         use std::collections::HashMap;
 
@@ -169,19 +329,25 @@ impl CapabilitySet {
         let mut seen: HashMap<(PathBuf, bool), usize> = HashMap::new();
         let mut to_remove = Vec::new();
 
-        for (i, cap) in self.fs.iter().enumerate() {
-            let key = (cap.resolved.clone(), cap.is_file);
+        for i in 0..self.fs.len() {
+            let key = (self.fs[i].resolved.clone(), self.fs[i].is_file);
             if let Some(&existing_idx) = seen.get(&key) {
                 // Duplicate found - decide which to keep
-                let existing = &self.fs[existing_idx];
-                if cap.access == FsAccess::ReadWrite && existing.access != FsAccess::ReadWrite {
+                let (cap, existing) = (&self.fs[i], &self.fs[existing_idx]);
+                let noexec = !cap.executable() && !existing.executable();
+                let kept = if cap.access == FsAccess::ReadWrite
+                    && existing.access != FsAccess::ReadWrite
+                {
                     // New one has higher access, remove old
                     to_remove.push(existing_idx);
                     seen.insert(key, i);
+                    i
                 } else {
                     // Keep existing, remove new
                     to_remove.push(i);
-                }
+                    existing_idx
+                };
+                self.fs[kept].noexec = noexec;
             } else {
                 seen.insert(key, i);
             }
@@ -195,6 +361,49 @@ impl CapabilitySet {
         }
     }
 
+    /// Allow ioctls on a device file or a directory of devices
+    ///
+    /// Missing paths are skipped: device nodes differ between systems.
+    pub fn add_ioctl_device(&mut self, path: &Path) {
+        match path.canonicalize() {
+            Ok(resolved) => {
+                if !self.ioctl_devices.contains(&resolved) {
+                    self.ioctl_devices.push(resolved);
+                }
+            }
+            Err(_) => tracing::warn!("Device path '{}' not found, skipping", path.display()),
+        }
+    }
+
+    /// Allow executing a program in allowlist mode, given by name (looked
+    /// up in `PATH`) or path, along with the interpreter it needs
+    ///
+    /// Programs that are not installed are skipped.
+    pub fn add_executable(&mut self, program: &Path) {
+        let found = if program.components().count() > 1 {
+            program.canonicalize().ok()
+        } else {
+            which::which(program)
+                .ok()
+                .and_then(|p| p.canonicalize().ok())
+        };
+        let Some(resolved) = found else {
+            tracing::warn!("Program '{}' not found, skipping", program.display());
+            return;
+        };
+
+        let allowlist = self.exec_allowlist.get_or_insert_with(Vec::new);
+        if allowlist.contains(&resolved) {
+            return;
+        }
+        allowlist.push(resolved.clone());
+        // The kernel opens a binary's dynamic linker, or a script's
+        // interpreter, the same way as the program itself
+        for interpreter in interpreters(&resolved) {
+            self.add_executable(&interpreter);
+        }
+    }
+
     /// Check if this set has any filesystem capabilities
     pub fn has_fs(&self) -> bool {
         !self.fs.is_empty()
@@ -230,6 +439,16 @@ impl CapabilitySet {
             caps.add_fs(cap);
         }
 
+        for path in &args.read_noexec {
+            let cap = FsCapability::new_dir(path.clone(), FsAccess::Read)?.without_exec();
+            caps.add_fs(cap);
+        }
+
+        for path in &args.exec_dir {
+            let cap = FsCapability::new_dir(path.clone(), FsAccess::Read)?;
+            caps.add_fs(cap);
+        }
+
         // Process file permissions
         for path in &args.allow_file {
             let cap = FsCapability::new_file(path.clone(), FsAccess::ReadWrite)?;
@@ -247,33 +466,47 @@ impl CapabilitySet {
         }
 
         // Process --net-block flag
-        caps.net_block = args.net_block;
+        caps.net_block = args.net_block || args.require_net_block;
+        caps.net_block_required = args.require_net_block;
+        caps.net_mode = args.net.unwrap_or_default();
+        caps.scopes = IpcScope::ALL.to_vec();
 
         // Process command allow/block lists
         caps.allowed_commands = args.allow_command.clone();
         caps.blocked_commands = args.block_command.clone();
+        caps.exec_guard = args.exec_guard;
+
+        caps.limits = ResourceLimits::from(&args.limits);
+        caps.isolate = args.isolate;
 
         caps.deduplicate();
         Ok(caps)
     }
 
     /// Build capabilities from a profile, with CLI overrides
-    pub fn from_profile(profile: &Profile, workdir: &Path, args: &SandboxArgs) -> Result<Self> {
+    pub fn from_profile(
+        profile: &Profile,
+        workdir: &Path,
+        tmpdir: Option<&Path>,
+        args: &SandboxArgs,
+    ) -> Result<Self> {
         let mut caps = Self::new();
 
-        // Helper to process profile paths and add capabilities
+        // Helper to process profile paths, returning each capability with
+        // its index in the profile's list
         fn process_profile_paths(
-            caps: &mut CapabilitySet,
             paths: &[String],
             workdir: &Path,
+            tmpdir: Option<&Path>,
             access: FsAccess,
             is_file: bool,
-        ) -> Result<()> {
-            for path_str in paths {
-                let path = profile::expand_vars(path_str, workdir);
+        ) -> Result<Vec<(usize, FsCapability)>> {
+            let mut caps = Vec::new();
+            for (index, path_str) in paths.iter().enumerate() {
+                let path = profile::expand_vars(path_str, workdir, tmpdir);
                 if is_file {
                     if path.exists() && !path.is_dir() {
-                        caps.add_fs(FsCapability::new_file(path, access)?);
+                        caps.push((index, FsCapability::new_file(path, access)?));
                     } else if path.exists() {
                         tracing::warn!(
                             "Profile path '{}' exists but is a directory, skipping (use allow/read/write for directories)",
@@ -283,7 +516,7 @@ impl CapabilitySet {
                         tracing::warn!("Profile path '{}' not found, skipping", path.display());
                     }
                 } else if path.exists() && path.is_dir() {
-                    caps.add_fs(FsCapability::new_dir(path, access)?);
+                    caps.push((index, FsCapability::new_dir(path, access)?));
                 } else if path.exists() {
                     tracing::warn!(
                         "Profile path '{}' exists but is not a directory, skipping",
@@ -293,54 +526,70 @@ impl CapabilitySet {
                     tracing::warn!("Profile path '{}' not found, skipping", path.display());
                 }
             }
-            Ok(())
-        }
-
-        // Process profile directory permissions
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.allow,
-            workdir,
-            FsAccess::ReadWrite,
-            false,
-        )?;
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.read,
-            workdir,
-            FsAccess::Read,
-            false,
-        )?;
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.write,
-            workdir,
-            FsAccess::Write,
-            false,
-        )?;
-
-        // Process profile file permissions
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.allow_file,
-            workdir,
-            FsAccess::ReadWrite,
-            true,
-        )?;
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.read_file,
-            workdir,
-            FsAccess::Read,
-            true,
-        )?;
-        process_profile_paths(
-            &mut caps,
-            &profile.filesystem.write_file,
-            workdir,
-            FsAccess::Write,
-            true,
-        )?;
+            Ok(caps)
+        }
+
+        // Process profile directory and file permissions:
+        // (key, paths, access, is_file, noexec)
+        let filesystem = &profile.filesystem;
+        let entries = [
+            (
+                "allow",
+                &filesystem.allow,
+                FsAccess::ReadWrite,
+                false,
+                false,
+            ),
+            ("read", &filesystem.read, FsAccess::Read, false, false),
+            ("write", &filesystem.write, FsAccess::Write, false, false),
+            (
+                "read_noexec",
+                &filesystem.read_noexec,
+                FsAccess::Read,
+                false,
+                true,
+            ),
+            (
+                "exec_dir",
+                &filesystem.exec_dir,
+                FsAccess::Read,
+                false,
+                false,
+            ),
+            (
+                "allow_file",
+                &filesystem.allow_file,
+                FsAccess::ReadWrite,
+                true,
+                false,
+            ),
+            (
+                "read_file",
+                &filesystem.read_file,
+                FsAccess::Read,
+                true,
+                false,
+            ),
+            (
+                "write_file",
+                &filesystem.write_file,
+                FsAccess::Write,
+                true,
+                false,
+            ),
+        ];
+        for (key, paths, access, is_file, noexec) in entries {
+            for (index, mut cap) in process_profile_paths(paths, workdir, tmpdir, access, is_file)?
+            {
+                cap.noexec = noexec;
+                cap.source = CapabilitySource::Profile {
+                    name: profile.meta.name.clone(),
+                    file: profile.origin.file.clone(),
+                    line: profile.origin.line(key, index),
+                };
+                caps.add_fs(cap);
+            }
+        }
 
         // Merge CLI overrides (extend the profile)
         for path in &args.allow {
@@ -358,6 +607,16 @@ impl CapabilitySet {
             caps.add_fs(cap);
         }
 
+        for path in &args.read_noexec {
+            let cap = FsCapability::new_dir(path.clone(), FsAccess::Read)?.without_exec();
+            caps.add_fs(cap);
+        }
+
+        for path in &args.exec_dir {
+            let cap = FsCapability::new_dir(path.clone(), FsAccess::Read)?;
+            caps.add_fs(cap);
+        }
+
         for path in &args.allow_file {
             let cap = FsCapability::new_file(path.clone(), FsAccess::ReadWrite)?;
             caps.add_fs(cap);
@@ -374,12 +633,54 @@ impl CapabilitySet {
         }
 
         // Network: profile OR CLI flag can block network (network allowed by default)
-        caps.net_block = profile.network.block || args.net_block;
+        caps.net_block = profile.network.block || args.net_block || args.require_net_block;
+        caps.net_block_required = args.require_net_block
+            || (profile.network.block && profile.network.enforcement == NetEnforcement::Strict);
+        caps.net_mode = args.net.unwrap_or_default();
+
+        // Devices the profile may issue ioctls on
+        for path_str in &profile.devices.ioctl {
+            caps.add_ioctl_device(&profile::expand_vars(path_str, workdir, tmpdir));
+        }
 
-        // Process command allow/block lists from CLI
-        // Profile support for commands will be added later
-        caps.allowed_commands = args.allow_command.clone();
-        caps.blocked_commands = args.block_command.clone();
+        // Executable allowlist: nothing else may run
+        if let Some(programs) = &profile.commands.allow_only {
+            caps.exec_allowlist = Some(Vec::new());
+            for program in programs {
+                caps.add_executable(&profile::expand_vars(program, workdir, tmpdir));
+            }
+        }
+
+        // IPC scoping: on unless the profile's [ipc] opts out
+        caps.scopes = IpcScope::ALL
+            .into_iter()
+            .filter(|scope| match scope {
+                IpcScope::AbstractUnixSocket => !profile.ipc.allow_abstract_unix_sockets,
+                IpcScope::Signal => !profile.ipc.allow_signals,
+            })
+            .collect();
+
+        // Command allow/block lists: profile [commands] extended by CLI flags
+        caps.allowed_commands = profile
+            .commands
+            .allow
+            .iter()
+            .chain(&args.allow_command)
+            .cloned()
+            .collect();
+        caps.blocked_commands = profile
+            .commands
+            .block
+            .iter()
+            .chain(&args.block_command)
+            .cloned()
+            .collect();
+        caps.command_rules = profile.commands.rules.clone();
+        caps.exec_guard = profile.commands.guard || args.exec_guard;
+
+        // Resource limits: CLI flags override the profile's [limits]
+        caps.limits = ResourceLimits::from(&args.limits).or(&profile.limits);
+        caps.isolate = args.isolate;
 
         caps.deduplicate();
         Ok(caps)
@@ -404,11 +705,35 @@ impl CapabilitySet {
         }
 
         lines.push("Network:".to_string());
-        if self.net_block {
+        if self.net_block_degraded {
+            lines.push("  outbound: allowed (--net-block not enforced by kernel)".to_string());
+        } else if self.net_block {
             lines.push("  outbound: blocked".to_string());
         } else {
             lines.push("  outbound: allowed".to_string());
         }
+        if self.net_mode != NetMode::Host {
+            lines.push(format!("  namespace: {}", self.net_mode));
+        }
+
+        if !self.scopes.is_empty() {
+            let scopes: Vec<String> = self.scopes.iter().map(|s| s.to_string()).collect();
+            lines.push(format!("IPC scoped to sandbox: {}", scopes.join(", ")));
+        }
+
+        if let Some(allowlist) = &self.exec_allowlist {
+            lines.push("Executables (only these):".to_string());
+            for program in allowlist {
+                lines.push(format!("  {}", program.display()));
+            }
+        }
+
+        if !self.ioctl_devices.is_empty() {
+            lines.push("Devices (ioctl):".to_string());
+            for dev in &self.ioctl_devices {
+                lines.push(format!("  {}", dev.display()));
+            }
+        }
 
         if lines.is_empty() {
             lines.push("(no capabilities granted)".to_string());
@@ -418,6 +743,109 @@ impl CapabilitySet {
     }
 }
 
+/// The programs the kernel runs `path` with: the dynamic linker of an ELF
+/// binary (`PT_INTERP`), or the interpreter on a script's `#!` line. For
+/// `#!/usr/bin/env <name>`, `<name>` runs too.
+fn interpreters(path: &Path) -> Vec<PathBuf> {
+    interpreter_line(path).unwrap_or_default()
+}
+
+fn interpreter_line(path: &Path) -> Option<Vec<PathBuf>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut header = [0u8; 64];
+    let len = file.read(&mut header).ok()?;
+    let header = &header[..len];
+
+    if let Some(line) = header.strip_prefix(b"#!") {
+        let line = line.split(|&b| b == b'\n').next()?;
+        let line = String::from_utf8_lossy(line);
+        let mut words = line.split_whitespace();
+        let interpreter = PathBuf::from(words.next()?);
+        let mut programs = vec![interpreter.clone()];
+        if interpreter.file_name().is_some_and(|name| name == "env") {
+            programs.extend(words.find(|word| !word.starts_with('-')).map(PathBuf::from));
+        }
+        return Some(programs);
+    }
+
+    if len < 64 || &header[..4] != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = header[4] == 2;
+    let little_endian = header[5] == 1;
+    let u16_at = |b: &[u8], at: usize| {
+        let bytes = [b[at], b[at + 1]];
+        if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let u32_at = |b: &[u8], at: usize| {
+        let bytes = [b[at], b[at + 1], b[at + 2], b[at + 3]];
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+    let u64_at = |b: &[u8], at: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&b[at..at + 8]);
+        if little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        }
+    };
+
+    // e_phoff, e_phentsize and e_phnum, then p_offset and p_filesz
+    let (phoff, phentsize, phnum) = if is_64 {
+        (
+            u64_at(header, 0x20),
+            u16_at(header, 0x36),
+            u16_at(header, 0x38),
+        )
+    } else {
+        (
+            u64::from(u32_at(header, 0x1c)),
+            u16_at(header, 0x2a),
+            u16_at(header, 0x2c),
+        )
+    };
+    const PT_INTERP: u32 = 3;
+    if usize::from(phentsize) < if is_64 { 0x28 } else { 0x14 } {
+        return None;
+    }
+    let mut phdr = vec![0u8; usize::from(phentsize)];
+    for i in 0..u64::from(phnum) {
+        file.seek(SeekFrom::Start(phoff + i * u64::from(phentsize)))
+            .ok()?;
+        file.read_exact(&mut phdr).ok()?;
+        if u32_at(&phdr, 0) != PT_INTERP {
+            continue;
+        }
+        let (offset, size) = if is_64 {
+            (u64_at(&phdr, 0x08), u64_at(&phdr, 0x20))
+        } else {
+            (
+                u64::from(u32_at(&phdr, 0x04)),
+                u64::from(u32_at(&phdr, 0x10)),
+            )
+        };
+        let mut interp = vec![0u8; usize::try_from(size).ok()?.min(4096)];
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(&mut interp).ok()?;
+        let end = interp.iter().position(|&b| b == 0).unwrap_or(interp.len());
+        return Some(vec![PathBuf::from(
+            String::from_utf8_lossy(&interp[..end]).into_owned(),
+        )]);
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -512,6 +940,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_ioctl_device() {
+        let dir = tempdir().unwrap();
+        let mut caps = CapabilitySet::new();
+
+        caps.add_ioctl_device(dir.path());
+        caps.add_ioctl_device(dir.path());
+        caps.add_ioctl_device(&dir.path().join("missing"));
+
+        assert_eq!(caps.ioctl_devices, vec![dir.path().canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_add_executable() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("tool");
+        std::fs::write(&script, "#!/usr/bin/env -S sh -e\necho hi\n").unwrap();
+        assert_eq!(
+            interpreters(&script),
+            vec![PathBuf::from("/usr/bin/env"), PathBuf::from("sh")]
+        );
+
+        let mut caps = CapabilitySet::new();
+        caps.add_executable(&script);
+        caps.add_executable(&dir.path().join("missing"));
+        let allowlist = caps.exec_allowlist.unwrap();
+        assert_eq!(allowlist[0], script.canonicalize().unwrap());
+        // The interpreters are found and allowed along with the script
+        if Path::new("/usr/bin/env").exists() {
+            assert!(allowlist.contains(&Path::new("/usr/bin/env").canonicalize().unwrap()));
+        }
+
+        // A dynamically linked binary brings its dynamic linker
+        let exe = std::env::current_exe().unwrap();
+        for interpreter in interpreters(&exe) {
+            assert!(interpreter.exists(), "{}", interpreter.display());
+        }
+    }
+
     #[test]
     fn test_capability_set_from_args() {
         let dir = tempdir().unwrap();
@@ -519,21 +986,7 @@ mod tests {
 
         let args = SandboxArgs {
             allow: vec![path.clone()],
-            read: vec![],
-            write: vec![],
-            allow_file: vec![],
-            read_file: vec![],
-            write_file: vec![],
-            net_block: false,
-            allow_command: vec![],
-            block_command: vec![],
-            secrets: None,
-            profile: None,
-            allow_cwd: false,
-            workdir: None,
-            config: None,
-            verbose: 0,
-            dry_run: false,
+            ..Default::default()
         };
 
         let caps = CapabilitySet::from_args(&args).unwrap();
@@ -542,6 +995,35 @@ mod tests {
         assert!(!caps.net_block); // network allowed by default
     }
 
+    #[test]
+    fn test_deduplicate_keeps_exec() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let noexec = |access| {
+            FsCapability::new_dir(path.clone(), access)
+                .unwrap()
+                .without_exec()
+        };
+
+        // Executable if any grant for the path is
+        let mut caps = CapabilitySet::new();
+        caps.add_fs(noexec(FsAccess::ReadWrite));
+        caps.add_fs(FsCapability::new_dir(path.clone(), FsAccess::Read).unwrap());
+        caps.deduplicate();
+        assert_eq!(caps.fs.len(), 1);
+        assert_eq!(caps.fs[0].access, FsAccess::ReadWrite);
+        assert!(caps.fs[0].executable());
+
+        // Write-only grants never make a path executable
+        let mut caps = CapabilitySet::new();
+        caps.add_fs(noexec(FsAccess::Read));
+        caps.add_fs(FsCapability::new_dir(path.clone(), FsAccess::Write).unwrap());
+        caps.deduplicate();
+        assert_eq!(caps.fs.len(), 1);
+        assert!(caps.fs[0].noexec);
+        assert!(!caps.fs[0].executable());
+    }
+
     #[test]
     fn test_capability_set_with_files() {
         let dir = tempdir().unwrap();
@@ -550,21 +1032,8 @@ mod tests {
 
         let args = SandboxArgs {
             allow: vec![dir.path().to_path_buf()],
-            read: vec![],
-            write: vec![],
-            allow_file: vec![],
-            read_file: vec![],
             write_file: vec![file_path],
-            net_block: false,
-            allow_command: vec![],
-            block_command: vec![],
-            secrets: None,
-            profile: None,
-            allow_cwd: false,
-            workdir: None,
-            config: None,
-            verbose: 0,
-            dry_run: false,
+            ..Default::default()
         };
 
         let caps = CapabilitySet::from_args(&args).unwrap();
@@ -575,6 +1044,27 @@ mod tests {
         assert!(caps.fs[1].is_file);
     }
 
+    #[test]
+    fn test_capability_set_profile_commands_merged_with_cli() {
+        let dir = tempdir().unwrap();
+
+        let mut prof = Profile::default();
+        prof.commands.allow = vec!["cp".to_string()];
+        prof.commands.block = vec!["curl".to_string()];
+        prof.ipc.allow_signals = true;
+
+        let args = SandboxArgs {
+            allow_command: vec!["rm".to_string()],
+            block_command: vec!["wget".to_string()],
+            ..Default::default()
+        };
+
+        let caps = CapabilitySet::from_profile(&prof, dir.path(), None, &args).unwrap();
+        assert_eq!(caps.allowed_commands, vec!["cp", "rm"]);
+        assert_eq!(caps.blocked_commands, vec!["curl", "wget"]);
+        assert_eq!(caps.scopes, vec![IpcScope::AbstractUnixSocket]);
+    }
+
     #[test]
     fn test_capability_set_network_blocked() {
         let dir = tempdir().unwrap();
@@ -582,21 +1072,8 @@ mod tests {
 
         let args = SandboxArgs {
             allow: vec![path.clone()],
-            read: vec![],
-            write: vec![],
-            allow_file: vec![],
-            read_file: vec![],
-            write_file: vec![],
             net_block: true,
-            allow_command: vec![],
-            block_command: vec![],
-            secrets: None,
-            profile: None,
-            allow_cwd: false,
-            workdir: None,
-            config: None,
-            verbose: 0,
-            dry_run: false,
+            ..Default::default()
         };
 
         let caps = CapabilitySet::from_args(&args).unwrap();
@@ -0,0 +1,399 @@
+//! Per-session cgroup for session-wide resource limits (Linux, cgroup v2)
+//!
+//! When `[limits]` sets `memory`, `pids` or `cpus`, nono creates a cgroup
+//! `nono-<pid>` next to its own cgroup and moves the command into it, so the
+//! limits cover every process the command spawns. This needs a cgroup v2
+//! subtree delegated to the user (e.g. systemd's `user@.service`, or a
+//! service with `Delegate=yes`).
+//!
+//! Everything under the cgroup mount is opened BEFORE the sandbox is applied:
+//! the command joins by writing to the pre-opened `cgroup.procs`, usage is read
+//! from pre-opened stat files, processes left behind are killed through the
+//! pre-opened `cgroup.kill`, and the cgroup is removed by a small helper
+//! process forked before the sandbox, once nono exits.
+
+// Only session limits on Linux use most of this module
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::error::{NonoError, Result};
+use crate::limits::ResourceLimits;
+use std::fs::File;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+/// `cpu.max` period in microseconds (the kernel default)
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+/// Resource usage of a finished session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Peak memory in bytes (`memory.peak`, Linux 5.19+)
+    pub peak_memory: Option<u64>,
+    /// Total CPU time
+    pub cpu_time: Option<Duration>,
+    /// CPU time in user mode
+    pub user_time: Option<Duration>,
+    /// CPU time in kernel mode
+    pub system_time: Option<Duration>,
+}
+
+/// A cgroup created for one sandboxed session.
+pub struct SessionCgroup {
+    path: PathBuf,
+    /// `cgroup.procs`, opened for writing before the sandbox is applied
+    procs: File,
+    cpu_stat: File,
+    memory_peak: Option<File>,
+    /// `cgroup.events`, which reports whether any process is left
+    events: File,
+    /// `cgroup.kill` (Linux 5.14+), opened for writing before the sandbox
+    kill: Option<File>,
+    /// Write end of the cleanup helper's pipe; closing it (when nono exits)
+    /// lets the helper remove the cgroup
+    _cleanup: OwnedFd,
+}
+
+impl SessionCgroup {
+    /// Create the session cgroup and apply the session-wide limits.
+    ///
+    /// Must be called BEFORE the sandbox is applied.
+    #[cfg(target_os = "linux")]
+    pub fn create(limits: &ResourceLimits) -> Result<Self> {
+        let mount = find_cgroup2_mount()?;
+        let own = std::fs::read_to_string("/proc/self/cgroup")
+            .ok()
+            .and_then(|content| parse_proc_cgroup(&content))
+            .ok_or_else(|| {
+                cgroup_error("cannot determine nono's cgroup v2 path from /proc/self/cgroup")
+            })?;
+        let own = match own.trim_start_matches('/') {
+            "" => mount.clone(),
+            relative => mount.join(relative),
+        };
+
+        // A cgroup with processes cannot pass controllers on to children, so
+        // the session cgroup is created as a sibling of nono's own cgroup
+        let parent = if own == mount {
+            own
+        } else {
+            own.parent().map(Path::to_path_buf).unwrap_or(own)
+        };
+
+        enable_controllers(&parent, &required_controllers(limits))?;
+        // Best effort, so the summary can report peak memory
+        let _ = enable_controllers(&parent, &["memory"]);
+
+        let path = parent.join(format!("nono-{}", std::process::id()));
+        std::fs::create_dir(&path).map_err(|e| {
+            cgroup_error(&format!(
+                "no delegated cgroup v2 subtree: cannot create {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        match Self::configure(path.clone(), limits) {
+            Ok(cgroup) => Ok(cgroup),
+            Err(e) => {
+                let _ = std::fs::remove_dir(&path);
+                Err(e)
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn create(_limits: &ResourceLimits) -> Result<Self> {
+        Err(cgroup_error(
+            "session limits (memory, pids, cpus) need cgroup v2 and are only supported on Linux",
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn configure(path: PathBuf, limits: &ResourceLimits) -> Result<Self> {
+        if let Some(memory) = limits.memory {
+            write_control(&path, "memory.max", &memory.to_string())?;
+        }
+        if let Some(pids) = limits.pids {
+            write_control(&path, "pids.max", &pids.to_string())?;
+        }
+        if let Some(cpus) = limits.cpus {
+            write_control(&path, "cpu.max", &cpu_max(cpus))?;
+        }
+
+        let procs = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"))
+            .map_err(|e| cgroup_error(&format!("cannot open cgroup.procs: {}", e)))?;
+        let cpu_stat = File::open(path.join("cpu.stat"))
+            .map_err(|e| cgroup_error(&format!("cannot open cpu.stat: {}", e)))?;
+        let memory_peak = File::open(path.join("memory.peak")).ok();
+        let events = File::open(path.join("cgroup.events"))
+            .map_err(|e| cgroup_error(&format!("cannot open cgroup.events: {}", e)))?;
+        let kill = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.kill"))
+            .ok();
+        let cleanup = spawn_cleanup(&path)?;
+
+        Ok(Self {
+            path,
+            procs,
+            cpu_stat,
+            memory_peak,
+            events,
+            kill,
+            _cleanup: cleanup,
+        })
+    }
+
+    /// Path of the cgroup
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Descriptor a forked child writes `0` to in order to join the cgroup.
+    pub fn procs_fd(&self) -> RawFd {
+        self.procs.as_raw_fd()
+    }
+
+    /// Whether any process is still in the cgroup.
+    pub fn is_populated(&self) -> bool {
+        read_from_start(&self.events).is_some_and(|events| events_populated(&events))
+    }
+
+    /// SIGKILL every process in the cgroup, including those that left the
+    /// command's process group.
+    pub fn kill(&self) {
+        use std::io::Write;
+
+        let result = match self.kill.as_ref() {
+            Some(mut file) => file.write_all(b"1"),
+            None => Err(std::io::Error::other("cgroup.kill needs Linux 5.14+")),
+        };
+        if let Err(e) = result {
+            warn!("Cannot kill processes left in the session cgroup: {}", e);
+        }
+    }
+
+    /// Read the session's resource usage so far.
+    pub fn usage(&self) -> ResourceUsage {
+        let stat = read_from_start(&self.cpu_stat).unwrap_or_default();
+        let usec = |key| cpu_stat_field(&stat, key).map(Duration::from_micros);
+        ResourceUsage {
+            peak_memory: self
+                .memory_peak
+                .as_ref()
+                .and_then(read_from_start)
+                .and_then(|s| s.trim().parse().ok()),
+            cpu_time: usec("usage_usec"),
+            user_time: usec("user_usec"),
+            system_time: usec("system_usec"),
+        }
+    }
+}
+
+/// Controllers that must be enabled for the given limits
+fn required_controllers(limits: &ResourceLimits) -> Vec<&'static str> {
+    let mut controllers = Vec::new();
+    if limits.memory.is_some() {
+        controllers.push("memory");
+    }
+    if limits.pids.is_some() {
+        controllers.push("pids");
+    }
+    if limits.cpus.is_some() {
+        controllers.push("cpu");
+    }
+    controllers
+}
+
+/// Make sure `controllers` are enabled for the children of `parent`.
+#[cfg(target_os = "linux")]
+fn enable_controllers(parent: &Path, controllers: &[&str]) -> Result<()> {
+    let enabled = std::fs::read_to_string(parent.join("cgroup.subtree_control"))
+        .map_err(|e| cgroup_error(&format!("cannot read {}: {}", parent.display(), e)))?;
+    let available = std::fs::read_to_string(parent.join("cgroup.controllers")).unwrap_or_default();
+
+    for controller in controllers {
+        if enabled.split_whitespace().any(|c| c == *controller) {
+            continue;
+        }
+        if !available.split_whitespace().any(|c| c == *controller) {
+            return Err(cgroup_error(&format!(
+                "the '{}' controller is not available in {}",
+                controller,
+                parent.display()
+            )));
+        }
+        write_control(
+            parent,
+            "cgroup.subtree_control",
+            &format!("+{}", controller),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn write_control(dir: &Path, file: &str, value: &str) -> Result<()> {
+    std::fs::write(dir.join(file), value).map_err(|e| {
+        cgroup_error(&format!(
+            "cannot write '{}' to {}: {}",
+            value,
+            dir.join(file).display(),
+            e
+        ))
+    })
+}
+
+/// Find where the cgroup v2 hierarchy is mounted (usually /sys/fs/cgroup).
+#[cfg(target_os = "linux")]
+fn find_cgroup2_mount() -> Result<PathBuf> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")
+        .map_err(|e| cgroup_error(&format!("cannot read /proc/self/mountinfo: {}", e)))?;
+    parse_cgroup2_mount(&mountinfo)
+        .ok_or_else(|| cgroup_error("no cgroup v2 filesystem is mounted"))
+}
+
+/// Mount point of the first cgroup2 filesystem in mountinfo
+fn parse_cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        // Optional fields end at " - ", followed by the filesystem type
+        let (mount, fs) = line.split_once(" - ")?;
+        if fs.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        mount.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// cgroup v2 path from /proc/self/cgroup (the `0::` line)
+fn parse_proc_cgroup(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().to_string())
+}
+
+/// `cpu.max` value for a bandwidth in CPUs
+fn cpu_max(cpus: f64) -> String {
+    let quota = (cpus * CPU_PERIOD_USEC as f64).round() as u64;
+    format!("{} {}", quota, CPU_PERIOD_USEC)
+}
+
+fn cpu_stat_field(stat: &str, key: &str) -> Option<u64> {
+    stat.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        if name == key {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// The `populated` field of `cgroup.events`
+fn events_populated(events: &str) -> bool {
+    events
+        .lines()
+        .any(|line| line.split_whitespace().eq(["populated", "1"]))
+}
+
+/// Re-read a cgroup file from the start through an already open descriptor
+fn read_from_start(mut file: &File) -> Option<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    file.seek(SeekFrom::Start(0)).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Fork a helper that removes the cgroup once nono exits.
+///
+/// nono cannot remove the cgroup itself: once sandboxed, it has no write
+/// access to the cgroup filesystem.
+#[cfg(target_os = "linux")]
+fn spawn_cleanup(path: &Path) -> Result<OwnedFd> {
+    use nix::errno::Errno;
+    use nix::libc;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// How long to wait for the last processes to leave the cgroup
+    const RMDIR_ATTEMPTS: u32 = 50;
+    const RMDIR_RETRY_USEC: libc::c_uint = 100_000;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| cgroup_error("cgroup path contains a NUL byte"))?;
+
+    // Secrets may already have started keyring threads, so the cleanup only
+    // uses async-signal-safe functions (rmdir, usleep)
+    crate::cleanup::spawn_on_exit(move || {
+        for _ in 0..RMDIR_ATTEMPTS {
+            if unsafe { libc::rmdir(path.as_ptr()) } == 0 || Errno::last() != Errno::EBUSY {
+                break;
+            }
+            unsafe { libc::usleep(RMDIR_RETRY_USEC) };
+        }
+    })
+    .map_err(|e| cgroup_error(&format!("cannot start cleanup helper: {}", e)))
+}
+
+fn cgroup_error(message: &str) -> NonoError {
+    NonoError::SandboxInit(format!("Cannot set up session cgroup: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup_paths() {
+        let mountinfo = "\
+25 30 0:23 / /sys rw,nosuid shared:7 - sysfs sysfs rw
+32 25 0:27 / /sys/fs/cgroup rw,nosuid,nodev shared:9 - cgroup2 cgroup2 rw,nsdelegate
+";
+        assert_eq!(
+            parse_cgroup2_mount(mountinfo),
+            Some(PathBuf::from("/sys/fs/cgroup"))
+        );
+        assert_eq!(
+            parse_cgroup2_mount("25 30 0:23 / /sys rw - sysfs sysfs rw"),
+            None
+        );
+
+        let proc_cgroup = "4:memory:/legacy\n0::/user.slice/user-1000.slice/user@1000.service/app.slice/term.scope\n";
+        assert_eq!(
+            parse_proc_cgroup(proc_cgroup).as_deref(),
+            Some("/user.slice/user-1000.slice/user@1000.service/app.slice/term.scope")
+        );
+        assert_eq!(parse_proc_cgroup("4:memory:/legacy\n"), None);
+    }
+
+    #[test]
+    fn test_cpu_settings() {
+        assert_eq!(cpu_max(1.5), "150000 100000");
+        assert_eq!(cpu_max(0.25), "25000 100000");
+
+        let stat = "usage_usec 2500000\nuser_usec 2000000\nsystem_usec 500000\nnr_periods 0\n";
+        assert_eq!(cpu_stat_field(stat, "usage_usec"), Some(2_500_000));
+        assert_eq!(cpu_stat_field(stat, "system_usec"), Some(500_000));
+        assert_eq!(cpu_stat_field(stat, "usage"), None);
+
+        let limits = ResourceLimits {
+            memory: Some(1 << 30),
+            cpus: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(required_controllers(&limits), vec!["memory", "cpu"]);
+    }
+
+    #[test]
+    fn test_events_populated() {
+        assert!(events_populated("populated 1\nfrozen 0\n"));
+        assert!(!events_populated("populated 0\nfrozen 0\n"));
+        assert!(!events_populated(""));
+    }
+}
@@ -0,0 +1,187 @@
+//! Cleanup that runs after nono exits
+//!
+//! Once the sandbox is applied, nono can no longer remove what it set up
+//! outside its own grants: the session cgroup, or the private temp directory's
+//! entry in the system temp directory. Instead, a helper process is forked
+//! BEFORE the sandbox is applied. It detaches from nono, blocks until every
+//! copy of a pipe's write end is closed and nono's process has exited, and
+//! then runs the cleanup. In direct exec mode, nono's process is the command,
+//! which never holds the pipe: it cannot run the cleanup early by closing it.
+
+use nix::errno::Errno;
+use nix::fcntl::OFlag;
+use nix::libc;
+use nix::sys::wait::waitpid;
+use nix::unistd::{fork, pipe2, ForkResult};
+#[cfg(target_os = "linux")]
+use std::os::fd::FromRawFd;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+
+/// Fork a detached helper that runs `action` once the returned descriptor is
+/// closed and nono's process has exited, even if it was replaced by the
+/// sandboxed command (direct exec mode).
+///
+/// The descriptor is close-on-exec, so it stays out of the sandboxed command.
+/// The helper is double-forked, so it is never one of nono's children.
+///
+/// `action` runs in a forked child: unless nono is still single-threaded when
+/// this is called, it must only use async-signal-safe functions.
+pub fn spawn_on_exit<F: FnOnce()>(action: F) -> nix::Result<OwnedFd> {
+    let (read_end, write_end) = pipe2(OFlag::O_CLOEXEC)?;
+    let read_fd = read_end.as_raw_fd();
+    let max_fd = unsafe { libc::sysconf(libc::_SC_OPEN_MAX) }.clamp(1024, 65536) as i32;
+    let watch = ExitWatch::open()?;
+    let watch_fd = watch.raw_fd();
+
+    // SAFETY: up to `action`, the children only call async-signal-safe
+    // functions (fork, setsid, close, read, poll, kevent, _exit); `action` is
+    // covered by the contract above
+    match unsafe { fork() }? {
+        ForkResult::Child => unsafe {
+            libc::setsid();
+            if libc::fork() != 0 {
+                libc::_exit(0);
+            }
+            for fd in 0..max_fd {
+                if fd != read_fd && Some(fd) != watch_fd {
+                    libc::close(fd);
+                }
+            }
+            let watch = watch.arm();
+
+            // Returns 0 once every copy of the write end is closed
+            let mut byte = 0u8;
+            while libc::read(read_fd, (&mut byte as *mut u8).cast(), 1) != 0 {
+                if Errno::last() != Errno::EINTR {
+                    break;
+                }
+            }
+            watch.wait();
+
+            action();
+            libc::_exit(0)
+        },
+        ForkResult::Parent { child } => {
+            drop(read_end);
+            drop(watch);
+            let _ = waitpid(child, None);
+            Ok(write_end)
+        }
+    }
+}
+
+/// Waits for nono's process to exit.
+///
+/// On Linux, a pidfd opened in nono before the fork, so a reused pid cannot
+/// be mistaken for nono. pidfd_open needs Linux 5.3, older than any kernel
+/// with Landlock.
+#[cfg(target_os = "linux")]
+struct ExitWatch(Option<OwnedFd>);
+
+#[cfg(target_os = "linux")]
+impl ExitWatch {
+    fn open() -> nix::Result<Self> {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, libc::getpid(), 0) };
+        if fd < 0 {
+            return Err(Errno::last());
+        }
+        Ok(Self(Some(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })))
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        self.0.as_ref().map(AsRawFd::as_raw_fd)
+    }
+
+    /// Start watching, in the helper. Async-signal-safe.
+    fn arm(self) -> Self {
+        self
+    }
+
+    /// Block until nono's process has exited. Async-signal-safe.
+    fn wait(self) {
+        let Some(fd) = self.0 else {
+            return;
+        };
+        let mut pollfd = libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        while unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 && Errno::last() == Errno::EINTR {}
+    }
+}
+
+/// Waits for nono's process to exit.
+///
+/// On macOS, a kqueue watching nono's pid. kqueues are not inherited across
+/// fork, so the helper registers it when it starts; if nono has already
+/// exited by then, the cleanup runs right away.
+#[cfg(target_os = "macos")]
+struct ExitWatch {
+    pid: libc::pid_t,
+    kqueue: RawFd,
+}
+
+#[cfg(target_os = "macos")]
+impl ExitWatch {
+    fn open() -> nix::Result<Self> {
+        Ok(Self {
+            pid: unsafe { libc::getpid() },
+            kqueue: -1,
+        })
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        None
+    }
+
+    /// Start watching, in the helper. Async-signal-safe.
+    fn arm(mut self) -> Self {
+        unsafe {
+            self.kqueue = libc::kqueue();
+            if self.kqueue < 0 {
+                return self;
+            }
+            let mut change: libc::kevent = std::mem::zeroed();
+            change.ident = self.pid as libc::uintptr_t;
+            change.filter = libc::EVFILT_PROC;
+            change.flags = libc::EV_ADD | libc::EV_ONESHOT;
+            change.fflags = libc::NOTE_EXIT;
+            if libc::kevent(
+                self.kqueue,
+                &change,
+                1,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null(),
+            ) < 0
+            {
+                // ESRCH: nono is already gone
+                libc::close(self.kqueue);
+                self.kqueue = -1;
+            }
+        }
+        self
+    }
+
+    /// Block until nono's process has exited. Async-signal-safe.
+    fn wait(self) {
+        if self.kqueue < 0 {
+            return;
+        }
+        unsafe {
+            let mut event: libc::kevent = std::mem::zeroed();
+            while libc::kevent(
+                self.kqueue,
+                std::ptr::null(),
+                0,
+                &mut event,
+                1,
+                std::ptr::null(),
+            ) < 0
+                && Errno::last() == Errno::EINTR
+            {}
+            libc::close(self.kqueue);
+        }
+    }
+}
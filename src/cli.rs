@@ -1,5 +1,8 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use crate::capability::NetMode;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// nono - The opposite of YOLO
 ///
@@ -19,7 +22,7 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Trace a command to discover required filesystem paths (Linux only)
+    /// Trace a command to discover required filesystem paths and network destinations (Linux only)
     #[command(trailing_var_arg = true)]
     #[command(after_help = "EXAMPLES:
     # Discover paths needed by a command
@@ -33,6 +36,12 @@ pub enum Commands {
 
     # Limit trace duration
     nono learn --timeout 30 -- my-app
+
+    # Merge discovered paths into a user profile (shows a diff first)
+    nono learn --profile my-profile --update -- my-app
+
+    # Run inside the profile's sandbox and report only what it denied
+    nono learn --profile my-profile --sandboxed -- my-app
 ")]
     Learn(Box<LearnArgs>),
 
@@ -68,6 +77,9 @@ pub enum Commands {
 
     # Load specific secrets from keystore (comma-separated)
     nono run --allow . --secrets openai_api_key,anthropic_api_key -- claude
+
+    # Stop an unattended run after an hour
+    nono run --profile claude-code --timeout 1h -- claude -p \"fix the tests\"
 ")]
     Run(Box<RunArgs>),
 
@@ -84,6 +96,16 @@ pub enum Commands {
 ")]
     Shell(Box<ShellArgs>),
 
+    /// Play back a session recorded with --record
+    #[command(after_help = "EXAMPLES:
+    # Replay at original speed
+    nono replay session.cast
+
+    # Replay an overnight run quickly, skipping long pauses
+    nono replay session.cast --speed 4 --idle-limit 2
+")]
+    Replay(ReplayArgs),
+
     /// Check why a path or network operation would be allowed or denied
     #[command(after_help = "EXAMPLES:
     # Check if ~/.ssh is readable (sensitive path check)
@@ -100,6 +122,12 @@ pub enum Commands {
 
     # Inside a sandbox, query own capabilities
     nono why --self --path /tmp --op write --json
+
+    # Show every rule considered and which one decided
+    nono why --path ~/.ssh/id_rsa --profile claude-code --explain
+
+    # Check many paths at once, one JSON query per line
+    printf '%s\\n' '{\"path\": \"~/.aws\"}' '{\"host\": \"github.com\"}' | nono why --batch --self
 ")]
     Why(Box<WhyArgs>),
 
@@ -120,7 +148,7 @@ pub enum Commands {
     Setup(SetupArgs),
 }
 
-#[derive(Parser, Debug, Clone)]
+#[derive(Parser, Debug, Clone, Default)]
 pub struct SandboxArgs {
     // === Directory permissions (recursive) ===
     /// Directories to allow read+write access (recursive).
@@ -153,11 +181,31 @@ pub struct SandboxArgs {
     #[arg(long, value_name = "FILE")]
     pub write_file: Vec<PathBuf>,
 
+    // === Execute permission ===
+    /// Directories to allow read-only access without executing anything in them (recursive)
+    #[arg(long, value_name = "DIR")]
+    pub read_noexec: Vec<PathBuf>,
+
+    /// Directories whose programs may be executed, with read access (recursive).
+    /// Re-enables execution below a --read-noexec or `noexec` working directory.
+    #[arg(long, value_name = "DIR")]
+    pub exec_dir: Vec<PathBuf>,
+
     /// Block network access (network allowed by default; use this flag to block)
     /// Note: Per-host filtering not supported by OS sandbox; this is on/off only
     #[arg(long)]
     pub net_block: bool,
 
+    /// Block network access and refuse to run if the kernel can't enforce
+    /// it (implies --net-block; Linux needs Landlock ABI V4+)
+    #[arg(long)]
+    pub require_net_block: bool,
+
+    /// Run in a network namespace of its own: "none" (no interfaces) or
+    /// "loopback" (only localhost). Linux only, needs user namespaces
+    #[arg(long, value_name = "MODE")]
+    pub net: Option<NetMode>,
+
     // === Command blocking ===
     /// Allow a normally-blocked dangerous command (use with caution).
     /// By default, destructive commands like rm, dd, chmod are blocked.
@@ -168,6 +216,15 @@ pub struct SandboxArgs {
     #[arg(long, value_name = "CMD")]
     pub block_command: Vec<String>,
 
+    /// Also check every program the command runs in turn against the
+    /// blocklist, not just the command itself (Linux only)
+    #[arg(long)]
+    pub exec_guard: bool,
+
+    // === Resource limits ===
+    #[command(flatten)]
+    pub limits: LimitArgs,
+
     // === Secrets options ===
     /// Load secrets from system keystore and inject as environment variables.
     /// Use with --profile to load secrets defined in the profile's [secrets] section,
@@ -190,6 +247,15 @@ pub struct SandboxArgs {
     #[arg(long, value_name = "DIR")]
     pub workdir: Option<PathBuf>,
 
+    /// Keep the session's private temp directory ($TMPDIR) after the command exits
+    #[arg(long)]
+    pub keep_tmp: bool,
+
+    /// Run in a minimal root holding only system paths and granted paths
+    /// (Linux: unprivileged user, mount and PID namespaces, under Landlock)
+    #[arg(long)]
+    pub isolate: bool,
+
     /// Configuration file path
     #[arg(long, short = 'c', value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -203,6 +269,48 @@ pub struct SandboxArgs {
     pub dry_run: bool,
 }
 
+/// Resource limit flags (override the profile's [limits] section)
+#[derive(Args, Debug, Clone, Default)]
+pub struct LimitArgs {
+    /// Maximum CPU time in seconds (RLIMIT_CPU)
+    #[arg(long, value_name = "SECS")]
+    pub max_cpu_time: Option<u64>,
+
+    /// Maximum virtual memory, e.g. 4G (RLIMIT_AS)
+    #[arg(long, value_name = "SIZE", value_parser = crate::limits::parse_size)]
+    pub max_address_space: Option<u64>,
+
+    /// Maximum number of open files (RLIMIT_NOFILE)
+    #[arg(long, value_name = "N")]
+    pub max_open_files: Option<u64>,
+
+    /// Maximum number of processes for your user, including ones outside
+    /// the sandbox (RLIMIT_NPROC)
+    #[arg(long, value_name = "N")]
+    pub max_processes: Option<u64>,
+
+    /// Maximum core dump size; 0 disables core dumps (RLIMIT_CORE)
+    #[arg(long, value_name = "SIZE", value_parser = crate::limits::parse_size)]
+    pub max_core_size: Option<u64>,
+
+    /// Maximum size of files the command writes, e.g. 1G (RLIMIT_FSIZE)
+    #[arg(long, value_name = "SIZE", value_parser = crate::limits::parse_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Maximum memory of the whole session, e.g. 2G (cgroup memory.max;
+    /// needs a delegated cgroup v2 subtree)
+    #[arg(long, value_name = "SIZE", value_parser = crate::limits::parse_size)]
+    pub max_memory: Option<u64>,
+
+    /// Maximum number of processes in the session (cgroup pids.max)
+    #[arg(long, value_name = "N")]
+    pub max_pids: Option<u64>,
+
+    /// Maximum CPU bandwidth in CPUs, e.g. 1.5 (cgroup cpu.max)
+    #[arg(long, value_name = "CPUS", value_parser = crate::limits::parse_cpus)]
+    pub max_cpus: Option<f64>,
+}
+
 #[derive(Parser, Debug)]
 pub struct RunArgs {
     #[command(flatten)]
@@ -214,8 +322,25 @@ pub struct RunArgs {
     #[arg(long)]
     pub no_diagnostics: bool,
 
-    /// Preserve TTY for interactive apps (e.g., Claude Code, vim, htop).
-    /// Without this flag, nono monitors output which can break interactive UIs.
+    /// Record everything the command prints, plus nono's diagnostics, to FILE
+    /// (asciicast v2 format; play back with `nono replay FILE`)
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Stop the command after DURATION of wall-clock time, e.g. 30m or 1h30m.
+    /// Its process group gets SIGTERM, and nono exits with code 124.
+    #[arg(long, value_name = "DURATION", value_parser = crate::limits::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Send SIGKILL to processes still running DURATION after SIGTERM, both
+    /// when --timeout fires and for processes left behind when the command
+    /// exits [default: 10s]
+    #[arg(long, value_name = "DURATION", value_parser = crate::limits::parse_duration)]
+    pub kill_after: Option<Duration>,
+
+    /// Exec directly into the command instead of monitoring it (no diagnostic footer).
+    /// Preserves the TTY for interactive apps (e.g., vim, htop) whose profile
+    /// does not set `interactive`, which already gets a proxied terminal.
     #[arg(long = "exec")]
     pub direct_exec: bool,
 
@@ -232,6 +357,30 @@ pub struct ShellArgs {
     /// Shell to execute (defaults to $SHELL or /bin/sh)
     #[arg(long, value_name = "SHELL")]
     pub shell: Option<PathBuf>,
+
+    /// Record the shell session to FILE (asciicast v2 format)
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Send SIGKILL to processes left behind by the shell that are still
+    /// running DURATION after SIGTERM [default: 10s]
+    #[arg(long, value_name = "DURATION", value_parser = crate::limits::parse_duration)]
+    pub kill_after: Option<Duration>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ReplayArgs {
+    /// Recording to play back (asciicast v2, as written by --record)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Playback speed multiplier
+    #[arg(long, default_value_t = 1.0, value_name = "FACTOR")]
+    pub speed: f64,
+
+    /// Cap pauses between output to this many seconds
+    #[arg(long, value_name = "SECS")]
+    pub idle_limit: Option<f64>,
 }
 
 #[derive(Parser, Debug)]
@@ -248,6 +397,10 @@ pub struct SetupArgs {
     #[arg(long)]
     pub shell_integration: bool,
 
+    /// Print the sandbox feature report as JSON (with --check-only)
+    #[arg(long, requires = "check_only")]
+    pub json: bool,
+
     /// Show detailed information during setup
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
@@ -275,6 +428,18 @@ pub struct WhyArgs {
     #[arg(long)]
     pub json: bool,
 
+    /// Read newline-delimited JSON queries from stdin, printing one JSON result per line
+    #[arg(long, conflicts_with_all = ["path", "host"])]
+    pub batch: bool,
+
+    /// List every rule considered, marking the one that decided the result
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Print the JSON Schema of the --json and --batch output
+    #[arg(long, conflicts_with_all = ["path", "host", "batch"])]
+    pub schema: bool,
+
     /// Query current sandbox state (use inside a sandboxed process)
     #[arg(long = "self")]
     pub self_query: bool,
@@ -304,10 +469,26 @@ pub struct WhyArgs {
     #[arg(long, value_name = "FILE")]
     pub write_file: Vec<PathBuf>,
 
+    /// Directories to allow read-only access without execute (for query context)
+    #[arg(long, value_name = "DIR")]
+    pub read_noexec: Vec<PathBuf>,
+
+    /// Directories whose programs may be executed (for query context)
+    #[arg(long, value_name = "DIR")]
+    pub exec_dir: Vec<PathBuf>,
+
     /// Block network access (for query context)
     #[arg(long)]
     pub net_block: bool,
 
+    /// Network namespace (for query context)
+    #[arg(long, value_name = "MODE")]
+    pub net: Option<NetMode>,
+
+    /// Query as if running with --isolate (for query context)
+    #[arg(long)]
+    pub isolate: bool,
+
     /// Use a named profile for query context
     #[arg(long, short = 'p', value_name = "NAME")]
     pub profile: Option<String>,
@@ -335,6 +516,16 @@ pub struct LearnArgs {
     #[arg(long)]
     pub all: bool,
 
+    /// Merge discovered paths into the user profile given by --profile.
+    /// Shows a diff and asks for confirmation before writing the file.
+    #[arg(long, requires = "profile")]
+    pub update: bool,
+
+    /// Run the command inside the sandbox of --profile and report only the
+    /// accesses the sandbox denied, instead of tracing it unrestricted
+    #[arg(long, requires = "profile")]
+    pub sandboxed: bool,
+
     /// Enable verbose output
     #[arg(long, short = 'v', action = clap::ArgAction::Count)]
     pub verbose: u8,
@@ -345,7 +536,8 @@ pub struct LearnArgs {
 }
 
 /// Operation type for why command
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Debug, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum WhyOp {
     /// Read-only access
     Read,
@@ -515,6 +707,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_learn_update_requires_profile() {
+        let result = Cli::try_parse_from(["nono", "learn", "--update", "--", "my-app"]);
+        assert!(result.is_err());
+
+        let cli = Cli::parse_from([
+            "nono",
+            "learn",
+            "--profile",
+            "my-profile",
+            "--update",
+            "--",
+            "my-app",
+        ]);
+        match cli.command {
+            Commands::Learn(args) => {
+                assert!(args.update);
+                assert_eq!(args.profile, Some("my-profile".to_string()));
+            }
+            _ => panic!("Expected Learn command"),
+        }
+    }
+
+    #[test]
+    fn test_learn_sandboxed_requires_profile() {
+        let result = Cli::try_parse_from(["nono", "learn", "--sandboxed", "--", "my-app"]);
+        assert!(result.is_err());
+
+        let cli = Cli::parse_from(["nono", "learn", "-p", "my-profile", "--sandboxed", "my-app"]);
+        match cli.command {
+            Commands::Learn(args) => assert!(args.sandboxed),
+            _ => panic!("Expected Learn command"),
+        }
+    }
+
     #[test]
     fn test_shell_basic() {
         let cli = Cli::parse_from(["nono", "shell", "--allow", "."]);
@@ -4,6 +4,7 @@
 
 #![allow(dead_code)]
 
+use super::CommandRule;
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 
@@ -13,6 +14,9 @@ pub struct SecurityLists {
     pub meta: SecurityListsMeta,
     pub sensitive_paths: SensitivePaths,
     pub dangerous_commands: DangerousCommands,
+    /// Commands blocked only with certain arguments
+    #[serde(default)]
+    pub command_rules: Vec<CommandRule>,
     pub system_read_paths: SystemReadPaths,
 }
 
@@ -117,8 +121,6 @@ pub struct LinuxSystemPaths {
     pub proc: Vec<String>,
     #[serde(default)]
     pub nix: Vec<String>,
-    #[serde(default)]
-    pub tmp: Vec<String>,
 }
 
 /// macOS-specific system paths
@@ -205,7 +207,6 @@ impl SecurityLists {
             paths.extend(self.system_read_paths.linux.devices.iter().cloned());
             paths.extend(self.system_read_paths.linux.proc.iter().cloned());
             paths.extend(self.system_read_paths.linux.nix.iter().cloned());
-            paths.extend(self.system_read_paths.linux.tmp.iter().cloned());
         }
 
         #[cfg(target_os = "macos")]
@@ -295,6 +296,7 @@ mod tests {
                 arbitrary_execution: vec![],
                 privilege_escalation: vec![],
             },
+            command_rules: vec![],
             system_read_paths: SystemReadPaths {
                 common: vec!["/bin".to_string(), "/usr/bin".to_string()],
                 linux: LinuxSystemPaths::default(),
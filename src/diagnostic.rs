@@ -13,7 +13,10 @@
 //! - **Actionable**: Provides specific flags to grant additional access
 //! - **Library code**: No process management, no CLI assumptions
 
-use crate::capability::{CapabilitySet, FsAccess};
+use crate::capability::{CapabilitySet, FsAccess, NetMode};
+use crate::config::BlockedCommand;
+use crate::limits::format_duration;
+use std::time::Duration;
 
 /// Formats diagnostic information about sandbox policy.
 ///
@@ -80,10 +83,12 @@ impl<'a> DiagnosticFormatter<'a> {
                     FsAccess::ReadWrite => "read+write",
                 };
                 let kind = cap.kind_label();
+                let noexec = if cap.noexec { ", noexec" } else { "" };
                 lines.push(format!(
-                    "[nono]     {} ({}, {})",
+                    "[nono]     {} ({}{}, {})",
                     cap.resolved.display(),
                     access_str,
+                    noexec,
                     kind
                 ));
             }
@@ -92,10 +97,18 @@ impl<'a> DiagnosticFormatter<'a> {
 
     /// Format the network status.
     fn format_network_status(&self, lines: &mut Vec<String>) {
-        if self.caps.net_block {
-            lines.push("[nono]   Network: blocked".to_string());
-        } else {
-            lines.push("[nono]   Network: allowed".to_string());
+        match self.caps.net_mode {
+            NetMode::None => lines.push("[nono]   Network: none (--net none)".to_string()),
+            NetMode::Loopback => {
+                lines.push("[nono]   Network: loopback only (--net loopback)".to_string())
+            }
+            NetMode::Host if self.caps.net_block_degraded => lines.push(
+                "[nono]   Network: allowed (--net-block not enforced by this kernel)".to_string(),
+            ),
+            NetMode::Host if self.caps.net_block => {
+                lines.push("[nono]   Network: blocked".to_string())
+            }
+            NetMode::Host => lines.push("[nono]   Network: allowed".to_string()),
         }
     }
 
@@ -119,10 +132,58 @@ impl<'a> DiagnosticFormatter<'a> {
     }
 }
 
+/// Format the footer shown when a wall-clock timeout stopped the command.
+///
+/// `killed` means the command ignored SIGTERM and was killed after `kill_after`.
+#[must_use]
+pub fn format_timeout_footer(timeout: Duration, kill_after: Duration, killed: bool) -> String {
+    let mut lines = vec![format!(
+        "[nono] Command timed out after {} and was stopped (--timeout).",
+        format_duration(timeout)
+    )];
+    if killed {
+        lines.push(format!(
+            "[nono] It did not exit within {} of SIGTERM and was killed with SIGKILL.",
+            format_duration(kill_after)
+        ));
+    } else {
+        lines.push("[nono] It exited after SIGTERM.".to_string());
+    }
+    lines.push("[nono] This is not a sandbox denial.".to_string());
+    lines.join("\n")
+}
+
+/// Format the message shown when an exec in the command's process tree was
+/// refused because the program is on the blocklist or matches a command rule.
+#[must_use]
+pub fn format_blocked_exec(blocked: &BlockedCommand, path: &str) -> String {
+    [
+        format!(
+            "[nono] Blocked execution of '{}' ({}): {}",
+            blocked.command, path, blocked.reason
+        ),
+        format!(
+            "[nono] To allow it, re-run with: {}",
+            blocked.override_hint()
+        ),
+    ]
+    .join("\n")
+}
+
+/// Format the message shown when an exec was refused because nono could not
+/// read which program the process was about to run.
+#[must_use]
+pub fn format_unchecked_exec(pid: u32) -> String {
+    format!(
+        "[nono] Blocked an exec in process {}: could not read which program it runs.",
+        pid
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::capability::FsCapability;
+    use crate::capability::{CapabilitySource, FsCapability};
     use std::path::PathBuf;
 
     fn make_test_caps() -> CapabilitySet {
@@ -132,11 +193,44 @@ mod tests {
             resolved: PathBuf::from("/test/project"),
             access: FsAccess::ReadWrite,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
         caps.net_block = true;
         caps
     }
 
+    #[test]
+    fn test_format_blocked_exec() {
+        let mut blocked = BlockedCommand {
+            command: "git".to_string(),
+            reason: "Force-pushing is blocked.".to_string(),
+            by_rule: false,
+        };
+        let output = format_blocked_exec(&blocked, "/usr/bin/git");
+        assert!(output.contains("'git' (/usr/bin/git): Force-pushing is blocked."));
+        assert!(output.contains("--allow-command git"));
+        assert!(output.lines().all(|line| line.starts_with("[nono]")));
+
+        // A rule: allowing the program allows more than this command line
+        blocked.by_rule = true;
+        let output = format_blocked_exec(&blocked, "/usr/bin/git");
+        assert!(output.contains("every use of git"));
+    }
+
+    #[test]
+    fn test_format_timeout_footer() {
+        let output = format_timeout_footer(Duration::from_secs(90), Duration::from_secs(10), false);
+        assert!(output.contains("timed out after 1m30s"));
+        assert!(output.contains("SIGTERM"));
+        assert!(!output.contains("SIGKILL"));
+        assert!(output.lines().all(|line| line.starts_with("[nono]")));
+
+        let output = format_timeout_footer(Duration::from_secs(90), Duration::from_secs(10), true);
+        assert!(output.contains("within 10s"));
+        assert!(output.contains("SIGKILL"));
+    }
+
     #[test]
     fn test_format_footer_contains_exit_code() {
         let caps = make_test_caps();
@@ -259,12 +353,16 @@ mod tests {
             resolved: PathBuf::from("/test/file.txt"),
             access: FsAccess::Read,
             is_file: true,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
         caps.fs.push(FsCapability {
             original: PathBuf::from("/test/dir"),
             resolved: PathBuf::from("/test/dir"),
             access: FsAccess::Write,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let formatter = DiagnosticFormatter::new(&caps);
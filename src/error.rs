@@ -73,7 +73,6 @@ pub enum NonoError {
         source: std::io::Error,
     },
 
-    #[allow(dead_code)]
     #[error("Failed to write config {path}: {source}")]
     ConfigWrite {
         path: std::path::PathBuf,
@@ -112,6 +111,9 @@ pub enum NonoError {
 
     #[error("Learn mode error: {0}")]
     LearnError(String),
+
+    #[error("Session recording error: {0}")]
+    Recording(String),
 }
 
 pub type Result<T> = std::result::Result<T, NonoError>;
@@ -0,0 +1,806 @@
+//! Command blocking for the whole process tree (Linux)
+//!
+//! nono refuses to start a blocked command such as `rm` or `sudo`, but that
+//! check only sees `argv[0]`: `bash -c 'rm -rf ~'`, or an agent that spawns
+//! `sudo` later on, would walk right past it. With `--exec-guard` (or
+//! `[commands] guard = true`), the forked child therefore installs a seccomp
+//! filter that turns every `execve` and
+//! `execveat` of the command and its descendants into a user notification,
+//! and hands the filter's listener to nono over a socket. A supervisor thread
+//! in nono answers each notification: it reads the path the process is about
+//! to execute and its arguments, lets the exec continue unless the program is
+//! on the effective blocklist or the command line matches a command rule, and
+//! otherwise fails it with `EPERM` and prints a `[nono]` diagnostic.
+//!
+//! This is a guard-rail against accidents, not a security boundary. The
+//! kernel reads the path again after nono allowed it, so a process racing its
+//! own memory can swap in another one, and a blocked program copied or linked
+//! under another name is not recognized. What a command can do to files is
+//! still decided by Landlock.
+//!
+//! The supervisor thread is started before nono restricts itself for
+//! `--isolate`. Landlock restrictions are per thread, so the thread can still
+//! read the memory of the command, which runs in a sibling Landlock domain.
+
+// Only used on Linux
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::capability::CapabilitySet;
+use crate::config::BlockedCommand;
+#[cfg(target_os = "linux")]
+use crate::config::{CommandRule, DefaultCommands};
+use crate::error::{NonoError, Result};
+#[cfg(target_os = "linux")]
+use nix::libc;
+#[cfg(target_os = "linux")]
+use std::collections::HashSet;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use tracing::{debug, warn};
+
+/// `SECCOMP_IOCTL_NOTIF_*` requests (not in libc)
+#[cfg(target_os = "linux")]
+const SECCOMP_IOCTL_NOTIF_RECV: u32 = 0xc050_2100;
+#[cfg(target_os = "linux")]
+const SECCOMP_IOCTL_NOTIF_SEND: u32 = 0xc018_2101;
+#[cfg(target_os = "linux")]
+const SECCOMP_IOCTL_NOTIF_ID_VALID: u32 = 0x4008_2102;
+
+/// Offsets of `nr` and `arch` in `struct seccomp_data`
+const NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+
+/// Longest path nono reads from a process (`PATH_MAX`)
+const PATH_MAX: usize = 4096;
+
+/// Longest argument nono reads from a process (`MAX_ARG_STRLEN`)
+const MAX_ARG_LEN: usize = 128 * 1024;
+
+/// Arguments nono reads at most; command rules only look at the first few
+const MAX_ARGS: usize = 256;
+
+/// The exec system calls of one architecture
+struct ExecSyscalls {
+    /// `AUDIT_ARCH_*` value reported in `seccomp_data.arch`
+    arch: u32,
+    execve: &'static [i32],
+    execveat: &'static [i32],
+}
+
+/// Bit set in x32 system call numbers
+const X32_SYSCALL_BIT: i32 = 0x4000_0000;
+
+/// Native ABI first, then the compat ABIs the kernel may also run
+#[cfg(target_arch = "x86_64")]
+const EXEC_SYSCALLS: &[ExecSyscalls] = &[
+    // x86_64 and x32
+    ExecSyscalls {
+        arch: 0xC000_003E,
+        execve: &[59, X32_SYSCALL_BIT | 520],
+        execveat: &[322, X32_SYSCALL_BIT | 545],
+    },
+    // i386
+    ExecSyscalls {
+        arch: 0x4000_0003,
+        execve: &[11],
+        execveat: &[358],
+    },
+];
+
+#[cfg(target_arch = "aarch64")]
+const EXEC_SYSCALLS: &[ExecSyscalls] = &[
+    // aarch64
+    ExecSyscalls {
+        arch: 0xC000_00B7,
+        execve: &[221],
+        execveat: &[281],
+    },
+    // arm
+    ExecSyscalls {
+        arch: 0x4000_0028,
+        execve: &[11],
+        execveat: &[387],
+    },
+];
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const EXEC_SYSCALLS: &[ExecSyscalls] = &[];
+
+/// One BPF instruction, as in `struct sock_filter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Insn {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+// Classic BPF opcodes (`linux/bpf_common.h`)
+/// `BPF_LD | BPF_W | BPF_ABS`
+const BPF_LD_W_ABS: u16 = 0x20;
+/// `BPF_JMP | BPF_JEQ | BPF_K`
+const BPF_JEQ_K: u16 = 0x15;
+/// `BPF_RET | BPF_K`
+const BPF_RET_K: u16 = 0x06;
+
+// Filter return values (`linux/seccomp.h`)
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+
+/// Build a filter that notifies on the exec system calls in `table` and
+/// allows everything else. A system call from an architecture not in `table`
+/// kills the process, since its execs could not be recognized.
+fn build_filter(table: &[ExecSyscalls]) -> Vec<Insn> {
+    let load = |offset| Insn {
+        code: BPF_LD_W_ABS,
+        jt: 0,
+        jf: 0,
+        k: offset,
+    };
+    let ret = |value| Insn {
+        code: BPF_RET_K,
+        jt: 0,
+        jf: 0,
+        k: value,
+    };
+
+    let mut filter = vec![load(ARCH_OFFSET)];
+    let mut notify_jumps = Vec::new();
+    for syscalls in table {
+        let numbers: Vec<i32> = syscalls
+            .execve
+            .iter()
+            .chain(syscalls.execveat)
+            .copied()
+            .collect();
+        // On another architecture, skip the load, the checks and the return
+        filter.push(Insn {
+            code: BPF_JEQ_K,
+            jt: 0,
+            jf: (numbers.len() + 2) as u8,
+            k: syscalls.arch,
+        });
+        filter.push(load(NR_OFFSET));
+        for nr in numbers {
+            notify_jumps.push(filter.len());
+            filter.push(Insn {
+                code: BPF_JEQ_K,
+                jt: 0,
+                jf: 0,
+                k: nr as u32,
+            });
+        }
+        filter.push(ret(SECCOMP_RET_ALLOW));
+    }
+    filter.push(ret(SECCOMP_RET_KILL_PROCESS));
+
+    let notify = filter.len();
+    filter.push(ret(SECCOMP_RET_USER_NOTIF));
+    for at in notify_jumps {
+        filter[at].jt = (notify - at - 1) as u8;
+    }
+    filter
+}
+
+/// Which exec system call `nr` is on `arch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExecCall {
+    Execve,
+    Execveat,
+}
+
+/// Also returns the size of a pointer in the calling process: 4 bytes for
+/// the compat ABIs and x32.
+fn exec_call(arch: u32, nr: i32) -> Option<(ExecCall, usize)> {
+    let index = EXEC_SYSCALLS.iter().position(|s| s.arch == arch)?;
+    let syscalls = &EXEC_SYSCALLS[index];
+    let pointer_size = if index > 0 || nr & X32_SYSCALL_BIT != 0 {
+        4
+    } else {
+        8
+    };
+    if syscalls.execve.contains(&nr) {
+        Some((ExecCall::Execve, pointer_size))
+    } else if syscalls.execveat.contains(&nr) {
+        Some((ExecCall::Execveat, pointer_size))
+    } else {
+        None
+    }
+}
+
+/// Checks every exec in the command's process tree against the blocklist.
+///
+/// Prepared in the parent before fork: the child installs the filter with
+/// [`ExecGuard::install`], the parent answers it with [`ExecGuard::supervise`].
+#[cfg(target_os = "linux")]
+pub struct ExecGuard {
+    filter: Vec<libc::sock_filter>,
+    /// The child's end of the socket the listener is sent over
+    child_socket: Mutex<Option<OwnedFd>>,
+    child_fd: RawFd,
+    /// nono's end of the socket
+    socket: Mutex<Option<OwnedFd>>,
+    defaults: DefaultCommands,
+    allowed_commands: Vec<String>,
+    blocked_commands: Vec<String>,
+    command_rules: Vec<CommandRule>,
+}
+
+#[cfg(target_os = "linux")]
+impl ExecGuard {
+    /// Prepare the guard. Fails on an architecture it does not know the
+    /// system calls of, or if Yama keeps nono from reading the memory of the
+    /// command's processes: the guard was asked for, so the command must not
+    /// run without it.
+    pub fn prepare(caps: &CapabilitySet) -> Result<Option<Self>> {
+        if EXEC_SYSCALLS.is_empty() {
+            return Err(NonoError::SandboxInit(
+                "--exec-guard does not know the exec system calls of this architecture. \
+                 Run without --exec-guard."
+                    .to_string(),
+            ));
+        }
+        if let Some(reason) = ptrace_restricted() {
+            return Err(NonoError::SandboxInit(format!(
+                "--exec-guard needs to read which program each process of the command \
+                 runs, but {}. Run without --exec-guard, or lower the setting.",
+                reason
+            )));
+        }
+
+        let mut fds = [0; 2];
+        if unsafe {
+            libc::socketpair(
+                libc::AF_UNIX,
+                libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+                0,
+                fds.as_mut_ptr(),
+            )
+        } != 0
+        {
+            return Err(NonoError::SandboxInit(format!(
+                "Failed to create the exec guard socket: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        let (socket, child_socket) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        let filter = build_filter(EXEC_SYSCALLS)
+            .into_iter()
+            .map(|insn| libc::sock_filter {
+                code: insn.code,
+                jt: insn.jt,
+                jf: insn.jf,
+                k: insn.k,
+            })
+            .collect();
+
+        Ok(Some(Self {
+            filter,
+            child_fd: child_socket.as_raw_fd(),
+            child_socket: Mutex::new(Some(child_socket)),
+            socket: Mutex::new(Some(socket)),
+            defaults: DefaultCommands::load(),
+            allowed_commands: caps.allowed_commands.clone(),
+            blocked_commands: caps.blocked_commands.clone(),
+            command_rules: caps.command_rules.clone(),
+        }))
+    }
+
+    /// Install the filter in the forked child and send its listener to nono.
+    ///
+    /// The child exits with 126 if the kernel refuses the filter, since the
+    /// guard was asked for, or if it cannot hand over the listener, since its
+    /// execs could not be answered.
+    ///
+    /// Async-signal-safe: only raw system calls on data prepared in the parent.
+    pub fn install(&self) {
+        let program = libc::sock_fprog {
+            len: self.filter.len() as libc::c_ushort,
+            filter: self.filter.as_ptr().cast_mut(),
+        };
+        unsafe {
+            libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+            let listener = libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                &program as *const libc::sock_fprog,
+            );
+            if listener < 0 {
+                const MSG: &[u8] = b"nono: the kernel refused the exec guard's seccomp filter\n";
+                libc::write(libc::STDERR_FILENO, MSG.as_ptr().cast(), MSG.len());
+                libc::_exit(126);
+            }
+            let listener = listener as RawFd;
+            let sent = send_fd(self.child_fd, listener);
+            libc::close(listener);
+            libc::close(self.child_fd);
+            if !sent {
+                const MSG: &[u8] = b"nono: failed to hand over the exec guard\n";
+                libc::write(libc::STDERR_FILENO, MSG.as_ptr().cast(), MSG.len());
+                libc::_exit(126);
+            }
+        }
+    }
+
+    /// Answer the child's exec notifications from a thread of its own.
+    ///
+    /// Must be called in the parent right after fork, before nono restricts
+    /// itself. `report` shows a diagnostic to the user.
+    pub fn supervise(&self, report: impl Fn(&str) + Send + 'static) {
+        // With only the child holding its end, reading reports EOF if the
+        // child sends no listener
+        drop(lock(&self.child_socket).take());
+        let Some(socket) = lock(&self.socket).take() else {
+            return;
+        };
+        let supervisor = Supervisor {
+            defaults: self.defaults.clone(),
+            allowed_commands: self.allowed_commands.clone(),
+            blocked_commands: self.blocked_commands.clone(),
+            command_rules: self.command_rules.clone(),
+            report: Box::new(report),
+            reported: HashSet::new(),
+        };
+
+        let spawned = std::thread::Builder::new()
+            .name("exec-guard".to_string())
+            .spawn(move || {
+                let Some(listener) = recv_fd(&socket) else {
+                    // The child exited before running the command
+                    debug!("The command did not hand over the exec guard");
+                    return;
+                };
+                drop(socket);
+                supervisor.run(&listener);
+            });
+        if let Err(e) = spawned {
+            // Closing the socket closes the listener, so the command's execs
+            // fail rather than wait forever
+            warn!("Failed to start the exec guard: {}", e);
+        }
+    }
+}
+
+/// Why nono may not read the memory of the command's processes, if Yama
+/// forbids it: `ptrace_scope` 1 allows it for descendants, 2 only with
+/// `CAP_SYS_PTRACE`, and 3 never.
+#[cfg(target_os = "linux")]
+fn ptrace_restricted() -> Option<String> {
+    const CAP_SYS_PTRACE: u32 = 19;
+
+    let scope: u32 = std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    if scope < 2 {
+        return None;
+    }
+    if scope == 2 {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let effective = status
+            .lines()
+            .find_map(|line| line.strip_prefix("CapEff:"))
+            .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+            .unwrap_or(0);
+        if effective & (1 << CAP_SYS_PTRACE) != 0 {
+            return None;
+        }
+    }
+    Some(format!(
+        "kernel.yama.ptrace_scope is {}, which forbids it",
+        scope
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Send `fd` over a Unix socket.
+///
+/// Async-signal-safe.
+#[cfg(target_os = "linux")]
+unsafe fn send_fd(socket: RawFd, fd: RawFd) -> bool {
+    let mut byte = 0u8;
+    let mut iov = libc::iovec {
+        iov_base: (&mut byte as *mut u8).cast(),
+        iov_len: 1,
+    };
+    // Room for CMSG_SPACE(sizeof(int)), suitably aligned
+    let mut control = [0u64; 4];
+    let mut msg: libc::msghdr = std::mem::zeroed();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = libc::CMSG_SPACE(size_of::<libc::c_int>() as u32) as _;
+
+    let cmsg = libc::CMSG_FIRSTHDR(&msg);
+    (*cmsg).cmsg_level = libc::SOL_SOCKET;
+    (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+    (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<libc::c_int>() as u32) as _;
+    std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>(), fd);
+
+    libc::sendmsg(socket, &msg, 0) == 1
+}
+
+/// Receive a file descriptor sent with [`send_fd`], or `None` on EOF.
+#[cfg(target_os = "linux")]
+fn recv_fd(socket: &OwnedFd) -> Option<OwnedFd> {
+    let mut byte = 0u8;
+    let mut iov = libc::iovec {
+        iov_base: (&mut byte as *mut u8).cast(),
+        iov_len: 1,
+    };
+    let mut control = [0u64; 4];
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = std::mem::size_of_val(&control) as _;
+
+    loop {
+        let n = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+        if n > 0 {
+            break;
+        }
+        if n == 0 || std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if cmsg.is_null()
+            || (*cmsg).cmsg_level != libc::SOL_SOCKET
+            || (*cmsg).cmsg_type != libc::SCM_RIGHTS
+        {
+            return None;
+        }
+        let fd = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>());
+        Some(OwnedFd::from_raw_fd(fd))
+    }
+}
+
+/// What to do with an exec
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Verdict {
+    Allow,
+    /// The program is on the blocklist, or its arguments match a rule
+    Blocked {
+        blocked: BlockedCommand,
+        path: String,
+    },
+    /// The path could not be read, e.g. because the process is not dumpable
+    Unreadable,
+}
+
+/// The supervisor thread's state
+#[cfg(target_os = "linux")]
+struct Supervisor {
+    defaults: DefaultCommands,
+    allowed_commands: Vec<String>,
+    blocked_commands: Vec<String>,
+    command_rules: Vec<CommandRule>,
+    report: Box<dyn Fn(&str) + Send>,
+    /// Commands already reported: shells retry a refused exec for every
+    /// directory on `PATH`
+    reported: HashSet<(String, String)>,
+}
+
+#[cfg(target_os = "linux")]
+impl Supervisor {
+    /// Answer notifications until every process using the filter has exited.
+    fn run(mut self, listener: &OwnedFd) {
+        let fd = listener.as_raw_fd();
+        // The first exec is the command itself, which nono already checked.
+        // Its memory cannot be read yet: it inherited nono's PR_SET_DUMPABLE(0)
+        let mut first = true;
+
+        loop {
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 {
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            if pollfd.revents & libc::POLLIN == 0 {
+                // POLLHUP: the command and all its descendants have exited
+                return;
+            }
+
+            let mut req: libc::seccomp_notif = unsafe { std::mem::zeroed() };
+            if unsafe { libc::ioctl(fd, SECCOMP_IOCTL_NOTIF_RECV as _, &mut req) } < 0 {
+                // ENOENT: the process was killed while nono was not looking
+                match std::io::Error::last_os_error().raw_os_error() {
+                    Some(libc::ENOENT) | Some(libc::EINTR) => continue,
+                    _ => return,
+                }
+            }
+
+            let verdict = if first {
+                first = false;
+                Verdict::Allow
+            } else {
+                self.check(&req)
+            };
+
+            // The process may have died, and its pid been reused, while the
+            // path was read
+            if unsafe { libc::ioctl(fd, SECCOMP_IOCTL_NOTIF_ID_VALID as _, &req.id) } < 0 {
+                continue;
+            }
+
+            let mut resp = libc::seccomp_notif_resp {
+                id: req.id,
+                val: 0,
+                error: 0,
+                flags: 0,
+            };
+            match verdict {
+                Verdict::Allow => resp.flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32,
+                Verdict::Blocked {
+                    ref blocked,
+                    ref path,
+                } => {
+                    debug!(
+                        "Exec guard: blocked {} ({}) in pid {}",
+                        blocked.command, path, req.pid
+                    );
+                    resp.error = -libc::EPERM;
+                    if self
+                        .reported
+                        .insert((blocked.command.clone(), blocked.reason.clone()))
+                    {
+                        (self.report)(&crate::diagnostic::format_blocked_exec(blocked, path));
+                    }
+                }
+                Verdict::Unreadable => {
+                    resp.error = -libc::EPERM;
+                    (self.report)(&crate::diagnostic::format_unchecked_exec(req.pid));
+                }
+            }
+            unsafe { libc::ioctl(fd, SECCOMP_IOCTL_NOTIF_SEND as _, &mut resp) };
+        }
+    }
+
+    fn check(&self, req: &libc::seccomp_notif) -> Verdict {
+        let Some(command) = exec_command(req) else {
+            return Verdict::Unreadable;
+        };
+        let command: Vec<&std::ffi::OsStr> = command
+            .iter()
+            .map(|arg| std::ffi::OsStr::from_bytes(arg))
+            .collect();
+        match crate::config::check_blocked_command_with(
+            &self.defaults,
+            &command,
+            &self.allowed_commands,
+            &self.blocked_commands,
+            &self.command_rules,
+        ) {
+            Some(blocked) => Verdict::Blocked {
+                blocked,
+                path: command[0].to_string_lossy().into_owned(),
+            },
+            None => Verdict::Allow,
+        }
+    }
+}
+
+/// The path a notified process is about to execute, followed by its
+/// arguments after `argv[0]`, which is whatever the caller chose.
+///
+/// A NULL or empty `argv` leaves just the path, so the program is still
+/// checked by name.
+#[cfg(target_os = "linux")]
+fn exec_command(req: &libc::seccomp_notif) -> Option<Vec<Vec<u8>>> {
+    let pid = req.pid as libc::pid_t;
+    let args = &req.data.args;
+    let (call, pointer_size) = exec_call(req.data.arch, req.data.nr)?;
+    let (path, argv) = match call {
+        ExecCall::Execve => (read_remote_string(pid, args[0], PATH_MAX)?, args[1]),
+        ExecCall::Execveat => {
+            let path = read_remote_string(pid, args[1], PATH_MAX)?;
+            if path.is_empty() && args[4] & libc::AT_EMPTY_PATH as u64 != 0 {
+                // fexecve(): the program is the file descriptor itself
+                let link = format!("/proc/{}/fd/{}", pid, args[0] as i32);
+                let target = std::fs::read_link(link).ok()?;
+                (target.into_os_string().into_vec(), args[2])
+            } else {
+                (path, args[2])
+            }
+        }
+    };
+
+    let mut command = vec![path];
+    if argv == 0 {
+        return Some(command);
+    }
+    for index in 0..MAX_ARGS {
+        let mut pointer = [0u8; 8];
+        let slot = argv.checked_add((index * pointer_size) as u64)?;
+        read_remote(pid, slot, &mut pointer[..pointer_size])?;
+        let arg = if pointer_size == 4 {
+            u64::from(u32::from_ne_bytes([
+                pointer[0], pointer[1], pointer[2], pointer[3],
+            ]))
+        } else {
+            u64::from_ne_bytes(pointer)
+        };
+        if arg == 0 {
+            break;
+        }
+        if index == 0 {
+            continue;
+        }
+        command.push(read_remote_string(pid, arg, MAX_ARG_LEN)?);
+    }
+    Some(command)
+}
+
+/// Read exactly `buf.len()` bytes from another process's memory.
+#[cfg(target_os = "linux")]
+fn read_remote(pid: libc::pid_t, addr: u64, buf: &mut [u8]) -> Option<()> {
+    let local = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    let remote = libc::iovec {
+        iov_base: usize::try_from(addr).ok()? as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let n = unsafe { libc::process_vm_readv(pid, &local, 1, &remote, 1, 0) };
+    (n == buf.len() as isize).then_some(())
+}
+
+/// Read a NUL-terminated string from another process's memory.
+///
+/// Reads page by page, since the string may end right before unmapped memory.
+#[cfg(target_os = "linux")]
+fn read_remote_string(pid: libc::pid_t, addr: u64, max_len: usize) -> Option<Vec<u8>> {
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    };
+    let mut addr = usize::try_from(addr).ok()?;
+    let mut string = Vec::new();
+
+    loop {
+        let mut buf = vec![0u8; page_size - addr % page_size];
+        let local = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        let remote = libc::iovec {
+            iov_base: addr as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let n = unsafe { libc::process_vm_readv(pid, &local, 1, &remote, 1, 0) };
+        if n <= 0 {
+            return None;
+        }
+        buf.truncate(n as usize);
+        if let Some(end) = buf.iter().position(|&b| b == 0) {
+            string.extend_from_slice(&buf[..end]);
+            return (string.len() <= max_len).then_some(string);
+        }
+        string.extend_from_slice(&buf);
+        if string.len() > max_len {
+            return None;
+        }
+        addr += buf.len();
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct ExecGuard;
+
+#[cfg(not(target_os = "linux"))]
+impl ExecGuard {
+    pub fn prepare(_caps: &CapabilitySet) -> Result<Option<Self>> {
+        Err(NonoError::SandboxInit(
+            "--exec-guard is only supported on Linux. Run without --exec-guard.".to_string(),
+        ))
+    }
+
+    pub fn install(&self) {}
+
+    pub fn supervise(&self, _report: impl Fn(&str) + Send + 'static) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `filter` the way the kernel would for one system call
+    fn run_filter(filter: &[Insn], arch: u32, nr: i32) -> u32 {
+        let mut acc = 0;
+        let mut pc = 0;
+        loop {
+            let insn = filter[pc];
+            pc += 1;
+            match insn.code {
+                BPF_LD_W_ABS if insn.k == ARCH_OFFSET => acc = arch,
+                BPF_LD_W_ABS if insn.k == NR_OFFSET => acc = nr as u32,
+                BPF_JEQ_K if acc == insn.k => pc += insn.jt as usize,
+                BPF_JEQ_K => pc += insn.jf as usize,
+                BPF_RET_K => return insn.k,
+                code => panic!("unexpected instruction {:#x}", code),
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_notifies_on_exec_only() {
+        let filter = build_filter(EXEC_SYSCALLS);
+        for syscalls in EXEC_SYSCALLS {
+            for &nr in syscalls.execve.iter().chain(syscalls.execveat) {
+                assert_eq!(
+                    run_filter(&filter, syscalls.arch, nr),
+                    SECCOMP_RET_USER_NOTIF
+                );
+            }
+            // read() or write() on every architecture
+            for nr in [0, 1, 3, 4, 63, 64] {
+                assert_eq!(run_filter(&filter, syscalls.arch, nr), SECCOMP_RET_ALLOW);
+            }
+        }
+        // An architecture the filter does not know
+        assert_eq!(run_filter(&filter, 0x1234, 59), SECCOMP_RET_KILL_PROCESS);
+    }
+
+    #[test]
+    fn test_exec_call() {
+        for syscalls in EXEC_SYSCALLS {
+            assert_eq!(
+                exec_call(syscalls.arch, syscalls.execve[0]).map(|(call, _)| call),
+                Some(ExecCall::Execve)
+            );
+            assert_eq!(
+                exec_call(syscalls.arch, syscalls.execveat[0]).map(|(call, _)| call),
+                Some(ExecCall::Execveat)
+            );
+        }
+        // Pointers are 8 bytes in the native ABI, which comes first
+        if let Some(native) = EXEC_SYSCALLS.first() {
+            assert_eq!(
+                exec_call(native.arch, native.execve[0]),
+                Some((ExecCall::Execve, 8))
+            );
+        }
+        assert_eq!(exec_call(0x1234, 59), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_remote_string() {
+        let path = std::ffi::CString::new("/usr/bin/rm").unwrap();
+        let pid = std::process::id() as libc::pid_t;
+        assert_eq!(
+            read_remote_string(pid, path.as_ptr() as u64, PATH_MAX),
+            Some(b"/usr/bin/rm".to_vec())
+        );
+        // Longer than allowed
+        assert_eq!(read_remote_string(pid, path.as_ptr() as u64, 4), None);
+        assert_eq!(read_remote_string(pid, 0, PATH_MAX), None);
+    }
+}
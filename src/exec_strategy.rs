@@ -11,21 +11,29 @@
 //! allocation is safe) and uses only raw libc calls in the child.
 
 use crate::capability::CapabilitySet;
-use crate::diagnostic::DiagnosticFormatter;
+use crate::cgroup::SessionCgroup;
+use crate::diagnostic::{self, DiagnosticFormatter};
 use crate::error::{NonoError, Result};
+use crate::exec_guard::ExecGuard;
+use crate::isolate::Isolation;
+use crate::limits::PreparedLimits;
+use crate::recording::Recorder;
 use nix::libc;
 use nix::sys::signal::{self, Signal};
+use nix::sys::termios;
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd::{fork, ForkResult, Pid};
+use std::collections::HashSet;
 use std::ffi::CString;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::mem::ManuallyDrop;
-use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::fd::{AsFd, AsRawFd, OwnedFd, RawFd};
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::Command;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 /// Resolve a program name to its absolute path.
@@ -44,6 +52,28 @@ pub fn resolve_program(program: &str) -> Result<PathBuf> {
     })
 }
 
+/// Exit code reported when `--timeout` fires (as used by GNU `timeout`)
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Grace period between SIGTERM and SIGKILL when `--kill-after` is not given
+pub const DEFAULT_KILL_AFTER: Duration = Duration::from_secs(10);
+
+/// Wall-clock limit for the command (`--timeout`, `--kill-after`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    /// How long the command may run
+    pub duration: Duration,
+    /// How long to wait after SIGTERM before sending SIGKILL
+    pub kill_after: Duration,
+}
+
+/// How often to check whether leftover processes have exited
+const LEFTOVER_POLL_MS: u64 = 50;
+
+/// Maximum time to wait for buffered output once nono stops waiting for the
+/// command. Background processes may keep the pty or pipes open indefinitely.
+const OUTPUT_DRAIN_TIMEOUT_MS: u64 = 500;
+
 /// Maximum threads allowed when keyring backend is active.
 /// Main thread (1) + up to 3 keyring threads for D-Bus/Security.framework.
 const MAX_KEYRING_THREADS: usize = 4;
@@ -93,6 +123,15 @@ pub enum ExecStrategy {
     #[default]
     Monitor,
 
+    /// Monitor mode for interactive apps: like Monitor, but the child runs
+    /// on a pseudo-terminal that nono proxies (raw mode, resize, job control).
+    ///
+    /// - Same attack surface as Monitor (parent sandboxed too)
+    /// - Diagnostic footer on non-zero exit, after the terminal is restored
+    /// - No mid-stream diagnostics (would corrupt full-screen UIs)
+    /// - Default for interactive profiles and `nono shell` on a terminal
+    MonitorPty,
+
     /// Supervised mode: fork first, sandbox only child.
     /// Parent is unsandboxed.
     ///
@@ -122,6 +161,21 @@ pub struct ExecConfig<'a> {
     pub no_diagnostics: bool,
     /// Threading context for fork safety validation.
     pub threading: ThreadingContext,
+    /// Session recorder (`--record`); not supported in Direct mode.
+    pub recorder: Option<Arc<Recorder>>,
+    /// Session cgroup the child joins; not supported in Direct mode.
+    pub cgroup: Option<&'a SessionCgroup>,
+    /// Wall-clock timeout; not supported in Direct mode.
+    pub timeout: Option<Timeout>,
+    /// Grace period between SIGTERM and SIGKILL for processes left behind
+    /// when the command exits (`--kill-after`).
+    pub kill_after: Duration,
+    /// Mount-namespace isolation (`--isolate`); not supported in Direct mode.
+    /// nono itself is only sandboxed once the child has been started.
+    pub isolation: Option<&'a Isolation>,
+    /// Checks every exec in the process tree against the blocklist; not
+    /// supported in Direct mode.
+    pub exec_guard: Option<&'a ExecGuard>,
 }
 
 /// Execute a command using the Direct strategy (exec, nono disappears).
@@ -144,6 +198,14 @@ pub fn execute_direct(config: &ExecConfig<'_>) -> Result<()> {
         cmd.env(key, value);
     }
 
+    // nono is replaced by the command, so the limits can be set on this process
+    config
+        .caps
+        .limits
+        .prepare()?
+        .apply()
+        .map_err(|e| NonoError::SandboxInit(format!("Failed to set resource limits: {}", e)))?;
+
     let err = cmd.exec();
 
     // exec() only returns if there's an error
@@ -202,120 +264,18 @@ pub fn execute_direct(config: &ExecConfig<'_>) -> Result<()> {
 /// - Uses only raw libc calls in the child (no Rust allocations)
 /// - Exits with `libc::_exit()` on error (not `std::process::exit()` or panic)
 pub fn execute_monitor(config: &ExecConfig<'_>) -> Result<i32> {
-    let program = &config.command[0];
-    let cmd_args = &config.command[1..];
-
-    info!("Executing (monitor): {} {:?}", program, cmd_args);
-
-    // Use pre-resolved program path (resolved before sandbox was applied)
-    // This ensures the program can be found even if its directory is not
-    // in the sandbox's allowed paths.
-    let program_path = config.resolved_program;
-
-    // Convert program path to CString for execve
-    let program_c = CString::new(program_path.to_string_lossy().as_bytes())
-        .map_err(|_| NonoError::SandboxInit("Program path contains null byte".to_string()))?;
-
-    // Build argv: [program, args..., NULL]
-    let mut argv_c: Vec<CString> = Vec::with_capacity(1 + cmd_args.len());
-    argv_c.push(program_c.clone());
-    for arg in cmd_args {
-        argv_c.push(CString::new(arg.as_bytes()).map_err(|_| {
-            NonoError::SandboxInit(format!("Argument contains null byte: {}", arg))
-        })?);
-    }
-
-    // Build environment: inherit current env + add our vars
-    let mut env_c: Vec<CString> = Vec::new();
-
-    // Copy current environment, skipping vars we'll override
-    for (key, value) in std::env::vars_os() {
-        if let (Some(k), Some(v)) = (key.to_str(), value.to_str()) {
-            let should_skip =
-                config.env_vars.iter().any(|(ek, _)| *ek == k) || k == "NONO_CAP_FILE";
-            if !should_skip {
-                if let Ok(cstr) = CString::new(format!("{}={}", k, v)) {
-                    env_c.push(cstr);
-                }
-            }
-        }
-    }
-
-    // Add NONO_CAP_FILE
-    if let Some(cap_file_str) = config.cap_file.to_str() {
-        if let Ok(cstr) = CString::new(format!("NONO_CAP_FILE={}", cap_file_str)) {
-            env_c.push(cstr);
-        }
-    }
-
-    // Add user-specified environment variables (secrets, etc.)
-    for (key, value) in &config.env_vars {
-        if let Ok(cstr) = CString::new(format!("{}={}", key, value)) {
-            env_c.push(cstr);
-        }
-    }
-
-    // Create null-terminated pointer arrays for execve
-    let argv_ptrs: Vec<*const libc::c_char> = argv_c
-        .iter()
-        .map(|s| s.as_ptr())
-        .chain(std::iter::once(std::ptr::null()))
-        .collect();
-
-    let envp_ptrs: Vec<*const libc::c_char> = env_c
-        .iter()
-        .map(|s| s.as_ptr())
-        .chain(std::iter::once(std::ptr::null()))
-        .collect();
+    info!(
+        "Executing (monitor): {} {:?}",
+        config.command[0],
+        &config.command[1..]
+    );
 
-    // Platform-specific ptrace hardening
-    #[cfg(target_os = "linux")]
-    {
-        use nix::sys::prctl;
-        if let Err(e) = prctl::set_dumpable(false) {
-            warn!("Failed to set PR_SET_DUMPABLE(0): {}", e);
-        }
-    }
+    let exec = ExecData::prepare(config)?;
+    let argv_ptrs = exec.argv_ptrs();
+    let envp_ptrs = exec.envp_ptrs();
 
-    #[cfg(target_os = "macos")]
-    {
-        const PT_DENY_ATTACH: libc::c_int = 31;
-        let result =
-            unsafe { libc::ptrace(PT_DENY_ATTACH, 0, std::ptr::null_mut::<libc::c_char>(), 0) };
-        if result != 0 {
-            warn!(
-                "Failed to set PT_DENY_ATTACH: {} (errno: {})",
-                result,
-                std::io::Error::last_os_error()
-            );
-        }
-    }
-
-    // Validate threading context before fork
-    let thread_count = get_thread_count();
-    match (config.threading, thread_count) {
-        (_, 1) => {}
-        (ThreadingContext::KeyringExpected, n) if n <= MAX_KEYRING_THREADS => {
-            debug!(
-                "Proceeding with fork despite {} threads (keyring backend threads expected)",
-                n
-            );
-        }
-        (ThreadingContext::Strict, n) => {
-            return Err(NonoError::SandboxInit(format!(
-                "Cannot fork: process has {} threads (expected 1). \
-                 This is a bug - fork() requires single-threaded execution.",
-                n
-            )));
-        }
-        (ThreadingContext::KeyringExpected, n) => {
-            return Err(NonoError::SandboxInit(format!(
-                "Cannot fork: process has {} threads (max {} with keyring). \
-                 Unexpected threading detected.",
-                n, MAX_KEYRING_THREADS
-            )));
-        }
-    }
+    harden_and_check_threads(config.threading)?;
+    become_subreaper();
 
     // Create pipes for stdout and stderr interception
     let (stdout_read, stdout_write): (OwnedFd, OwnedFd) = nix::unistd::pipe()
@@ -338,14 +298,25 @@ pub fn execute_monitor(config: &ExecConfig<'_>) -> Result<i32> {
 
     // Compute max FD in parent (get_max_fd may allocate on Linux)
     let max_fd = get_max_fd();
+    let terminal = controlling_terminal();
+    // Checked before fork, since the parent also moves the child to its own group
+    let foreground = terminal.filter(|&fd| in_foreground(fd));
 
     // SAFETY: fork() is safe here because we validated threading context
     // and child will only use async-signal-safe functions until exec()
-    let fork_result = unsafe { fork() };
+    let fork_result = unsafe { fork_child(config) };
 
     match fork_result {
         Ok(ForkResult::Child) => {
             // CHILD: No allocations allowed from here until exec()
+            join_cgroup_or_exit(exec.cgroup_procs);
+            enter_process_group(foreground);
+            if let Some(isolation) = config.isolation {
+                isolation.enter_or_exit();
+            }
+            if let Some(guard) = config.exec_guard {
+                guard.install();
+            }
 
             // Close read ends of pipes
             unsafe {
@@ -372,9 +343,15 @@ pub fn execute_monitor(config: &ExecConfig<'_>) -> Result<i32> {
                 }
             }
 
+            apply_limits_or_exit(&exec.limits);
+
             // Execute using pre-prepared CStrings (no allocation)
             unsafe {
-                libc::execve(program_c.as_ptr(), argv_ptrs.as_ptr(), envp_ptrs.as_ptr());
+                libc::execve(
+                    exec.program.as_ptr(),
+                    argv_ptrs.as_ptr(),
+                    envp_ptrs.as_ptr(),
+                );
             }
 
             // execve only returns on error - exit without cleanup
@@ -393,7 +370,13 @@ pub fn execute_monitor(config: &ExecConfig<'_>) -> Result<i32> {
             let stdout_file = std::fs::File::from(stdout_read);
             let stderr_file = std::fs::File::from(stderr_read);
 
-            execute_parent_monitor(child, config, stdout_file, stderr_file)
+            // Also set here, so the group exists before the parent signals it
+            // (fails harmlessly if the child already exec'd)
+            let _ = nix::unistd::setpgid(child, child);
+            start_exec_guard(config, false);
+            restrict_parent_or_kill(child, config)?;
+
+            execute_parent_monitor(child, config, terminal, stdout_file, stderr_file)
         }
         Err(e) => {
             unsafe {
@@ -407,6 +390,263 @@ pub fn execute_monitor(config: &ExecConfig<'_>) -> Result<i32> {
     }
 }
 
+/// Data for `execve` in a forked child, prepared in the parent where allocation is safe.
+struct ExecData {
+    program: CString,
+    argv: Vec<CString>,
+    env: Vec<CString>,
+    limits: PreparedLimits,
+    /// `cgroup.procs` of the session cgroup
+    cgroup_procs: Option<RawFd>,
+}
+
+impl ExecData {
+    fn prepare(config: &ExecConfig<'_>) -> Result<Self> {
+        let cmd_args = &config.command[1..];
+
+        // Use pre-resolved program path (resolved before sandbox was applied)
+        // This ensures the program can be found even if its directory is not
+        // in the sandbox's allowed paths.
+        let program = CString::new(config.resolved_program.to_string_lossy().as_bytes())
+            .map_err(|_| NonoError::SandboxInit("Program path contains null byte".to_string()))?;
+
+        // Build argv: [program, args..., NULL]
+        let mut argv: Vec<CString> = Vec::with_capacity(1 + cmd_args.len());
+        argv.push(program.clone());
+        for arg in cmd_args {
+            argv.push(CString::new(arg.as_bytes()).map_err(|_| {
+                NonoError::SandboxInit(format!("Argument contains null byte: {}", arg))
+            })?);
+        }
+
+        // Build environment: inherit current env + add our vars
+        let mut env: Vec<CString> = Vec::new();
+
+        // Copy current environment, skipping vars we'll override
+        for (key, value) in std::env::vars_os() {
+            if let (Some(k), Some(v)) = (key.to_str(), value.to_str()) {
+                let should_skip =
+                    config.env_vars.iter().any(|(ek, _)| *ek == k) || k == "NONO_CAP_FILE";
+                if !should_skip {
+                    if let Ok(cstr) = CString::new(format!("{}={}", k, v)) {
+                        env.push(cstr);
+                    }
+                }
+            }
+        }
+
+        // Add NONO_CAP_FILE
+        if let Some(cap_file_str) = config.cap_file.to_str() {
+            if let Ok(cstr) = CString::new(format!("NONO_CAP_FILE={}", cap_file_str)) {
+                env.push(cstr);
+            }
+        }
+
+        // Add user-specified environment variables (secrets, etc.)
+        for (key, value) in &config.env_vars {
+            if let Ok(cstr) = CString::new(format!("{}={}", key, value)) {
+                env.push(cstr);
+            }
+        }
+
+        let limits = config.caps.limits.prepare()?;
+
+        Ok(Self {
+            program,
+            argv,
+            env,
+            limits,
+            cgroup_procs: config.cgroup.map(SessionCgroup::procs_fd),
+        })
+    }
+
+    /// Null-terminated argv pointer array for execve
+    fn argv_ptrs(&self) -> Vec<*const libc::c_char> {
+        null_terminated(&self.argv)
+    }
+
+    /// Null-terminated envp pointer array for execve
+    fn envp_ptrs(&self) -> Vec<*const libc::c_char> {
+        null_terminated(&self.env)
+    }
+}
+
+fn null_terminated(strings: &[CString]) -> Vec<*const libc::c_char> {
+    strings
+        .iter()
+        .map(|s| s.as_ptr())
+        .chain(std::iter::once(std::ptr::null()))
+        .collect()
+}
+
+/// The controlling terminal nono is attached to, if any of stdin, stdout or
+/// stderr is one.
+fn controlling_terminal() -> Option<RawFd> {
+    [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+        .into_iter()
+        .find(|&fd| unsafe { libc::tcgetpgrp(fd) } > 0)
+}
+
+/// Whether the calling process's group is in the foreground of `terminal`.
+fn in_foreground(terminal: RawFd) -> bool {
+    unsafe { libc::tcgetpgrp(terminal) == libc::getpgrp() }
+}
+
+/// Make `pgrp` the foreground process group of `terminal`.
+///
+/// SIGTTOU is blocked meanwhile, since the caller may be in the background.
+/// Async-signal-safe.
+fn set_foreground(terminal: RawFd, pgrp: libc::pid_t) {
+    unsafe {
+        let mut block: libc::sigset_t = std::mem::zeroed();
+        let mut previous: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut block);
+        libc::sigaddset(&mut block, libc::SIGTTOU);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &block, &mut previous);
+        libc::tcsetpgrp(terminal, pgrp);
+        libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut());
+    }
+}
+
+/// Fork the child, into new namespaces with `--isolate`.
+///
+/// # Safety
+/// Same as `fork()`.
+unsafe fn fork_child(config: &ExecConfig<'_>) -> nix::Result<ForkResult> {
+    match config.isolation {
+        Some(isolation) => isolation.clone_child(),
+        None => fork(),
+    }
+}
+
+/// With `--isolate`, sandbox nono itself now that the child has been
+/// started, killing the child if that fails.
+fn restrict_parent_or_kill(child: Pid, config: &ExecConfig<'_>) -> Result<()> {
+    if let Some(isolation) = config.isolation {
+        if let Err(e) = isolation.restrict_parent() {
+            let _ = signal::kill(child, Signal::SIGKILL);
+            let _ = waitpid(child, None);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Answer the exec guard's notifications, reporting blocked execs on nono's
+/// stderr (with `\r\n` line endings on a raw `terminal`) and in the recording.
+///
+/// Must run before [`restrict_parent_or_kill`], see [`ExecGuard`].
+fn start_exec_guard(config: &ExecConfig<'_>, raw_terminal: bool) {
+    if let Some(guard) = config.exec_guard {
+        let recorder = config.recorder.clone();
+        guard.supervise(move |message| {
+            if raw_terminal {
+                eprint!("{}", terminal_lines(&format!("{}\n", message)));
+            } else {
+                eprintln!("{}", message);
+            }
+            if let Some(ref recorder) = recorder {
+                recorder.diagnostic(&terminal_lines(&format!("{}\n", message)));
+            }
+        });
+    }
+}
+
+/// Put the forked child in a process group of its own, so it can be
+/// signalled as a whole, and give it `foreground_terminal` if nono had one.
+///
+/// Async-signal-safe.
+fn enter_process_group(foreground_terminal: Option<RawFd>) {
+    unsafe {
+        libc::setpgid(0, 0);
+        if let Some(fd) = foreground_terminal {
+            set_foreground(fd, libc::getpid());
+        }
+    }
+}
+
+/// Move the forked child into the session cgroup, exiting if that fails.
+///
+/// Async-signal-safe: only calls write() and _exit().
+fn join_cgroup_or_exit(cgroup_procs: Option<RawFd>) {
+    if let Some(fd) = cgroup_procs {
+        // Writing "0" moves the writing process
+        if unsafe { libc::write(fd, b"0".as_ptr().cast(), 1) } != 1 {
+            const MSG: &[u8] = b"nono: failed to join the session cgroup\n";
+            unsafe {
+                libc::write(libc::STDERR_FILENO, MSG.as_ptr().cast(), MSG.len());
+                libc::_exit(126);
+            }
+        }
+    }
+}
+
+/// Apply resource limits in the forked child, exiting if that fails.
+///
+/// Async-signal-safe: only calls setrlimit(), write() and _exit().
+fn apply_limits_or_exit(limits: &PreparedLimits) {
+    if limits.apply().is_err() {
+        const MSG: &[u8] = b"nono: failed to set resource limits\n";
+        unsafe {
+            libc::write(libc::STDERR_FILENO, MSG.as_ptr().cast(), MSG.len());
+            libc::_exit(126);
+        }
+    }
+}
+
+/// Apply platform-specific ptrace hardening and verify it is safe to fork.
+fn harden_and_check_threads(threading: ThreadingContext) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use nix::sys::prctl;
+        if let Err(e) = prctl::set_dumpable(false) {
+            warn!("Failed to set PR_SET_DUMPABLE(0): {}", e);
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        const PT_DENY_ATTACH: libc::c_int = 31;
+        let result =
+            unsafe { libc::ptrace(PT_DENY_ATTACH, 0, std::ptr::null_mut::<libc::c_char>(), 0) };
+        if result != 0 {
+            warn!(
+                "Failed to set PT_DENY_ATTACH: {} (errno: {})",
+                result,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    check_threads(threading)
+}
+
+/// Check that nono is single-threaded, or has only the threads `threading`
+/// allows, before forking a child that runs non-async-signal-safe code.
+pub fn check_threads(threading: ThreadingContext) -> Result<()> {
+    let thread_count = get_thread_count();
+    match (threading, thread_count) {
+        (_, 1) => Ok(()),
+        (ThreadingContext::KeyringExpected, n) if n <= MAX_KEYRING_THREADS => {
+            debug!(
+                "Proceeding with fork despite {} threads (keyring backend threads expected)",
+                n
+            );
+            Ok(())
+        }
+        (ThreadingContext::Strict, n) => Err(NonoError::SandboxInit(format!(
+            "Cannot fork: process has {} threads (expected 1). \
+             This is a bug - fork() requires single-threaded execution.",
+            n
+        ))),
+        (ThreadingContext::KeyringExpected, n) => Err(NonoError::SandboxInit(format!(
+            "Cannot fork: process has {} threads (max {} with keyring). \
+             Unexpected threading detected.",
+            n, MAX_KEYRING_THREADS
+        ))),
+    }
+}
+
 /// Close inherited file descriptors, keeping stdin/stdout/stderr and specified FDs.
 ///
 /// `max_fd` must be computed in the parent before fork (get_max_fd may allocate).
@@ -440,6 +680,332 @@ fn get_max_fd() -> i32 {
     }
 }
 
+/// A pseudo-terminal for the `MonitorPty` strategy.
+///
+/// Must be opened BEFORE the sandbox is applied: Landlock only grants read
+/// access to `/dev/pts`, but descriptors opened beforehand keep their rights.
+pub struct PtyPair {
+    master: OwnedFd,
+    slave: OwnedFd,
+}
+
+/// Allocate a pseudo-terminal with the same settings and size as the
+/// controlling terminal on stdin.
+pub fn open_pty() -> Result<PtyPair> {
+    let termios = termios::tcgetattr(std::io::stdin().as_fd()).ok();
+    let winsize = terminal_size();
+    let pty = nix::pty::openpty(winsize.as_ref(), termios.as_ref())
+        .map_err(|e| NonoError::SandboxInit(format!("openpty() failed: {}", e)))?;
+    Ok(PtyPair {
+        master: pty.master,
+        slave: pty.slave,
+    })
+}
+
+/// Execute a command using the MonitorPty strategy (Monitor mode for interactive apps).
+///
+/// Like [`execute_monitor`], the sandbox is already applied to this process and the
+/// child is forked and waited on. Instead of pipes, the child gets a pseudo-terminal
+/// as its controlling terminal and nono proxies it transparently:
+///
+/// - The real terminal is put in raw mode, so every keystroke (including Ctrl+C)
+///   reaches the command; original settings are restored on exit
+/// - SIGWINCH resizes are copied to the pty, which notifies the command
+/// - When the command stops itself (e.g. Ctrl+Z in vim), nono restores the
+///   terminal and stops too; on `fg` it resumes the command
+/// - The diagnostic footer is printed after the terminal is restored, on
+///   non-zero exit. Output is not scanned for permission errors, since
+///   injecting text would corrupt a full-screen UI
+///
+/// The same single-threaded and async-signal-safety requirements as
+/// [`execute_monitor`] apply.
+pub fn execute_monitor_pty(config: &ExecConfig<'_>, pty: PtyPair) -> Result<i32> {
+    info!(
+        "Executing (monitor, pty): {} {:?}",
+        config.command[0],
+        &config.command[1..]
+    );
+
+    let exec = ExecData::prepare(config)?;
+    let argv_ptrs = exec.argv_ptrs();
+    let envp_ptrs = exec.envp_ptrs();
+
+    harden_and_check_threads(config.threading)?;
+    become_subreaper();
+
+    let master_fd = pty.master.as_raw_fd();
+    let slave_fd = pty.slave.as_raw_fd();
+
+    // Wrap in ManuallyDrop to prevent Drop from running in child
+    let pty = ManuallyDrop::new(pty);
+
+    // Compute max FD in parent (get_max_fd may allocate on Linux)
+    let max_fd = get_max_fd();
+
+    // SAFETY: fork() is safe here because we validated threading context
+    // and child will only use async-signal-safe functions until exec()
+    let fork_result = unsafe { fork_child(config) };
+
+    match fork_result {
+        Ok(ForkResult::Child) => {
+            // CHILD: No allocations allowed from here until exec()
+            join_cgroup_or_exit(exec.cgroup_procs);
+            unsafe {
+                libc::close(master_fd);
+
+                // Start a new session with the pty as controlling terminal, so
+                // terminal signals and job control apply to the command
+                libc::setsid();
+                libc::ioctl(slave_fd, libc::TIOCSCTTY as _, 0);
+
+                libc::dup2(slave_fd, libc::STDIN_FILENO);
+                libc::dup2(slave_fd, libc::STDOUT_FILENO);
+                libc::dup2(slave_fd, libc::STDERR_FILENO);
+                if slave_fd > libc::STDERR_FILENO {
+                    libc::close(slave_fd);
+                }
+            }
+
+            if let Some(isolation) = config.isolation {
+                isolation.enter_or_exit();
+            }
+            if let Some(guard) = config.exec_guard {
+                guard.install();
+            }
+            close_inherited_fds(max_fd, &[]);
+
+            apply_limits_or_exit(&exec.limits);
+
+            // Execute using pre-prepared CStrings (no allocation)
+            unsafe {
+                libc::execve(
+                    exec.program.as_ptr(),
+                    argv_ptrs.as_ptr(),
+                    envp_ptrs.as_ptr(),
+                );
+            }
+
+            // execve only returns on error - exit without cleanup
+            unsafe { libc::_exit(127) }
+        }
+        Ok(ForkResult::Parent { child }) => {
+            // PARENT: Only the child keeps the slave end open, so reading the
+            // master reports EOF once the command (and its children) exit
+            let PtyPair { master, slave } = ManuallyDrop::into_inner(pty);
+            drop(slave);
+            start_exec_guard(config, true);
+            restrict_parent_or_kill(child, config)?;
+
+            execute_parent_pty(child, config, master)
+        }
+        Err(e) => {
+            unsafe {
+                ManuallyDrop::drop(&mut { pty });
+            }
+            Err(NonoError::SandboxInit(format!("fork() failed: {}", e)))
+        }
+    }
+}
+
+/// Parent process in MonitorPty mode: proxy the terminal, wait for child.
+fn execute_parent_pty(child: Pid, config: &ExecConfig<'_>, master: OwnedFd) -> Result<i32> {
+    debug!("Parent proxying pty for child pid {}", child);
+
+    setup_signal_forwarding(child);
+    setup_resize_forwarding(master.as_raw_fd());
+    let master_fd = master.as_raw_fd();
+
+    let raw_mode = RawMode::enter();
+
+    let mut master_in = std::fs::File::from(
+        master
+            .try_clone()
+            .map_err(|e| NonoError::SandboxInit(format!("Failed to clone pty master: {}", e)))?,
+    );
+    let mut master_out = std::fs::File::from(master);
+    let recorder = config.recorder.clone();
+
+    // Keyboard -> command. This thread blocks on stdin and is simply
+    // abandoned when the command exits.
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        if let Err(e) = std::io::copy(&mut stdin, &mut master_in) {
+            debug!("stdin forwarding stopped: {}", e);
+        }
+    });
+
+    // Command -> screen
+    let (done_tx, done_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdout = std::io::stdout();
+        let mut buf = [0u8; 8192];
+        loop {
+            match master_out.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if let Some(ref recorder) = recorder {
+                        recorder.output_bytes(&buf[..n]);
+                    }
+                    if stdout.write_all(&buf[..n]).is_err() || stdout.flush().is_err() {
+                        debug!("Failed to write to stdout");
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                // EIO: every slave descriptor is closed
+                Err(_) => break,
+            }
+        }
+        let _ = done_tx.send(());
+    });
+
+    let watchdog = config
+        .timeout
+        .map(|timeout| Watchdog::start(timeout, child));
+    let status = wait_for_child_pty(child, &raw_mode, master_fd)?;
+    let timed_out = watchdog.and_then(Watchdog::finish);
+    terminate_leftovers(child, config.kill_after, config.cgroup);
+
+    if done_rx
+        .recv_timeout(Duration::from_millis(OUTPUT_DRAIN_TIMEOUT_MS))
+        .is_err()
+    {
+        debug!("pty still held open by background processes, not waiting for more output");
+    }
+    drop(raw_mode);
+
+    if let Some(outcome) = timed_out {
+        if !config.no_diagnostics {
+            print_timeout_footer(config, outcome);
+        }
+        return Ok(TIMEOUT_EXIT_CODE);
+    }
+
+    let exit_code = exit_code_from_status(status);
+
+    if exit_code != 0 && !config.no_diagnostics {
+        print_exit_footer(config, exit_code);
+    }
+
+    Ok(exit_code)
+}
+
+/// Wait for the child in MonitorPty mode, following it through job control stops.
+fn wait_for_child_pty(child: Pid, raw_mode: &RawMode, master_fd: RawFd) -> Result<WaitStatus> {
+    loop {
+        match waitpid(None, Some(WaitPidFlag::WUNTRACED)) {
+            // An orphan reparented to nono (see `become_subreaper`)
+            Ok(status) if status.pid() != Some(child) => continue,
+            Ok(WaitStatus::Stopped(_, signal)) => {
+                debug!("Child stopped by {:?}, suspending nono", signal);
+                // Hand the terminal back to the shell and stop alongside the child
+                raw_mode.restore();
+                let _ = signal::kill(Pid::this(), Signal::SIGSTOP);
+
+                // Resumed (e.g. `fg`): take the terminal back and wake the child
+                raw_mode.activate();
+                sync_window_size(master_fd);
+                let _ = signal::killpg(child, Signal::SIGCONT);
+            }
+            Ok(status) => return Ok(status),
+            Err(nix::errno::Errno::EINTR) => continue,
+            Err(e) => {
+                return Err(NonoError::SandboxInit(format!("waitpid() failed: {}", e)));
+            }
+        }
+    }
+}
+
+/// Raw mode for the terminal on stdin, restoring the original settings on drop.
+struct RawMode {
+    /// Settings before raw mode (`None` if stdin is not a terminal)
+    original: Option<termios::Termios>,
+}
+
+impl RawMode {
+    fn enter() -> Self {
+        let raw_mode = Self {
+            original: termios::tcgetattr(std::io::stdin().as_fd()).ok(),
+        };
+        raw_mode.activate();
+        raw_mode
+    }
+
+    fn activate(&self) {
+        if let Some(ref original) = self.original {
+            let mut raw = original.clone();
+            termios::cfmakeraw(&mut raw);
+            if let Err(e) =
+                termios::tcsetattr(std::io::stdin().as_fd(), termios::SetArg::TCSANOW, &raw)
+            {
+                warn!("Failed to put terminal in raw mode: {}", e);
+            }
+        }
+    }
+
+    fn restore(&self) {
+        if let Some(ref original) = self.original {
+            if let Err(e) =
+                termios::tcsetattr(std::io::stdin().as_fd(), termios::SetArg::TCSANOW, original)
+            {
+                warn!("Failed to restore terminal settings: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+/// Size of the terminal on stdin, if it is one.
+fn terminal_size() -> Option<libc::winsize> {
+    // SAFETY: winsize is plain data and TIOCGWINSZ only writes into it
+    let mut winsize: libc::winsize = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCGWINSZ, &mut winsize) };
+    (ret == 0).then_some(winsize)
+}
+
+/// Copy the terminal size to the pty; the kernel then sends SIGWINCH to the command.
+///
+/// Only uses ioctl(), so it is safe to call from a signal handler.
+fn sync_window_size(master_fd: RawFd) {
+    if master_fd < 0 {
+        return;
+    }
+    // SAFETY: winsize is plain data; both ioctls only read or write it
+    unsafe {
+        let mut winsize: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDIN_FILENO, libc::TIOCGWINSZ, &mut winsize) == 0 {
+            libc::ioctl(master_fd, libc::TIOCSWINSZ, &winsize);
+        }
+    }
+}
+
+/// Forward terminal resizes (SIGWINCH) to the pty.
+///
+/// Uses process-global state for the same reasons as [`setup_signal_forwarding`].
+fn setup_resize_forwarding(master_fd: RawFd) {
+    static PTY_MASTER: AtomicI32 = AtomicI32::new(-1);
+    PTY_MASTER.store(master_fd, Ordering::SeqCst);
+
+    extern "C" fn forward_resize(_sig: libc::c_int) {
+        sync_window_size(PTY_MASTER.load(Ordering::SeqCst));
+    }
+
+    // SAFETY: the handler only calls ioctl(), which is async-signal-safe
+    unsafe {
+        if let Err(e) = signal::signal(
+            Signal::SIGWINCH,
+            signal::SigHandler::Handler(forward_resize),
+        ) {
+            debug!("Failed to install handler for SIGWINCH: {}", e);
+        }
+    }
+}
+
 /// Patterns that indicate a permission error from sandbox restrictions.
 /// These are checked case-insensitively against stderr output.
 const PERMISSION_ERROR_PATTERNS: &[&str] = &[
@@ -457,6 +1023,7 @@ const DIAGNOSTIC_DEBOUNCE_MS: u128 = 2000;
 fn execute_parent_monitor(
     child: Pid,
     config: &ExecConfig<'_>,
+    terminal: Option<RawFd>,
     stdout_pipe: std::fs::File,
     stderr_pipe: std::fs::File,
 ) -> Result<i32> {
@@ -476,6 +1043,10 @@ fn execute_parent_monitor(
     let no_diagnostics = config.no_diagnostics;
     let diag_flag_stdout = Arc::clone(&diagnostic_injected);
     let diag_flag_stderr = Arc::clone(&diagnostic_injected);
+    let recorder_stdout = config.recorder.clone();
+    let recorder_stderr = config.recorder.clone();
+    let (done_stdout, output_done) = mpsc::channel();
+    let done_stderr = done_stdout.clone();
 
     let stdout_handle = std::thread::spawn(move || {
         process_output(
@@ -484,7 +1055,9 @@ fn execute_parent_monitor(
             no_diagnostics,
             false,
             diag_flag_stdout,
+            recorder_stdout,
         );
+        let _ = done_stdout.send(());
     });
 
     let stderr_handle = std::thread::spawn(move || {
@@ -494,22 +1067,181 @@ fn execute_parent_monitor(
             no_diagnostics,
             true,
             diag_flag_stderr,
+            recorder_stderr,
         );
+        let _ = done_stderr.send(());
     });
 
+    let watchdog = config
+        .timeout
+        .map(|timeout| Watchdog::start(timeout, child));
+
     // Wait for child to exit
-    let status = wait_for_child(child)?;
+    let status = wait_for_child(child, terminal)?;
+    let timed_out = watchdog.and_then(Watchdog::finish);
+
+    // Stop background processes the command left behind, which would
+    // otherwise keep the output pipes open
+    terminate_leftovers(child, config.kill_after, config.cgroup);
+
+    // Take the terminal back before printing anything
+    if let Some(fd) = terminal {
+        if unsafe { libc::tcgetpgrp(fd) } == child.as_raw() {
+            set_foreground(fd, unsafe { libc::getpgrp() });
+        }
+    }
 
-    // Wait for output threads to finish (they will exit when pipes close)
-    if let Err(e) = stdout_handle.join() {
-        warn!("stdout processing thread panicked: {:?}", e);
+    // Wait for output threads to finish (they will exit when pipes close).
+    // After a timeout the command has had its grace period: processes that
+    // escaped cleanup (e.g. via `setsid`) may hold the pipes open forever.
+    let drain_deadline =
+        timed_out.map(|_| Instant::now() + Duration::from_millis(OUTPUT_DRAIN_TIMEOUT_MS));
+    if wait_for_output(&output_done, 2, drain_deadline) {
+        if let Err(e) = stdout_handle.join() {
+            warn!("stdout processing thread panicked: {:?}", e);
+        }
+        if let Err(e) = stderr_handle.join() {
+            warn!("stderr processing thread panicked: {:?}", e);
+        }
+    } else {
+        debug!("output still held open by background processes, not waiting for more output");
     }
-    if let Err(e) = stderr_handle.join() {
-        warn!("stderr processing thread panicked: {:?}", e);
+
+    if let Some(outcome) = timed_out {
+        if !config.no_diagnostics {
+            print_timeout_footer(config, outcome);
+        }
+        return Ok(TIMEOUT_EXIT_CODE);
     }
 
-    // Determine exit code
-    let exit_code = match status {
+    let exit_code = exit_code_from_status(status);
+
+    // Print diagnostic footer on non-zero exit if not already injected
+    if exit_code != 0
+        && !config.no_diagnostics
+        && diagnostic_injected
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    {
+        print_exit_footer(config, exit_code);
+    }
+
+    Ok(exit_code)
+}
+
+/// Wait until `threads` output threads have finished, or until `deadline`.
+/// Returns false if the deadline passed first.
+fn wait_for_output(done: &mpsc::Receiver<()>, threads: usize, deadline: Option<Instant>) -> bool {
+    for _ in 0..threads {
+        let finished = match deadline {
+            Some(deadline) => done.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => done
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match finished {
+            Ok(()) => {}
+            // Every thread is gone, some by panicking
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => return false,
+        }
+    }
+    true
+}
+
+/// Print (and record) the diagnostic footer after the child exits.
+fn print_exit_footer(config: &ExecConfig<'_>, exit_code: i32) {
+    let formatter = DiagnosticFormatter::new(config.caps);
+    print_footer(config, &formatter.format_footer(exit_code));
+}
+
+/// Print (and record) the footer explaining that `--timeout` fired.
+fn print_timeout_footer(config: &ExecConfig<'_>, outcome: TimeoutOutcome) {
+    if let Some(timeout) = config.timeout {
+        let killed = outcome == TimeoutOutcome::Killed;
+        let footer =
+            diagnostic::format_timeout_footer(timeout.duration, timeout.kill_after, killed);
+        print_footer(config, &footer);
+    }
+}
+
+fn print_footer(config: &ExecConfig<'_>, footer: &str) {
+    eprintln!("\n{}", footer);
+    if let Some(ref recorder) = config.recorder {
+        recorder.diagnostic(&terminal_lines(&format!("\n{}\n", footer)));
+    }
+}
+
+/// How the command was stopped after `--timeout` fired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeoutOutcome {
+    /// Exited after SIGTERM
+    Terminated,
+    /// Still running after the grace period, sent SIGKILL
+    Killed,
+}
+
+/// Enforces `--timeout` from a background thread while the parent waits.
+struct Watchdog {
+    /// Dropped to tell the thread the command has exited
+    stop: mpsc::Sender<()>,
+    handle: std::thread::JoinHandle<Option<TimeoutOutcome>>,
+}
+
+impl Watchdog {
+    /// Start timing the command running in process group `group`.
+    fn start(timeout: Timeout, group: Pid) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || {
+            let expired = |wait| {
+                matches!(
+                    stopped.recv_timeout(wait),
+                    Err(mpsc::RecvTimeoutError::Timeout)
+                )
+            };
+            if !expired(timeout.duration) {
+                return None;
+            }
+
+            debug!(
+                "Timeout of {:?} reached, terminating {}",
+                timeout.duration, group
+            );
+            let _ = signal::killpg(group, Signal::SIGTERM);
+            // Stopped processes only act on SIGTERM once continued
+            let _ = signal::killpg(group, Signal::SIGCONT);
+            if !expired(timeout.kill_after) {
+                return Some(TimeoutOutcome::Terminated);
+            }
+
+            debug!(
+                "{} still running after {:?}, killing",
+                group, timeout.kill_after
+            );
+            let _ = signal::killpg(group, Signal::SIGKILL);
+            Some(TimeoutOutcome::Killed)
+        });
+        Self { stop, handle }
+    }
+
+    /// Stop the watchdog once the command has exited, reporting whether it fired.
+    fn finish(self) -> Option<TimeoutOutcome> {
+        drop(self.stop);
+        self.handle.join().unwrap_or_else(|e| {
+            warn!("timeout watchdog thread panicked: {:?}", e);
+            None
+        })
+    }
+}
+
+/// Convert line endings to what a terminal displays (`\r\n`), for recordings.
+fn terminal_lines(text: &str) -> String {
+    text.replace('\n', "\r\n")
+}
+
+/// Determine the exit code to report for the child's wait status.
+fn exit_code_from_status(status: WaitStatus) -> i32 {
+    match status {
         WaitStatus::Exited(_, code) => {
             debug!("Child exited with code {}", code);
             code
@@ -523,21 +1255,7 @@ fn execute_parent_monitor(
             warn!("Unexpected wait status: {:?}", other);
             1
         }
-    };
-
-    // Print diagnostic footer on non-zero exit if not already injected
-    if exit_code != 0
-        && !config.no_diagnostics
-        && diagnostic_injected
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_ok()
-    {
-        let formatter = DiagnosticFormatter::new(config.caps);
-        let footer = formatter.format_footer(exit_code);
-        eprintln!("\n{}", footer);
     }
-
-    Ok(exit_code)
 }
 
 /// Process output from the child (stdout or stderr), forwarding and injecting diagnostics.
@@ -551,6 +1269,7 @@ fn process_output(
     no_diagnostics: bool,
     is_stderr: bool,
     diagnostic_injected: Arc<AtomicBool>,
+    recorder: Option<Arc<Recorder>>,
 ) {
     let reader = BufReader::new(pipe);
     let mut stdout = std::io::stdout();
@@ -574,6 +1293,9 @@ fn process_output(
         } else if writeln!(stdout, "{}", line).is_err() {
             debug!("Failed to write to stdout");
         }
+        if let Some(ref recorder) = recorder {
+            recorder.output(&format!("{}\r\n", line));
+        }
 
         // Check for permission error patterns (skip if diagnostics disabled)
         if no_diagnostics {
@@ -603,6 +1325,9 @@ fn process_output(
                     let _ = writeln!(stdout, "{}", footer_line);
                 }
                 let _ = stdout.flush();
+                if let Some(ref recorder) = recorder {
+                    recorder.diagnostic(&terminal_lines(&format!("{}\n", footer)));
+                }
 
                 // Reset the flag after debounce period in a background thread
                 let flag = Arc::clone(&diagnostic_injected);
@@ -617,10 +1342,28 @@ fn process_output(
     }
 }
 
-/// Wait for child process, handling EINTR from signals.
-fn wait_for_child(child: Pid) -> Result<WaitStatus> {
+/// Wait for child process, handling EINTR from signals and reaping orphans.
+///
+/// When the child is stopped (e.g. Ctrl+Z), nono stops as well, so the shell
+/// regains the terminal, and continues the child once resumed.
+fn wait_for_child(child: Pid, terminal: Option<RawFd>) -> Result<WaitStatus> {
     loop {
-        match waitpid(child, Some(WaitPidFlag::empty())) {
+        match waitpid(None, Some(WaitPidFlag::WUNTRACED)) {
+            // An orphan reparented to nono (see `become_subreaper`)
+            Ok(status) if status.pid() != Some(child) => continue,
+            Ok(WaitStatus::Stopped(_, signal)) => {
+                debug!("Child stopped by {:?}, suspending nono", signal);
+                if let Some(fd) = terminal {
+                    set_foreground(fd, unsafe { libc::getpgrp() });
+                }
+                let _ = signal::kill(Pid::this(), Signal::SIGSTOP);
+
+                // Resumed: give the terminal back if we were resumed in the foreground
+                if let Some(fd) = terminal.filter(|&fd| in_foreground(fd)) {
+                    set_foreground(fd, child.as_raw());
+                }
+                let _ = signal::killpg(child, Signal::SIGCONT);
+            }
             Ok(status) => return Ok(status),
             Err(nix::errno::Errno::EINTR) => {
                 // Interrupted by signal, retry
@@ -633,9 +1376,144 @@ fn wait_for_child(child: Pid) -> Result<WaitStatus> {
     }
 }
 
+/// Make nono the reaper of orphaned descendants of the command (Linux).
+///
+/// Processes that outlive their parent, such as daemons that call `setsid`,
+/// are reparented to nono instead of init, so they can be reaped while the
+/// command runs and stopped when it exits.
+fn become_subreaper() {
+    #[cfg(target_os = "linux")]
+    {
+        use nix::sys::prctl;
+        if let Err(e) = prctl::set_child_subreaper(true) {
+            warn!("Failed to set PR_SET_CHILD_SUBREAPER: {}", e);
+        }
+    }
+}
+
+/// Stop the processes the command left behind once it has exited.
+///
+/// The command's process group and nono's remaining children (orphans
+/// reparented by `become_subreaper`, which may have left the group) get
+/// SIGTERM, then SIGKILL if they are still running after `grace`. Neither
+/// PID can be reused while it is being signalled: the group ID stays
+/// allocated while any member is alive, and an orphan stays a zombie until
+/// nono reaps it. On macOS orphans that left the group are not found.
+/// Anything still in the session cgroup is killed last.
+fn terminate_leftovers(group: Pid, grace: Duration, cgroup: Option<&SessionCgroup>) {
+    reap_orphans();
+    let mut terminated = HashSet::new();
+    if terminate_new_leftovers(group, &mut terminated) {
+        debug!("Terminating processes left behind by the command");
+
+        let deadline = Instant::now() + grace;
+        loop {
+            reap_orphans();
+            // Orphans of processes that just exited
+            terminate_new_leftovers(group, &mut terminated);
+            if !signal_leftovers(group, None) {
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    "Processes left behind by the command did not exit within {:?}, killing them",
+                    grace
+                );
+                // Killing a process can orphan its children, so repeat
+                // until none are left
+                for _ in 0..10 {
+                    reap_orphans();
+                    if !signal_leftovers(group, Signal::SIGKILL) {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(LEFTOVER_POLL_MS));
+                }
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(LEFTOVER_POLL_MS));
+        }
+    }
+
+    if let Some(cgroup) = cgroup {
+        if cgroup.is_populated() {
+            debug!("Killing processes left in the session cgroup");
+            cgroup.kill();
+        }
+    }
+    reap_orphans();
+}
+
+/// Send SIGTERM (and SIGCONT, as stopped processes only act on SIGTERM once
+/// continued) to the process group the first time, and to orphans not in
+/// `terminated` yet. Returns whether any leftover was found.
+fn terminate_new_leftovers(group: Pid, terminated: &mut HashSet<Pid>) -> bool {
+    let mut found = false;
+    if terminated.insert(group) && signal::killpg(group, Signal::SIGTERM).is_ok() {
+        let _ = signal::killpg(group, Signal::SIGCONT);
+        found = true;
+    }
+    for pid in remaining_children() {
+        if terminated.insert(pid) && signal::kill(pid, Signal::SIGTERM).is_ok() {
+            let _ = signal::kill(pid, Signal::SIGCONT);
+            found = true;
+        }
+    }
+    found
+}
+
+/// Send `sig` to the command's process group and to nono's remaining
+/// children (`None` only checks for them). Returns whether any still exist.
+fn signal_leftovers(group: Pid, sig: impl Into<Option<Signal>> + Copy) -> bool {
+    let mut found = signal::killpg(group, sig).is_ok();
+    for pid in remaining_children() {
+        found |= signal::kill(pid, sig).is_ok();
+    }
+    found
+}
+
+/// Reap exited orphans without blocking.
+fn reap_orphans() {
+    while let Ok(status) = waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+        if status == WaitStatus::StillAlive {
+            break;
+        }
+    }
+}
+
+/// nono's child processes, i.e. orphans once the command itself has exited.
+fn remaining_children() -> Vec<Pid> {
+    #[cfg(target_os = "linux")]
+    {
+        // Orphans are reparented to one of nono's threads
+        let Ok(tasks) = std::fs::read_dir("/proc/self/task") else {
+            return Vec::new();
+        };
+        tasks
+            .filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("children")).ok())
+            .flat_map(|children| parse_children(&children))
+            .collect()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Vec::new()
+    }
+}
+
+/// The PIDs in a `/proc/<pid>/task/<tid>/children` file.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_children(children: &str) -> Vec<Pid> {
+    children
+        .split_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .map(Pid::from_raw)
+        .collect()
+}
+
 /// Set up signal forwarding from parent to child.
 ///
-/// Signals received by the parent are forwarded to the child process.
+/// Signals received by the parent are forwarded to the child's process group
+/// (the child leads it), so they also reach processes the command started.
 /// This ensures Ctrl+C, SIGTERM, etc. properly reach the sandboxed command.
 ///
 /// # Process-Global State
@@ -676,10 +1554,10 @@ fn setup_signal_forwarding(child: Pid) {
     extern "C" fn forward_signal(sig: libc::c_int) {
         let child_raw = CHILD_PID.load(std::sync::atomic::Ordering::SeqCst);
         if child_raw > 0 {
-            // Forward signal to child
+            // Forward signal to the child's process group
             // SAFETY: kill() is async-signal-safe
             unsafe {
-                libc::kill(child_raw, sig);
+                libc::kill(-child_raw, sig);
             }
         }
     }
@@ -755,6 +1633,16 @@ fn get_thread_count() -> usize {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_children() {
+        let pids: Vec<i32> = parse_children("4242 4243 \n")
+            .into_iter()
+            .map(Pid::as_raw)
+            .collect();
+        assert_eq!(pids, vec![4242, 4243]);
+        assert!(parse_children("").is_empty());
+    }
+
     #[test]
     fn test_exec_strategy_default_is_monitor() {
         assert_eq!(ExecStrategy::default(), ExecStrategy::Monitor);
@@ -0,0 +1,833 @@
+//! Namespace isolation (`--isolate` and `--net`, Linux only)
+//!
+//! Landlock decides what the command may access, but the rest of the
+//! filesystem stays visible by name. With `--isolate`, the command starts in
+//! new user, mount and PID namespaces whose root only contains the system read
+//! paths and the granted paths, bind-mounted read-only or read-write, plus a
+//! fresh `/proc` showing only the sandbox's processes. `/tmp` only holds the
+//! session's private temp directory. Landlock is applied on top with the same
+//! rules as without `--isolate`, so access is unchanged: everything else is
+//! simply not there.
+//!
+//! With `--net none` or `--net loopback`, the command also gets a network
+//! namespace of its own: no interfaces at all, or only `lo`. Unlike
+//! `--net-block`, this holds on any kernel with user namespaces and covers
+//! every kind of socket, not just TCP. Either works without the other, always
+//! in a new user namespace, so neither needs privileges.
+//!
+//! The namespaces are created by the forked child. Everything it needs (the
+//! mount steps as C strings, the uid/gid maps and the Landlock ruleset) is
+//! prepared here, in the parent, so the child only makes raw system calls. The
+//! new root is built on a tmpfs, the same way bubblewrap does it: a staging
+//! tmpfs on `/tmp` becomes the root, with the old root under `/oldroot` as the
+//! source of the bind mounts and the new root assembled under `/newroot`.
+//!
+//! The child stays behind as the PID namespace's init: it forks the command,
+//! reaps orphans, and exits with the command's status. When it exits, the
+//! kernel kills whatever is left in the namespace.
+
+// Only used on Linux
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::capability::{CapabilitySet, FsAccess, NetMode};
+use crate::error::{NonoError, Result};
+use std::collections::BTreeSet;
+use std::ffi::CString;
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+
+/// What a path in the new root is
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Dir,
+    File,
+    /// A symlink, recreated with the same target
+    Symlink(PathBuf),
+}
+
+/// A path that must exist in the new root
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    path: PathBuf,
+    kind: Kind,
+    read_only: bool,
+}
+
+/// One step in building the new root, in host paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Mkdir(PathBuf),
+    /// Create an empty file to bind a file onto
+    Touch(PathBuf),
+    Symlink {
+        path: PathBuf,
+        target: PathBuf,
+    },
+    Bind {
+        path: PathBuf,
+        read_only: bool,
+    },
+    /// Mount a fresh procfs
+    Proc(PathBuf),
+    /// Make the new root's own directories read-only
+    SealRoot,
+}
+
+/// Order the mounts and the directories and files they need.
+///
+/// Entries are merged by path (read-only only if every grant is). A path
+/// already visible through an earlier directory bind is only bound again to
+/// make it writable. `/proc` is always a fresh procfs, so grants under it are
+/// dropped.
+fn layout(mut entries: Vec<Entry>) -> Vec<Action> {
+    let proc_path = Path::new("/proc");
+    entries.retain(|e| !e.path.starts_with(proc_path));
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries.dedup_by(|later, earlier| {
+        if later.path == earlier.path {
+            earlier.read_only &= later.read_only;
+            true
+        } else {
+            false
+        }
+    });
+
+    let mut actions = Vec::new();
+    // Directory binds so far, and whether they are read-only
+    let mut binds: Vec<(PathBuf, bool)> = Vec::new();
+    // Directories created in the new root
+    let mut dirs: BTreeSet<PathBuf> = BTreeSet::from([PathBuf::from("/")]);
+
+    let covering = |binds: &[(PathBuf, bool)], path: &Path| {
+        binds
+            .iter()
+            .rev()
+            .find(|(dir, _)| path.starts_with(dir))
+            .map(|(_, read_only)| *read_only)
+    };
+
+    for entry in entries {
+        match covering(&binds, &entry.path) {
+            Some(covered_read_only) => {
+                if matches!(entry.kind, Kind::Symlink(_)) || !covered_read_only || entry.read_only {
+                    continue;
+                }
+            }
+            None => {
+                let mut ancestors: Vec<&Path> = entry.path.ancestors().skip(1).collect();
+                ancestors.reverse();
+                for ancestor in ancestors {
+                    if dirs.insert(ancestor.to_path_buf()) {
+                        actions.push(Action::Mkdir(ancestor.to_path_buf()));
+                    }
+                }
+                match entry.kind {
+                    Kind::Dir => {
+                        if dirs.insert(entry.path.clone()) {
+                            actions.push(Action::Mkdir(entry.path.clone()));
+                        }
+                    }
+                    Kind::File => actions.push(Action::Touch(entry.path.clone())),
+                    Kind::Symlink(target) => {
+                        actions.push(Action::Symlink {
+                            path: entry.path,
+                            target,
+                        });
+                        continue;
+                    }
+                }
+            }
+        }
+
+        actions.push(Action::Bind {
+            path: entry.path.clone(),
+            read_only: entry.read_only,
+        });
+        if entry.kind == Kind::Dir {
+            binds.push((entry.path, entry.read_only));
+        }
+    }
+
+    let root_bound = binds.iter().any(|(dir, _)| dir == Path::new("/"));
+    if covering(&binds, proc_path).is_none() && dirs.insert(proc_path.to_path_buf()) {
+        actions.push(Action::Mkdir(proc_path.to_path_buf()));
+    }
+    actions.push(Action::Proc(proc_path.to_path_buf()));
+    if !root_bound {
+        actions.push(Action::SealRoot);
+    }
+    actions
+}
+
+/// A system read path as an entry in the new root, if it exists.
+///
+/// Symlinks (e.g. `/bin -> usr/bin`, `/dev/stdin`) are recreated with the same
+/// target rather than bound, like Landlock skips them rather than following.
+fn system_entry(path: &Path) -> Option<Entry> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path).ok()?;
+        let parent = path.parent()?.canonicalize().ok()?;
+        return Some(Entry {
+            path: parent.join(path.file_name()?),
+            kind: Kind::Symlink(target),
+            read_only: true,
+        });
+    }
+    let resolved = path.canonicalize().ok()?;
+    Some(Entry {
+        kind: if resolved.is_dir() {
+            Kind::Dir
+        } else {
+            Kind::File
+        },
+        path: resolved,
+        read_only: true,
+    })
+}
+
+/// An [`Action`] with its paths prepared for the child
+enum Step {
+    Mkdir(CString),
+    Touch(CString),
+    Symlink {
+        path: CString,
+        target: CString,
+    },
+    Bind {
+        source: CString,
+        target: CString,
+        /// Flags for the read-only remount, if read-only
+        remount: Option<nix::libc::c_ulong>,
+    },
+    Proc(CString),
+    SealRoot,
+}
+
+/// Everything the child needs to enter its namespaces.
+pub struct Isolation {
+    /// `clone()` flags for the namespaces
+    clone_flags: nix::libc::c_int,
+    /// Whether to build a new root (`--isolate`)
+    new_root: bool,
+    /// Whether to bring up the loopback interface (`--net loopback`)
+    loopback: bool,
+    steps: Vec<Step>,
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+    /// The command's working directory, if it exists in the new root
+    workdir: CString,
+    /// Landlock ruleset for both nono and the command. The child adds its
+    /// fresh `/proc`, which nono cannot reach, so it does not matter whether
+    /// nono restricts itself before or after that.
+    ruleset: OwnedFd,
+    /// Signal scoping, stacked by the command alone so that nono can still
+    /// signal it from its own Landlock domain
+    signal_scope: Option<OwnedFd>,
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    //! Raw system calls for the child, which must stay async-signal-safe
+
+    use nix::errno::Errno;
+    use nix::libc;
+    use std::ffi::CStr;
+
+    /// `LANDLOCK_RULE_PATH_BENEATH`
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    /// `struct landlock_path_beneath_attr`
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// Report a failed step on stderr and exit.
+    pub fn fail(what: &str, path: &CStr) -> ! {
+        let reason = Errno::last().desc();
+        let parts: [&[u8]; 6] = [
+            b"nono: --isolate: cannot ",
+            what.as_bytes(),
+            b" ",
+            path.to_bytes(),
+            b": ",
+            reason.as_bytes(),
+        ];
+        unsafe {
+            for part in parts {
+                libc::write(libc::STDERR_FILENO, part.as_ptr().cast(), part.len());
+            }
+            libc::write(libc::STDERR_FILENO, b"\n".as_ptr().cast(), 1);
+            libc::_exit(126)
+        }
+    }
+
+    /// Exit through [`fail`] if a call returned -1
+    pub fn check(ret: libc::c_long, what: &str, path: &CStr) {
+        if ret == -1 {
+            fail(what, path);
+        }
+    }
+
+    pub fn write_file(path: &CStr, content: &[u8]) {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            check(fd as libc::c_long, "open", path);
+            if libc::write(fd, content.as_ptr().cast(), content.len()) != content.len() as isize {
+                fail("write", path);
+            }
+            libc::close(fd);
+        }
+    }
+
+    pub fn mount(
+        source: &CStr,
+        target: &CStr,
+        fstype: Option<&CStr>,
+        flags: libc::c_ulong,
+        data: Option<&CStr>,
+    ) {
+        let ret = unsafe {
+            libc::mount(
+                source.as_ptr(),
+                target.as_ptr(),
+                fstype.map_or(std::ptr::null(), CStr::as_ptr),
+                flags,
+                data.map_or(std::ptr::null(), |d| d.as_ptr().cast()),
+            )
+        };
+        check(ret as libc::c_long, "mount", target);
+    }
+
+    pub fn pivot_root(new_root: &CStr, put_old: &CStr) {
+        let ret =
+            unsafe { libc::syscall(libc::SYS_pivot_root, new_root.as_ptr(), put_old.as_ptr()) };
+        check(ret, "pivot_root to", new_root);
+    }
+
+    pub fn add_rule(ruleset: libc::c_int, allowed_access: u64, path: &CStr) {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC);
+            check(fd as libc::c_long, "open", path);
+            let attr = PathBeneathAttr {
+                allowed_access,
+                parent_fd: fd,
+            };
+            let ret = libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset,
+                RULE_PATH_BENEATH,
+                &attr as *const PathBeneathAttr,
+                0,
+            );
+            check(ret, "add a Landlock rule for", path);
+            libc::close(fd);
+        }
+    }
+
+    /// Bring up the loopback interface of a new network namespace
+    pub fn loopback_up() {
+        unsafe {
+            let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+            check(sock as libc::c_long, "open a socket for", c"lo");
+            let mut request: libc::ifreq = std::mem::zeroed();
+            for (dst, src) in request.ifr_name.iter_mut().zip(b"lo") {
+                *dst = *src as libc::c_char;
+            }
+            let ret = libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut request);
+            check(ret as libc::c_long, "read the flags of", c"lo");
+            request.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+            let ret = libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &request);
+            check(ret as libc::c_long, "bring up", c"lo");
+            libc::close(sock);
+        }
+    }
+
+    pub fn restrict_self(ruleset: libc::c_int) -> libc::c_long {
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return -1;
+            }
+            libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Isolation {
+    /// Plan the namespaces for `caps` and build the Landlock ruleset.
+    ///
+    /// Call this INSTEAD of `sandbox::apply()`: nono itself is sandboxed by
+    /// [`Isolation::restrict_parent`] once the child has been started.
+    pub fn prepare(caps: &CapabilitySet) -> Result<Self> {
+        use nix::libc;
+
+        let mut clone_flags = libc::CLONE_NEWUSER | libc::SIGCHLD;
+        if caps.isolate {
+            clone_flags |= libc::CLONE_NEWNS | libc::CLONE_NEWPID;
+        }
+        if caps.net_mode != NetMode::Host {
+            clone_flags |= libc::CLONE_NEWNET;
+        }
+        let steps = if caps.isolate {
+            plan_root(caps)?
+        } else {
+            Vec::new()
+        };
+
+        let workdir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        let uid = nix::unistd::geteuid();
+        let gid = nix::unistd::getegid();
+
+        Ok(Self {
+            clone_flags,
+            new_root: caps.isolate,
+            loopback: caps.net_mode == NetMode::Loopback,
+            steps,
+            uid_map: format!("{} {} 1\n", uid, uid).into_bytes(),
+            gid_map: format!("{} {} 1\n", gid, gid).into_bytes(),
+            workdir: c_path("", &workdir)?,
+            ruleset: crate::sandbox::prepare_ruleset(caps)?,
+            signal_scope: crate::sandbox::prepare_signal_scope(caps)?,
+        })
+    }
+
+    /// Fork the child into its new namespaces.
+    ///
+    /// # Safety
+    /// Same as `fork()`: the child must only call async-signal-safe functions.
+    pub unsafe fn clone_child(&self) -> nix::Result<nix::unistd::ForkResult> {
+        use nix::libc;
+
+        let flags = self.clone_flags as libc::c_ulong;
+        let null = std::ptr::null_mut::<libc::c_void>();
+        // No new stack: like fork(), the child continues on a copy of ours
+        #[cfg(target_arch = "s390x")]
+        let ret = libc::syscall(libc::SYS_clone, null, flags, null, null, null);
+        #[cfg(not(target_arch = "s390x"))]
+        let ret = libc::syscall(libc::SYS_clone, flags, null, null, null, null);
+
+        match ret {
+            -1 => Err(nix::errno::Errno::last()),
+            0 => Ok(nix::unistd::ForkResult::Child),
+            pid => Ok(nix::unistd::ForkResult::Parent {
+                child: nix::unistd::Pid::from_raw(pid as libc::pid_t),
+            }),
+        }
+    }
+
+    /// In the child: set up the namespaces and apply Landlock. With
+    /// `--isolate`, also build the new root, switch to it and become the PID
+    /// namespace's init. Returns in the command's process, or exits on failure.
+    ///
+    /// Async-signal-safe.
+    pub fn enter_or_exit(&self) {
+        use nix::libc;
+        use std::os::fd::AsRawFd;
+
+        unsafe {
+            // The id maps belong to root unless the process is dumpable
+            libc::prctl(libc::PR_SET_DUMPABLE, 1, 0, 0, 0);
+            sys::write_file(c"/proc/self/setgroups", b"deny");
+            sys::write_file(c"/proc/self/uid_map", &self.uid_map);
+            sys::write_file(c"/proc/self/gid_map", &self.gid_map);
+            libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+
+            if self.new_root {
+                self.enter_new_root();
+            }
+            if self.loopback {
+                sys::loopback_up();
+            }
+
+            let ruleset = self.ruleset_fd();
+            if self.new_root {
+                // The fresh /proc is read-only like the host's in the system paths
+                sys::add_rule(ruleset, crate::sandbox::read_access_bits(), c"/proc");
+            }
+            if sys::restrict_self(ruleset) != 0 {
+                sys::fail("apply", c"the Landlock ruleset");
+            }
+            libc::close(ruleset);
+            if let Some(scope) = &self.signal_scope {
+                let scope = scope.as_raw_fd();
+                if sys::restrict_self(scope) != 0 {
+                    sys::fail("apply", c"the Landlock signal scope");
+                }
+                libc::close(scope);
+            }
+
+            if self.new_root {
+                run_init();
+            }
+        }
+    }
+
+    /// Build the new root and switch to it. Async-signal-safe.
+    unsafe fn enter_new_root(&self) {
+        use nix::libc;
+
+        let private = libc::MS_REC | libc::MS_PRIVATE;
+        sys::mount(c"none", c"/", None, private, None);
+
+        // Swap in a staging tmpfs as the root, keeping the old one under
+        // /oldroot as the source of the bind mounts
+        let tmpfs_flags = libc::MS_NOSUID | libc::MS_NODEV;
+        sys::mount(
+            c"tmpfs",
+            c"/tmp",
+            Some(c"tmpfs"),
+            tmpfs_flags,
+            Some(c"mode=0755"),
+        );
+        for dir in [c"/tmp/oldroot", c"/tmp/newroot"] {
+            sys::check(
+                libc::mkdir(dir.as_ptr(), 0o755) as libc::c_long,
+                "create",
+                dir,
+            );
+        }
+        sys::check(
+            libc::chdir(c"/tmp".as_ptr()) as libc::c_long,
+            "enter",
+            c"/tmp",
+        );
+        sys::pivot_root(c".", c"oldroot");
+        libc::chdir(c"/".as_ptr());
+
+        sys::mount(
+            c"tmpfs",
+            c"/newroot",
+            Some(c"tmpfs"),
+            tmpfs_flags,
+            Some(c"mode=0755"),
+        );
+        for step in &self.steps {
+            self.run_step(step);
+        }
+
+        // Drop the old root and switch to the new one
+        let detach = libc::MNT_DETACH;
+        sys::check(
+            libc::umount2(c"/oldroot".as_ptr(), detach) as libc::c_long,
+            "unmount",
+            c"/oldroot",
+        );
+        sys::check(
+            libc::chdir(c"/newroot".as_ptr()) as libc::c_long,
+            "enter",
+            c"/newroot",
+        );
+        sys::pivot_root(c".", c".");
+        sys::check(
+            libc::umount2(c".".as_ptr(), detach) as libc::c_long,
+            "unmount",
+            c"the staging root",
+        );
+        if libc::chdir(self.workdir.as_ptr()) != 0 {
+            libc::chdir(c"/".as_ptr());
+        }
+    }
+
+    /// Sandbox nono itself, once the child has been started.
+    ///
+    /// Must not be called before the child has set up its namespaces:
+    /// Landlock also forbids changing mounts.
+    pub fn restrict_parent(&self) -> Result<()> {
+        if sys::restrict_self(self.ruleset_fd()) != 0 {
+            return Err(NonoError::SandboxInit(format!(
+                "Failed to restrict self: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    fn ruleset_fd(&self) -> nix::libc::c_int {
+        use std::os::fd::AsRawFd;
+        self.ruleset.as_raw_fd()
+    }
+
+    /// Async-signal-safe.
+    unsafe fn run_step(&self, step: &Step) {
+        use nix::libc;
+
+        match step {
+            Step::Mkdir(path) => {
+                if libc::mkdir(path.as_ptr(), 0o755) != 0
+                    && nix::errno::Errno::last() != nix::errno::Errno::EEXIST
+                {
+                    sys::fail("create", path);
+                }
+            }
+            Step::Touch(path) => {
+                let fd = libc::open(
+                    path.as_ptr(),
+                    libc::O_WRONLY | libc::O_CREAT | libc::O_CLOEXEC,
+                    0o644,
+                );
+                sys::check(fd as libc::c_long, "create", path);
+                libc::close(fd);
+            }
+            Step::Symlink { path, target } => {
+                let ret = libc::symlink(target.as_ptr(), path.as_ptr());
+                sys::check(ret as libc::c_long, "create", path);
+            }
+            Step::Bind {
+                source,
+                target,
+                remount,
+            } => {
+                let bind = libc::MS_BIND | libc::MS_REC;
+                sys::mount(source, target, None, bind, None);
+                if let Some(flags) = remount {
+                    let read_only = libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY;
+                    sys::mount(c"none", target, None, read_only | flags, None);
+                }
+            }
+            Step::Proc(path) => {
+                let flags = libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC;
+                sys::mount(c"proc", path, Some(c"proc"), flags, None);
+            }
+            Step::SealRoot => {
+                let flags = libc::MS_REMOUNT
+                    | libc::MS_BIND
+                    | libc::MS_RDONLY
+                    | libc::MS_NOSUID
+                    | libc::MS_NODEV;
+                sys::mount(c"none", c"/newroot", None, flags, None);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Isolation {
+    pub fn prepare(_caps: &CapabilitySet) -> Result<Self> {
+        Err(NonoError::SandboxInit(
+            "--isolate and --net need Linux namespaces and are only supported on Linux".to_string(),
+        ))
+    }
+
+    /// # Safety
+    /// Same as `fork()`.
+    pub unsafe fn clone_child(&self) -> nix::Result<nix::unistd::ForkResult> {
+        nix::unistd::fork()
+    }
+
+    pub fn enter_or_exit(&self) {}
+
+    pub fn restrict_parent(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The steps that build the new root for `--isolate`
+#[cfg(target_os = "linux")]
+fn plan_root(caps: &CapabilitySet) -> Result<Vec<Step>> {
+    let mut entries: Vec<Entry> = crate::config::get_system_read_paths()
+        .iter()
+        .filter_map(|path| system_entry(Path::new(path)))
+        .collect();
+    entries.extend(caps.fs.iter().map(|cap| Entry {
+        path: cap.resolved.clone(),
+        kind: if cap.is_file { Kind::File } else { Kind::Dir },
+        read_only: cap.access == FsAccess::Read,
+    }));
+
+    layout(entries)
+        .into_iter()
+        .map(|action| {
+            Ok(match action {
+                Action::Mkdir(path) => Step::Mkdir(c_path(NEW_ROOT, &path)?),
+                Action::Touch(path) => Step::Touch(c_path(NEW_ROOT, &path)?),
+                Action::Symlink { path, target } => Step::Symlink {
+                    path: c_path(NEW_ROOT, &path)?,
+                    target: c_path("", &target)?,
+                },
+                Action::Bind { path, read_only } => Step::Bind {
+                    source: c_path(OLD_ROOT, &path)?,
+                    target: c_path(NEW_ROOT, &path)?,
+                    remount: if read_only {
+                        Some(readonly_remount_flags(&path)?)
+                    } else {
+                        None
+                    },
+                },
+                Action::Proc(path) => Step::Proc(c_path(NEW_ROOT, &path)?),
+                Action::SealRoot => Step::SealRoot,
+            })
+        })
+        .collect()
+}
+
+/// `prefix` followed by `path` as a C string ("/" adds nothing to a prefix)
+fn c_path(prefix: &str, path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut bytes = prefix.as_bytes().to_vec();
+    if prefix.is_empty() || path != Path::new("/") {
+        bytes.extend_from_slice(path.as_os_str().as_bytes());
+    }
+    CString::new(bytes).map_err(|_| {
+        NonoError::SandboxInit(format!(
+            "--isolate: path contains a NUL byte: {}",
+            path.display()
+        ))
+    })
+}
+
+/// Staging directory of the new root, after the first pivot
+const NEW_ROOT: &str = "/newroot";
+/// The host's root, after the first pivot
+const OLD_ROOT: &str = "/oldroot";
+
+/// Flags for remounting a bind of `path` read-only.
+///
+/// A remount in a user namespace must keep the flags the host mount was
+/// locked with (nosuid, nodev, noexec, atime), so they are read from the
+/// host mount beforehand.
+#[cfg(target_os = "linux")]
+fn readonly_remount_flags(path: &Path) -> Result<nix::libc::c_ulong> {
+    use nix::libc;
+    use nix::sys::statvfs::{statvfs, FsFlags};
+
+    let stat = statvfs(path).map_err(|e| {
+        NonoError::SandboxInit(format!("--isolate: cannot stat {}: {}", path.display(), e))
+    })?;
+    let flags = stat.flags();
+    let kept = [
+        (FsFlags::ST_NOSUID, libc::MS_NOSUID),
+        (FsFlags::ST_NODEV, libc::MS_NODEV),
+        (FsFlags::ST_NOEXEC, libc::MS_NOEXEC),
+        (FsFlags::ST_NOATIME, libc::MS_NOATIME),
+        (FsFlags::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (FsFlags::ST_RELATIME, libc::MS_RELATIME),
+    ];
+    Ok(kept
+        .iter()
+        .filter(|(st, _)| flags.contains(*st))
+        .fold(0, |acc, (_, ms)| acc | ms))
+}
+
+/// Run as the PID namespace's init: fork the command, which returns, then
+/// reap processes until it exits and exit with its status.
+///
+/// Job-control stops are not passed on to nono: a stopped command is
+/// resumed. Async-signal-safe.
+#[cfg(target_os = "linux")]
+unsafe fn run_init() {
+    use nix::libc;
+
+    let command = libc::fork();
+    if command == 0 {
+        return;
+    }
+    if command < 0 {
+        sys::fail("start", c"the command");
+    }
+
+    loop {
+        let mut status = 0;
+        let pid = libc::waitpid(-1, &mut status, libc::WUNTRACED);
+        if pid < 0 {
+            if nix::errno::Errno::last() == nix::errno::Errno::EINTR {
+                continue;
+            }
+            libc::_exit(126);
+        }
+        if pid != command {
+            continue;
+        }
+        if libc::WIFSTOPPED(status) {
+            libc::kill(command, libc::SIGCONT);
+        } else if libc::WIFEXITED(status) {
+            libc::_exit(libc::WEXITSTATUS(status));
+        } else if libc::WIFSIGNALED(status) {
+            libc::_exit(128 + libc::WTERMSIG(status));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, kind: Kind, read_only: bool) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            kind,
+            read_only,
+        }
+    }
+
+    fn mkdir(path: &str) -> Action {
+        Action::Mkdir(PathBuf::from(path))
+    }
+
+    fn bind(path: &str, read_only: bool) -> Action {
+        Action::Bind {
+            path: PathBuf::from(path),
+            read_only,
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let actions = layout(vec![
+            entry("/usr/lib", Kind::Dir, true),
+            entry("/usr", Kind::Dir, true),
+            entry("/bin", Kind::Symlink(PathBuf::from("usr/bin")), true),
+            entry("/usr/bin", Kind::Symlink(PathBuf::from("x")), true),
+            entry("/etc/hosts", Kind::File, true),
+            entry("/proc", Kind::Dir, true),
+            entry("/proc/self", Kind::Dir, false),
+            entry("/home/u/proj", Kind::Dir, false),
+            entry("/home/u/proj/.git", Kind::Dir, true),
+            entry("/home/u", Kind::Dir, true),
+            entry("/home/u/notes", Kind::File, false),
+            entry("/home/u/notes", Kind::File, true),
+        ]);
+
+        assert_eq!(
+            actions,
+            vec![
+                Action::Symlink {
+                    path: PathBuf::from("/bin"),
+                    target: PathBuf::from("usr/bin"),
+                },
+                mkdir("/etc"),
+                Action::Touch(PathBuf::from("/etc/hosts")),
+                bind("/etc/hosts", true),
+                mkdir("/home"),
+                mkdir("/home/u"),
+                bind("/home/u", true),
+                // Writable below a read-only bind: bound again
+                bind("/home/u/notes", false),
+                // (/home/u/proj/.git, read-only below a writable bind, and
+                // /usr/lib and /usr/bin below /usr are already visible)
+                bind("/home/u/proj", false),
+                mkdir("/usr"),
+                bind("/usr", true),
+                mkdir("/proc"),
+                Action::Proc(PathBuf::from("/proc")),
+                Action::SealRoot,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_whole_root() {
+        let actions = layout(vec![
+            entry("/", Kind::Dir, false),
+            entry("/etc", Kind::Dir, true),
+        ]);
+
+        assert_eq!(
+            actions,
+            vec![bind("/", false), Action::Proc(PathBuf::from("/proc"))]
+        );
+    }
+}
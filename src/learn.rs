@@ -1,28 +1,64 @@
-//! Learn mode: trace file accesses to discover required paths
+//! Learn mode: trace file and network accesses to discover required paths
 //!
-//! Uses strace to monitor a command's file system accesses and produces
-//! a list of paths that would need to be allowed in a nono profile.
+//! Uses strace to monitor a command's file system and network accesses and
+//! produces a list of paths and destinations that would need to be allowed
+//! in a nono profile.
 
+use crate::capability::CapabilitySet;
 use crate::cli::LearnArgs;
 use crate::error::{NonoError, Result};
-use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Value};
 
 #[cfg(target_os = "linux")]
 use crate::config;
 #[cfg(target_os = "linux")]
+use crate::exec_strategy::{self, ThreadingContext};
+#[cfg(target_os = "linux")]
 use crate::profile::{self, Profile};
 #[cfg(target_os = "linux")]
-use std::collections::HashSet;
+use crate::sandbox;
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
 #[cfg(target_os = "linux")]
 use std::io::{BufRead, BufReader};
 #[cfg(target_os = "linux")]
-use std::path::Path;
+use std::os::unix::process::CommandExt;
 #[cfg(target_os = "linux")]
 use std::process::{Command, Stdio};
 #[cfg(target_os = "linux")]
 use tracing::{debug, info, warn};
 
+/// A network endpoint observed during tracing
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NetworkEndpoint {
+    /// IP address that was connected to or bound
+    pub addr: IpAddr,
+    /// Port number
+    pub port: u16,
+    /// DNS name queried by the traced process that resolves to `addr`
+    pub host: Option<String>,
+}
+
+impl NetworkEndpoint {
+    /// Host name if one was resolved, otherwise the IP address
+    pub fn host_label(&self) -> String {
+        self.host.clone().unwrap_or_else(|| self.addr.to_string())
+    }
+}
+
+impl std::fmt::Display for NetworkEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let socket = SocketAddr::new(self.addr, self.port);
+        match self.host {
+            Some(ref host) => write!(f, "{}:{} ({})", host, self.port, socket),
+            None => write!(f, "{}", socket),
+        }
+    }
+}
+
 /// Result of learning file access patterns
 #[derive(Debug)]
 pub struct LearnResult {
@@ -36,6 +72,18 @@ pub struct LearnResult {
     pub system_covered: BTreeSet<PathBuf>,
     /// Paths that were accessed but are already covered by profile
     pub profile_covered: BTreeSet<PathBuf>,
+    /// Remote endpoints the command connected to
+    pub connect_endpoints: BTreeSet<NetworkEndpoint>,
+    /// Local endpoints the command bound (listening sockets)
+    pub bind_endpoints: BTreeSet<NetworkEndpoint>,
+    /// DNS names the command looked up
+    pub dns_queries: BTreeSet<String>,
+    /// Programs executed by the traced process tree
+    pub executed: BTreeSet<PathBuf>,
+    /// Executed commands that nono blocks by default (need `allow_command`)
+    pub blocked_commands: BTreeSet<String>,
+    /// Variables used to write paths portably in profile output
+    pub path_vars: PathVariables,
 }
 
 impl LearnResult {
@@ -47,6 +95,12 @@ impl LearnResult {
             readwrite_paths: BTreeSet::new(),
             system_covered: BTreeSet::new(),
             profile_covered: BTreeSet::new(),
+            connect_endpoints: BTreeSet::new(),
+            bind_endpoints: BTreeSet::new(),
+            dns_queries: BTreeSet::new(),
+            executed: BTreeSet::new(),
+            blocked_commands: BTreeSet::new(),
+            path_vars: PathVariables::default(),
         }
     }
 
@@ -57,39 +111,80 @@ impl LearnResult {
             || !self.readwrite_paths.is_empty()
     }
 
+    /// Check if any network activity was observed
+    pub fn has_network(&self) -> bool {
+        !self.connect_endpoints.is_empty()
+            || !self.bind_endpoints.is_empty()
+            || !self.dns_queries.is_empty()
+    }
+
+    /// Hosts for `[network.observed]`: names (or IPs) of connections plus
+    /// every DNS name that was looked up
+    pub fn suggested_hosts(&self) -> BTreeSet<String> {
+        self.connect_endpoints
+            .iter()
+            .map(NetworkEndpoint::host_label)
+            .chain(self.dns_queries.iter().cloned())
+            .collect()
+    }
+
     /// Format as TOML fragment for profile
     pub fn to_toml(&self) -> String {
         let mut lines = Vec::new();
         lines.push("[filesystem]".to_string());
+        push_toml_list(
+            &mut lines,
+            "allow",
+            self.readwrite_paths
+                .iter()
+                .map(|p| self.path_vars.abstract_path(p)),
+        );
+        push_toml_list(
+            &mut lines,
+            "read",
+            self.read_paths
+                .iter()
+                .map(|p| self.path_vars.abstract_path(p)),
+        );
+        push_toml_list(
+            &mut lines,
+            "write",
+            self.write_paths
+                .iter()
+                .map(|p| self.path_vars.abstract_path(p)),
+        );
 
-        if !self.readwrite_paths.is_empty() {
-            lines.push("allow = [".to_string());
-            for path in &self.readwrite_paths {
-                lines.push(format!("    \"{}\",", path.display()));
-            }
-            lines.push("]".to_string());
-        } else {
-            lines.push("allow = []".to_string());
-        }
-
-        if !self.read_paths.is_empty() {
-            lines.push("read = [".to_string());
-            for path in &self.read_paths {
-                lines.push(format!("    \"{}\",", path.display()));
-            }
-            lines.push("]".to_string());
-        } else {
-            lines.push("read = []".to_string());
+        lines.push(String::new());
+        lines.push("[commands]".to_string());
+        if !self.blocked_commands.is_empty() {
+            lines.push("# Executed commands that are blocked by default".to_string());
         }
-
-        if !self.write_paths.is_empty() {
-            lines.push("write = [".to_string());
-            for path in &self.write_paths {
-                lines.push(format!("    \"{}\",", path.display()));
-            }
-            lines.push("]".to_string());
+        push_toml_list(&mut lines, "allow", &self.blocked_commands);
+
+        lines.push(String::new());
+        lines.push("[network]".to_string());
+        if self.has_network() {
+            let connect_ports: BTreeSet<u16> =
+                self.connect_endpoints.iter().map(|e| e.port).collect();
+            let listen_ports: BTreeSet<u16> = self.bind_endpoints.iter().map(|e| e.port).collect();
+
+            lines.push("block = false".to_string());
+            lines.push(String::new());
+            lines.push("[network.observed]".to_string());
+            lines
+                .push("# Advisory: nono enforces network on/off only, not these lists".to_string());
+            push_toml_list(&mut lines, "hosts", self.suggested_hosts());
+            lines.push(format!(
+                "connect_ports = {}",
+                format_port_list(&connect_ports)
+            ));
+            lines.push(format!(
+                "listen_ports = {}",
+                format_port_list(&listen_ports)
+            ));
         } else {
-            lines.push("write = []".to_string());
+            lines.push("# No network activity observed".to_string());
+            lines.push("block = true".to_string());
         }
 
         lines.join("\n")
@@ -120,6 +215,53 @@ impl LearnResult {
             }
         }
 
+        if !self.executed.is_empty() {
+            lines.push("Programs executed:".to_string());
+            for path in &self.executed {
+                lines.push(format!("  {}", path.display()));
+            }
+        }
+
+        if !self.blocked_commands.is_empty() {
+            lines.push("Blocked commands executed (need --allow-command):".to_string());
+            for command in &self.blocked_commands {
+                lines.push(format!("  {}", command));
+            }
+        }
+
+        if !self.connect_endpoints.is_empty() {
+            lines.push("Network connections:".to_string());
+            for endpoint in &self.connect_endpoints {
+                lines.push(format!("  {}", endpoint));
+            }
+        }
+
+        if !self.bind_endpoints.is_empty() {
+            lines.push("Listening sockets:".to_string());
+            for endpoint in &self.bind_endpoints {
+                lines.push(format!("  {}", endpoint));
+            }
+        }
+
+        // DNS names that never led to an observed connection are still worth
+        // reviewing (e.g. lookups whose connection happened after a timeout)
+        let connected_hosts: BTreeSet<&str> = self
+            .connect_endpoints
+            .iter()
+            .filter_map(|e| e.host.as_deref())
+            .collect();
+        let unmatched_queries: Vec<&String> = self
+            .dns_queries
+            .iter()
+            .filter(|q| !connected_hosts.contains(q.as_str()))
+            .collect();
+        if !unmatched_queries.is_empty() {
+            lines.push("DNS lookups:".to_string());
+            for query in unmatched_queries {
+                lines.push(format!("  {}", query));
+            }
+        }
+
         if !self.system_covered.is_empty() {
             lines.push(format!(
                 "\n({} paths already covered by system defaults)",
@@ -142,6 +284,350 @@ impl LearnResult {
     }
 }
 
+/// Profile variables (see `profile::expand_vars`) and the values they expand
+/// to on this machine, used to write learned paths back in `$VAR` form
+#[derive(Debug, Clone, Default)]
+pub struct PathVariables {
+    /// Directory variables and their canonical paths
+    dirs: Vec<(&'static str, PathBuf)>,
+    /// Current user ID, substituted as `$UID` in per-user locations
+    uid: Option<String>,
+}
+
+impl PathVariables {
+    /// Resolve the variables for the current user and working directory
+    pub fn detect(workdir: &Path) -> Self {
+        // No `$TMPDIR`: in a profile it is the session's private temp
+        // directory, not the temp directory the command was traced with
+        let mut dirs = vec![("$WORKDIR", workdir.to_path_buf())];
+        if let Some(home) = xdg_home::home_dir() {
+            dirs.push((
+                "$XDG_CONFIG_HOME",
+                std::env::var("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| home.join(".config")),
+            ));
+            dirs.push((
+                "$XDG_DATA_HOME",
+                std::env::var("XDG_DATA_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| home.join(".local").join("share")),
+            ));
+            dirs.push(("$HOME", home));
+        }
+
+        // Learned paths are canonical, so compare against canonical bases too
+        let dirs = dirs
+            .into_iter()
+            .map(|(var, dir)| (var, dir.canonicalize().unwrap_or(dir)))
+            .collect();
+
+        Self {
+            dirs,
+            uid: Some(nix::unistd::getuid().to_string()),
+        }
+    }
+
+    /// Rewrite a path using the most specific directory variable that
+    /// contains it, and `$UID` for the user ID in a per-user location
+    /// (see [`Self::uid_component`])
+    pub fn abstract_path(&self, path: &Path) -> String {
+        let best = self
+            .dirs
+            .iter()
+            .filter(|(_, dir)| dir.components().count() > 1 && path.starts_with(dir))
+            .max_by_key(|(_, dir)| dir.components().count());
+
+        let (prefix, rest) = match best
+            .and_then(|(var, dir)| path.strip_prefix(dir).ok().map(|rest| (*var, rest)))
+        {
+            Some((var, rest)) => (var.to_string(), rest),
+            None => (String::new(), path),
+        };
+
+        let uid_index = self.uid_component(path);
+        let skipped = path.components().count() - rest.components().count();
+
+        let mut out = prefix;
+        for (i, component) in rest.components().enumerate() {
+            if component == Component::RootDir {
+                out.push('/');
+                continue;
+            }
+            if !out.is_empty() && !out.ends_with('/') {
+                out.push('/');
+            }
+            let name = component.as_os_str().to_string_lossy();
+            match self.uid.as_deref() {
+                Some(uid) if uid_index == Some(skipped + i) => {
+                    out.push_str(name.strip_suffix(uid).unwrap_or(&name));
+                    out.push_str("$UID");
+                }
+                _ => out.push_str(&name),
+            }
+        }
+        out
+    }
+
+    /// Index of the path component that ends in the user ID, if the path is
+    /// in a location named after the user: `/run/user/<uid>` or
+    /// `/tmp/<name>-<uid>` (e.g. `/tmp/tmux-1000`).
+    ///
+    /// A component that merely equals the user ID elsewhere is left alone:
+    /// for root that would turn `/dev/pts/0` into `/dev/pts/$UID`.
+    fn uid_component(&self, path: &Path) -> Option<usize> {
+        let uid = self.uid.as_deref()?;
+        let names: Vec<_> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+
+        match names.as_slice() {
+            [root, run, user, id, ..]
+                if root == "/" && run == "run" && user == "user" && id == uid =>
+            {
+                Some(3)
+            }
+            [root, tmp, name, ..]
+                if root == "/"
+                    && tmp == "tmp"
+                    && name
+                        .strip_suffix(uid)
+                        .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('-')) =>
+            {
+                Some(2)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Merge learned accesses into the text of an existing profile.
+///
+/// New entries are appended to the matching lists (`[filesystem]`,
+/// `[commands]`, `[network.observed]`); entries already present are left alone, as are
+/// comments and formatting. Paths are written in `$VAR` form where possible
+/// (see [`PathVariables`]).
+pub fn merge_into_profile(content: &str, result: &LearnResult) -> Result<String> {
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e| NonoError::ProfileParse(format!("{}", e)))?;
+
+    let path_values = |paths: &BTreeSet<PathBuf>| -> Vec<Value> {
+        paths
+            .iter()
+            .map(|p| Value::from(result.path_vars.abstract_path(p)))
+            .collect()
+    };
+
+    merge_list(
+        &mut doc,
+        "filesystem",
+        "allow",
+        path_values(&result.readwrite_paths),
+        true,
+    )?;
+    merge_list(
+        &mut doc,
+        "filesystem",
+        "read",
+        path_values(&result.read_paths),
+        true,
+    )?;
+    merge_list(
+        &mut doc,
+        "filesystem",
+        "write",
+        path_values(&result.write_paths),
+        true,
+    )?;
+    merge_list(
+        &mut doc,
+        "commands",
+        "allow",
+        result
+            .blocked_commands
+            .iter()
+            .map(|c| Value::from(c.as_str()))
+            .collect(),
+        true,
+    )?;
+
+    if result.has_network() {
+        let connect_ports: BTreeSet<u16> =
+            result.connect_endpoints.iter().map(|e| e.port).collect();
+        let listen_ports: BTreeSet<u16> = result.bind_endpoints.iter().map(|e| e.port).collect();
+
+        merge_list(
+            &mut doc,
+            "network.observed",
+            "hosts",
+            result
+                .suggested_hosts()
+                .into_iter()
+                .map(Value::from)
+                .collect(),
+            true,
+        )?;
+        merge_list(
+            &mut doc,
+            "network.observed",
+            "connect_ports",
+            connect_ports
+                .into_iter()
+                .map(|p| Value::from(i64::from(p)))
+                .collect(),
+            false,
+        )?;
+        merge_list(
+            &mut doc,
+            "network.observed",
+            "listen_ports",
+            listen_ports
+                .into_iter()
+                .map(|p| Value::from(i64::from(p)))
+                .collect(),
+            false,
+        )?;
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Append values missing from `[table] key = [...]`, creating either as needed.
+///
+/// `table` may be a dotted path such as `network.observed`.
+///
+/// New entries follow the layout of the existing array: one per line if the
+/// array is already multi-line (or `multiline` is set and it is empty),
+/// otherwise inline.
+fn merge_list(
+    doc: &mut DocumentMut,
+    table: &str,
+    key: &str,
+    values: Vec<Value>,
+    multiline: bool,
+) -> Result<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+
+    let not_table = || NonoError::ProfileParse(format!("'{}' must be a table", table));
+    let not_array = || NonoError::ProfileParse(format!("'{}.{}' must be an array", table, key));
+    let mut item = doc.as_item_mut();
+    for name in table.split('.') {
+        item = item
+            .as_table_like_mut()
+            .ok_or_else(not_table)?
+            .entry(name)
+            .or_insert(toml_edit::table());
+    }
+    let array = item
+        .as_table_like_mut()
+        .ok_or_else(not_table)?
+        .entry(key)
+        .or_insert(toml_edit::value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(not_array)?;
+
+    let existing: HashSet<String> = array.iter().filter_map(value_key).collect();
+    let missing: Vec<Value> = values
+        .into_iter()
+        .filter(|v| value_key(v).is_some_and(|k| !existing.contains(&k)))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let was_empty = array.is_empty();
+    // Reuse the indentation of existing entries when the array is multi-line
+    let indent = array
+        .iter()
+        .find_map(|v| v.decor().prefix().and_then(|p| p.as_str()))
+        .filter(|p| p.contains('\n'))
+        .map(str::to_string)
+        .or_else(|| (was_empty && multiline).then(|| "\n    ".to_string()));
+
+    let Some(indent) = indent else {
+        for value in missing {
+            array.push(value);
+        }
+        return Ok(());
+    };
+
+    // Whatever follows the last entry's comma on its line (typically an
+    // end-of-line comment) belongs to that entry, so it has to stay in front
+    // of the first new entry rather than drifting to the end of the array
+    if !array.trailing_comma() {
+        if let Some(last) = array.iter_mut().last() {
+            let suffix = raw_str(last.decor().suffix()).to_string();
+            last.decor_mut().set_suffix("");
+            array.set_trailing(suffix);
+        }
+    }
+    let trailing = raw_str(Some(array.trailing())).to_string();
+    let (first_line, rest) = match trailing.find('\n') {
+        Some(i) => trailing.split_at(i + 1),
+        None => ("", trailing.as_str()),
+    };
+
+    for (i, mut value) in missing.into_iter().enumerate() {
+        if i == 0 && !first_line.is_empty() {
+            let prefix = format!("{}{}", first_line, indent.trim_start_matches('\n'));
+            value.decor_mut().set_prefix(prefix);
+        } else {
+            value.decor_mut().set_prefix(indent.as_str());
+        }
+        array.push_formatted(value);
+    }
+
+    array.set_trailing_comma(true);
+    // Without a line break the remainder is just spacing before `]`
+    let rest = if first_line.is_empty() { "" } else { rest };
+    array.set_trailing(format!("\n{}", rest));
+
+    Ok(())
+}
+
+/// Text of a raw TOML decor string (empty if absent or not representable)
+fn raw_str(raw: Option<&toml_edit::RawString>) -> &str {
+    raw.and_then(|r| r.as_str()).unwrap_or("")
+}
+
+/// Comparable key for a string or integer array element
+fn value_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.value().clone()),
+        Value::Integer(i) => Some(i.value().to_string()),
+        _ => None,
+    }
+}
+
+/// Append a TOML array of strings as `key = [...]`, one entry per line
+fn push_toml_list<T: std::fmt::Display>(
+    lines: &mut Vec<String>,
+    key: &str,
+    values: impl IntoIterator<Item = T>,
+) {
+    let mut values = values.into_iter().peekable();
+    if values.peek().is_none() {
+        lines.push(format!("{} = []", key));
+        return;
+    }
+
+    lines.push(format!("{} = [", key));
+    for value in values {
+        lines.push(format!("    \"{}\",", value));
+    }
+    lines.push("]".to_string());
+}
+
+/// Format a set of ports as an inline TOML array
+fn format_port_list(ports: &BTreeSet<u16>) -> String {
+    let ports: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+    format!("[{}]", ports.join(", "))
+}
+
 /// Check if strace is available
 #[cfg(target_os = "linux")]
 fn check_strace() -> Result<()> {
@@ -155,15 +641,24 @@ fn check_strace() -> Result<()> {
 
 /// Run learn mode (non-Linux stub)
 #[cfg(not(target_os = "linux"))]
-pub fn run_learn(_args: &LearnArgs) -> Result<LearnResult> {
+pub fn run_learn(
+    _args: &LearnArgs,
+    _sandbox: Option<&CapabilitySet>,
+    _tmpdir: Option<&Path>,
+) -> Result<LearnResult> {
     Err(NonoError::LearnError(
         "nono learn is only available on Linux (requires strace)".to_string(),
     ))
 }
 
-/// Run learn mode (Linux implementation)
+/// Run learn mode (Linux implementation). With `--sandboxed`, `sandbox` is
+/// the profile's prepared sandbox and `tmpdir` its session temp directory.
 #[cfg(target_os = "linux")]
-pub fn run_learn(args: &LearnArgs) -> Result<LearnResult> {
+pub fn run_learn(
+    args: &LearnArgs,
+    sandbox: Option<&CapabilitySet>,
+    tmpdir: Option<&Path>,
+) -> Result<LearnResult> {
     check_strace()?;
 
     // Load profile if specified
@@ -173,11 +668,26 @@ pub fn run_learn(args: &LearnArgs) -> Result<LearnResult> {
         None
     };
 
+    let workdir = std::env::current_dir().map_err(NonoError::CommandExecution)?;
+
     // Run strace and collect paths
-    let raw_accesses = run_strace(&args.command, args.timeout)?;
+    let events = run_strace(&args.command, args.timeout, sandbox, tmpdir)?;
+
+    // Attribute connections to the names the command's own lookups returned
+    let resolved = dns_answers(&events);
 
     // Process and categorize paths
-    let result = process_accesses(raw_accesses, profile.as_ref(), args.all)?;
+    let mut result = process_accesses(
+        events,
+        profile.as_ref(),
+        args.all,
+        &resolved,
+        &workdir,
+        tmpdir,
+    )?;
+
+    // Profile output uses $VAR forms relative to the traced working directory
+    result.path_vars = PathVariables::detect(&workdir);
 
     Ok(result)
 }
@@ -188,11 +698,48 @@ pub fn run_learn(args: &LearnArgs) -> Result<LearnResult> {
 struct FileAccess {
     path: PathBuf,
     is_write: bool,
+    /// The access was denied by the sandbox (`--sandboxed` only)
+    sandbox_denied: bool,
+}
+
+/// An event of interest extracted from strace output
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone)]
+enum TraceEvent {
+    /// A filesystem access
+    File(FileAccess),
+    /// An outbound connection (connect)
+    Connect(SocketAddr),
+    /// A listening socket (bind)
+    Bind(SocketAddr),
+    /// A DNS query for the given name
+    DnsQuery(String),
+    /// A DNS response: the queried name and the addresses it resolved to
+    DnsAnswer(String, Vec<IpAddr>),
+    /// A program execution (execve)
+    Exec(PathBuf),
 }
 
-/// Run strace on the command and collect file accesses
+/// Socket address parsed from a strace sockaddr structure
 #[cfg(target_os = "linux")]
-fn run_strace(command: &[String], timeout: Option<u64>) -> Result<Vec<FileAccess>> {
+#[derive(Debug, Clone, PartialEq)]
+enum SockAddr {
+    Inet(SocketAddr),
+    Unix(PathBuf),
+}
+
+/// Run strace on the command and collect file and network accesses.
+///
+/// With `sandbox`, strace and the command run under those capabilities and
+/// only the filesystem and network accesses the sandbox denied are collected.
+/// `tmpdir` becomes the command's `$TMPDIR`.
+#[cfg(target_os = "linux")]
+fn run_strace(
+    command: &[String],
+    timeout: Option<u64>,
+    sandbox: Option<&CapabilitySet>,
+    tmpdir: Option<&Path>,
+) -> Result<Vec<TraceEvent>> {
     use std::time::{Duration, Instant};
 
     if command.is_empty() {
@@ -202,7 +749,11 @@ fn run_strace(command: &[String], timeout: Option<u64>) -> Result<Vec<FileAccess
     let mut strace_args = vec![
         "-f".to_string(), // Follow forks
         "-e".to_string(), // Trace these syscalls
-        "openat,open,access,stat,lstat,readlink,execve,creat,mkdir,rename,unlink".to_string(),
+        "openat,open,access,stat,lstat,readlink,execve,creat,mkdir,rename,unlink,\
+         connect,bind,sendto,sendmsg,sendmmsg,recvfrom,recvmsg,recvmmsg"
+            .to_string(),
+        "-s".to_string(), // Long enough to capture DNS messages over UDP
+        "512".to_string(),
         "-o".to_string(),
         "/dev/stderr".to_string(), // Output to stderr so we can capture it
         "--".to_string(),
@@ -211,10 +762,32 @@ fn run_strace(command: &[String], timeout: Option<u64>) -> Result<Vec<FileAccess
 
     info!("Running strace with args: {:?}", strace_args);
 
-    let mut child = Command::new("strace")
+    let mut strace = Command::new("strace");
+    strace
         .args(&strace_args)
         .stdout(Stdio::inherit()) // Let command output go to terminal
-        .stderr(Stdio::piped()) // Capture strace output
+        .stderr(Stdio::piped()); // Capture strace output
+    if let Some(dir) = tmpdir {
+        strace.env("TMPDIR", dir);
+    }
+
+    // Sandbox only the strace child, so this process can still update the
+    // profile afterwards. Landlock lets strace trace the
+    // command because both run in the same domain.
+    if let Some(caps) = sandbox {
+        let caps = caps.clone();
+        exec_strategy::check_threads(ThreadingContext::Strict)?;
+        // SAFETY: nono is single-threaded (checked above), so the allocations
+        // made while building the ruleset in the forked child cannot deadlock
+        // on a lock held by another thread.
+        unsafe {
+            strace.pre_exec(move || {
+                sandbox::apply(&caps).map_err(|e| std::io::Error::other(e.to_string()))
+            });
+        }
+    }
+
+    let mut child = strace
         .spawn()
         .map_err(|e| NonoError::LearnError(format!("Failed to spawn strace: {}", e)))?;
 
@@ -226,7 +799,7 @@ fn run_strace(command: &[String], timeout: Option<u64>) -> Result<Vec<FileAccess
     let start = Instant::now();
     let timeout_duration = timeout.map(Duration::from_secs);
 
-    let mut accesses = Vec::new();
+    let mut events = Vec::new();
     let reader = BufReader::new(stderr);
 
     for line in reader.lines() {
@@ -247,16 +820,51 @@ fn run_strace(command: &[String], timeout: Option<u64>) -> Result<Vec<FileAccess
             }
         };
 
-        // Parse strace output
-        if let Some(access) = parse_strace_line(&line) {
-            accesses.push(access);
+        // Parse strace output (network syscalls first: their payloads may
+        // contain text that looks like a filesystem syscall)
+        let mut event = match parse_network_line(&line) {
+            Some(event) => event,
+            None => match parse_strace_line(&line) {
+                Some(access) => {
+                    if let Some(program) = parse_exec_line(&line, &access) {
+                        events.push(TraceEvent::Exec(program));
+                    }
+                    TraceEvent::File(access)
+                }
+                None => continue,
+            },
+        };
+
+        // Inside the sandbox only denials are of interest. DNS messages are
+        // kept to attribute denied connections to host names.
+        if sandbox.is_some() {
+            match event {
+                TraceEvent::File(ref mut access) => {
+                    if !is_sandbox_denial(&line) {
+                        continue;
+                    }
+                    access.sandbox_denied = true;
+                }
+                TraceEvent::Connect(_) | TraceEvent::Bind(_) if !is_sandbox_denial(&line) => {
+                    continue
+                }
+                _ => {}
+            }
         }
+
+        events.push(event);
     }
 
     // Wait for child to finish
     let _ = child.wait();
 
-    Ok(accesses)
+    Ok(events)
+}
+
+/// Check whether a syscall failed the way Landlock denies access (EACCES)
+#[cfg(target_os = "linux")]
+fn is_sandbox_denial(line: &str) -> bool {
+    line.contains(" = -1 EACCES ")
 }
 
 /// Parse a single strace line to extract file access
@@ -293,9 +901,291 @@ fn parse_strace_line(line: &str) -> Option<FileAccess> {
     Some(FileAccess {
         path: PathBuf::from(path),
         is_write,
+        sandbox_denied: false,
     })
 }
 
+/// Extract the syscall name from a strace line.
+///
+/// With `-f`, strace prefixes lines with the pid (`123 ` or `[pid   123] `).
+#[cfg(target_os = "linux")]
+fn syscall_name(line: &str) -> Option<&str> {
+    let mut rest = line.trim_start();
+    if let Some(stripped) = rest.strip_prefix("[pid") {
+        rest = stripped.split_once(']')?.1.trim_start();
+    } else {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest = rest[digits..].trim_start();
+    }
+
+    let name = &rest[..rest.find('(')?];
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Extract the syscall name from a `<... name resumed>` line.
+///
+/// With `-f`, a syscall interrupted by another process's output is split in
+/// two; the second half carries the results, such as a received buffer.
+#[cfg(target_os = "linux")]
+fn resumed_syscall_name(line: &str) -> Option<&str> {
+    extract_between(line, "<... ", " resumed>")
+}
+
+/// Return the program path if the line is an execve that may have succeeded.
+///
+/// Shells and `execvp` try each `$PATH` entry in turn; those attempts fail
+/// with ENOENT and are not programs that actually ran.
+#[cfg(target_os = "linux")]
+fn parse_exec_line(line: &str, access: &FileAccess) -> Option<PathBuf> {
+    if syscall_name(line)? != "execve" || line.contains(" = -1 ") {
+        return None;
+    }
+    Some(access.path.clone())
+}
+
+/// Parse a network syscall line (connect, bind, send*) into a trace event
+#[cfg(target_os = "linux")]
+fn parse_network_line(line: &str) -> Option<TraceEvent> {
+    // strace output format examples:
+    // connect(3, {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("1.2.3.4")}, 16) = 0
+    // bind(3, {sa_family=AF_INET6, sin6_port=htons(8080), sin6_flowinfo=htonl(0),
+    //     inet_pton(AF_INET6, "::", &sin6_addr), sin6_scope_id=0}, 28) = 0
+    // connect(3, {sa_family=AF_UNIX, sun_path="/run/dbus/system_bus_socket"}, 110) = 0
+    // sendto(3, "\253\315\1\0\0\1\0\0\0\0\0\0\7example\3com\0\0\1\0\1", 29, ...) = 29
+    // recvfrom(3, "\253\315\201\200\0\1\0\1..."..., 2048, 0, {...}, [16]) = 45
+    // [pid 12] <... recvfrom resumed>"\253\315\201\200..., 2048, 0, {...}, [16]) = 45
+    let syscall = syscall_name(line).or_else(|| resumed_syscall_name(line))?;
+    match syscall {
+        "connect" | "bind" => match parse_sockaddr(line)? {
+            SockAddr::Inet(addr) if syscall == "connect" => Some(TraceEvent::Connect(addr)),
+            SockAddr::Inet(addr) => Some(TraceEvent::Bind(addr)),
+            // Unix domain sockets are filesystem objects: connecting requires
+            // write access and binding creates the socket file
+            SockAddr::Unix(path) => Some(TraceEvent::File(FileAccess {
+                path,
+                is_write: true,
+                sandbox_denied: false,
+            })),
+        },
+        "sendto" | "sendmsg" | "sendmmsg" => {
+            parse_dns_query(&message_payload(line)?).map(TraceEvent::DnsQuery)
+        }
+        "recvfrom" | "recvmsg" | "recvmmsg" => {
+            let (name, addrs) = parse_dns_answer(&message_payload(line)?)?;
+            Some(TraceEvent::DnsAnswer(name, addrs))
+        }
+        _ => None,
+    }
+}
+
+/// Parse the socket address structure from a connect/bind line
+#[cfg(target_os = "linux")]
+fn parse_sockaddr(line: &str) -> Option<SockAddr> {
+    // Check AF_INET6 first since "AF_INET" is a prefix of it
+    if line.contains("sa_family=AF_INET6") {
+        let port = extract_between(line, "sin6_port=htons(", ")")?
+            .parse()
+            .ok()?;
+        let addr: std::net::Ipv6Addr = extract_between(line, "inet_pton(AF_INET6, \"", "\"")?
+            .parse()
+            .ok()?;
+        Some(SockAddr::Inet(SocketAddr::new(IpAddr::V6(addr), port)))
+    } else if line.contains("sa_family=AF_INET") {
+        let port = extract_between(line, "sin_port=htons(", ")")?
+            .parse()
+            .ok()?;
+        let addr: std::net::Ipv4Addr = extract_between(line, "inet_addr(\"", "\"")?.parse().ok()?;
+        Some(SockAddr::Inet(SocketAddr::new(IpAddr::V4(addr), port)))
+    } else if line.contains("sa_family=AF_UNIX") {
+        // Abstract sockets are shown as sun_path=@"..." and have no filesystem path
+        let idx = line.find("sun_path=\"")?;
+        let path = extract_quoted_string(&line[idx + "sun_path=".len()..])?;
+        if path.is_empty() {
+            return None;
+        }
+        Some(SockAddr::Unix(PathBuf::from(path)))
+    } else {
+        None
+    }
+}
+
+/// Extract the text between the first occurrence of `start` and the next `end`
+#[cfg(target_os = "linux")]
+fn extract_between<'a>(line: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let after = &line[line.find(start)? + start.len()..];
+    Some(&after[..after.find(end)?])
+}
+
+/// Extract and unescape a quoted strace string starting at `s[0] == '"'`.
+///
+/// Unlike a plain search for the next quote, this honours `\"` escapes,
+/// which matters for binary payloads.
+#[cfg(target_os = "linux")]
+fn extract_quoted_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?;
+    let mut escaped = false;
+    for (i, c) in inner.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(unescape_strace_string(&inner[..i])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Extract the data buffer of a send/recv line.
+///
+/// The `*msg` calls show it as `iov_base="..."`, after the peer address
+/// (which may contain quoted strings itself); `sendto`/`recvfrom` show it
+/// as the first argument after the descriptor.
+#[cfg(target_os = "linux")]
+fn message_payload(line: &str) -> Option<String> {
+    let start = match line.find("iov_base=\"") {
+        Some(idx) => idx + "iov_base=".len(),
+        None => line.find('"')?,
+    };
+    extract_quoted_string(&line[start..])
+}
+
+/// Parse the header and single question of a DNS message.
+///
+/// Returns whether the message is a response, the queried name and the
+/// offset of the first record after the question. Anything that isn't
+/// well-formed DNS returns None, so arbitrary UDP traffic is not mistaken
+/// for a lookup.
+#[cfg(target_os = "linux")]
+fn parse_dns_question(bytes: &[u8]) -> Option<(bool, String, usize)> {
+    // 12-byte header with exactly one question
+    if bytes.len() < 12 || bytes[4..6] != [0, 1] {
+        return None;
+    }
+    let is_response = bytes[2] & 0x80 != 0;
+
+    let mut labels = Vec::new();
+    let mut pos = 12;
+    loop {
+        let len = usize::from(*bytes.get(pos)?);
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        if len > 63 {
+            return None;
+        }
+        let label = bytes.get(pos..pos + len)?;
+        if !label
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b'_')
+        {
+            return None;
+        }
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        pos += len;
+    }
+
+    // QTYPE and QCLASS must follow the name
+    if labels.is_empty() || bytes.len() < pos + 4 {
+        return None;
+    }
+
+    Some((is_response, labels.join("."), pos + 4))
+}
+
+/// Bytes of a strace string (one char per byte of the original buffer)
+#[cfg(target_os = "linux")]
+fn payload_bytes(payload: &str) -> Option<Vec<u8>> {
+    payload
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)).ok())
+        .collect()
+}
+
+/// Parse a DNS query message and return the queried name
+#[cfg(target_os = "linux")]
+fn parse_dns_query(payload: &str) -> Option<String> {
+    match parse_dns_question(&payload_bytes(payload)?)? {
+        (false, name, _) => Some(name),
+        (true, _, _) => None,
+    }
+}
+
+/// Parse a DNS response and return the queried name and its A/AAAA addresses.
+///
+/// Addresses reached through a CNAME chain are attributed to the queried
+/// name. A buffer truncated by strace yields the records before the cut.
+#[cfg(target_os = "linux")]
+fn parse_dns_answer(payload: &str) -> Option<(String, Vec<IpAddr>)> {
+    let bytes = payload_bytes(payload)?;
+    let (is_response, name, mut pos) = parse_dns_question(&bytes)?;
+    // Responses only, and only successful ones (RCODE 0)
+    if !is_response || bytes[3] & 0x0f != 0 {
+        return None;
+    }
+
+    let answers = u16::from_be_bytes([bytes[6], bytes[7]]);
+    let mut addrs = Vec::new();
+    for _ in 0..answers {
+        // Owner name: labels ending in a zero byte or a compression pointer
+        loop {
+            let len = *bytes.get(pos)?;
+            if len & 0xc0 == 0xc0 {
+                pos += 2;
+                break;
+            }
+            pos += 1 + usize::from(len);
+            if len == 0 {
+                break;
+            }
+        }
+
+        // TYPE, CLASS, TTL, RDLENGTH, then RDATA
+        let Some(fixed) = bytes.get(pos..pos + 10) else {
+            break;
+        };
+        let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let rdlength = usize::from(u16::from_be_bytes([fixed[8], fixed[9]]));
+        pos += 10;
+        let Some(rdata) = bytes.get(pos..pos + rdlength) else {
+            break;
+        };
+        pos += rdlength;
+
+        match (rtype, rdata.len()) {
+            (1, 4) => addrs.push(IpAddr::from(<[u8; 4]>::try_from(rdata).ok()?)),
+            (28, 16) => addrs.push(IpAddr::from(<[u8; 16]>::try_from(rdata).ok()?)),
+            _ => {}
+        }
+    }
+
+    (!addrs.is_empty()).then_some((name, addrs))
+}
+
+/// Map addresses from the traced DNS answers to the names that were queried.
+///
+/// Only the command's own lookups count: resolving the names again here could
+/// return different addresses (round-robin DNS, CDNs) than the command used.
+/// Connections to addresses not found here are reported by IP.
+#[cfg(target_os = "linux")]
+fn dns_answers(events: &[TraceEvent]) -> HashMap<IpAddr, String> {
+    let mut resolved = HashMap::new();
+    for event in events {
+        if let TraceEvent::DnsAnswer(name, addrs) = event {
+            for addr in addrs {
+                resolved.entry(*addr).or_insert_with(|| name.clone());
+            }
+        }
+    }
+    resolved
+}
+
 /// Extract path from strace syscall line
 #[cfg(target_os = "linux")]
 fn extract_path_from_syscall(line: &str, syscall: &str) -> Option<String> {
@@ -333,7 +1223,7 @@ fn extract_path_from_syscall(line: &str, syscall: &str) -> Option<String> {
 }
 
 /// Unescape C-style escape sequences from strace output.
-/// Handles: \n \t \r \\ \" \0 \xNN (hex) \NNN (octal)
+/// Handles: \n \t \r \v \f \\ \" \0 \xNN (hex) \NNN (octal)
 ///
 /// Invalid or incomplete escape sequences are passed through literally
 /// to avoid data loss.
@@ -357,6 +1247,14 @@ fn unescape_strace_string(s: &str) -> String {
                     chars.next();
                     result.push('\r');
                 }
+                Some('v') => {
+                    chars.next();
+                    result.push('\x0b');
+                }
+                Some('f') => {
+                    chars.next();
+                    result.push('\x0c');
+                }
                 Some('\\') => {
                     chars.next();
                     result.push('\\');
@@ -426,12 +1324,40 @@ fn is_write_access(line: &str, syscall: &str) -> bool {
     }
 }
 
+/// Record a needed access, merging read and write into read+write
+#[cfg(target_os = "linux")]
+fn categorize_access(result: &mut LearnResult, path: PathBuf, is_write: bool) {
+    if is_write {
+        // Check if already in read, upgrade to readwrite
+        if result.read_paths.contains(&path) {
+            result.read_paths.remove(&path);
+            result.readwrite_paths.insert(path);
+        } else if !result.readwrite_paths.contains(&path) {
+            result.write_paths.insert(path);
+        }
+    } else {
+        // Read access
+        if result.write_paths.contains(&path) {
+            result.write_paths.remove(&path);
+            result.readwrite_paths.insert(path);
+        } else if !result.readwrite_paths.contains(&path) {
+            result.read_paths.insert(path);
+        }
+    }
+}
+
 /// Process raw accesses into categorized result
+///
+/// Profile paths are expanded as the sandbox expands them, for a command
+/// run in `workdir` with `tmpdir` as `$TMPDIR` (see `profile::expand_vars`).
 #[cfg(target_os = "linux")]
 fn process_accesses(
-    accesses: Vec<FileAccess>,
+    events: Vec<TraceEvent>,
     profile: Option<&Profile>,
     show_all: bool,
+    resolved: &HashMap<IpAddr, String>,
+    workdir: &Path,
+    tmpdir: Option<&Path>,
 ) -> Result<LearnResult> {
     let mut result = LearnResult::new();
 
@@ -440,20 +1366,87 @@ fn process_accesses(
     let system_read_set: HashSet<&str> = system_read_paths.iter().map(|s| s.as_str()).collect();
 
     // Get profile paths if available
-    let profile_paths: HashSet<String> = if let Some(prof) = profile {
-        let mut paths = HashSet::new();
-        paths.extend(prof.filesystem.allow.iter().cloned());
-        paths.extend(prof.filesystem.read.iter().cloned());
-        paths.extend(prof.filesystem.write.iter().cloned());
-        paths
-    } else {
-        HashSet::new()
+    let profile_paths: Vec<PathBuf> = match profile {
+        Some(prof) => {
+            let fs = &prof.filesystem;
+            [
+                &fs.allow,
+                &fs.read,
+                &fs.write,
+                &fs.allow_file,
+                &fs.read_file,
+                &fs.write_file,
+                &fs.read_noexec,
+                &fs.exec_dir,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|path| profile::expand_vars(path, workdir, tmpdir))
+            .collect()
+        }
+        None => Vec::new(),
     };
 
+    // Command overrides from the profile decide which executions would be blocked
+    let (allowed_commands, blocked_commands) = match profile {
+        Some(prof) => (prof.commands.allow.clone(), prof.commands.block.clone()),
+        None => (Vec::new(), Vec::new()),
+    };
+    let default_commands = config::DefaultCommands::load();
+
     // Track unique paths (canonicalized where possible)
     let mut seen_paths: HashSet<PathBuf> = HashSet::new();
 
-    for access in accesses {
+    for event in events {
+        let access = match event {
+            TraceEvent::File(access) => access,
+            TraceEvent::Connect(addr) => {
+                // Port 0 connects are address-selection probes by the resolver and
+                // port 53 is the resolver itself; neither is a real destination
+                if addr.port() != 0 && addr.port() != 53 {
+                    result.connect_endpoints.insert(NetworkEndpoint {
+                        addr: addr.ip(),
+                        port: addr.port(),
+                        host: resolved.get(&addr.ip()).cloned(),
+                    });
+                }
+                continue;
+            }
+            TraceEvent::Bind(addr) => {
+                // Port 0 asks the kernel for an ephemeral port (client sockets)
+                if addr.port() != 0 {
+                    result.bind_endpoints.insert(NetworkEndpoint {
+                        addr: addr.ip(),
+                        port: addr.port(),
+                        host: None,
+                    });
+                }
+                continue;
+            }
+            TraceEvent::DnsQuery(name) => {
+                result.dns_queries.insert(name);
+                continue;
+            }
+            TraceEvent::DnsAnswer(..) => continue,
+            TraceEvent::Exec(program) => {
+                // An exec split across "<unfinished ...>" lines carries no result,
+                // so drop PATH-search misses by checking the binary exists
+                if program.exists() {
+                    if let Some(blocked) = config::check_blocked_command_with(
+                        &default_commands,
+                        &[&program],
+                        &allowed_commands,
+                        &blocked_commands,
+                        &[],
+                    ) {
+                        result.blocked_commands.insert(blocked.command);
+                    }
+                    result.executed.insert(program);
+                }
+                continue;
+            }
+        };
+
         // Try to canonicalize, fall back to original
         let canonical = access.path.canonicalize().unwrap_or(access.path.clone());
 
@@ -463,6 +1456,13 @@ fn process_accesses(
         }
         seen_paths.insert(canonical.clone());
 
+        // The sandbox's own verdict beats guessing from the path lists below
+        // (e.g. a write to a path the profile grants read-only)
+        if access.sandbox_denied {
+            categorize_access(&mut result, collapse_to_parent(&canonical), access.is_write);
+            continue;
+        }
+
         // Check if covered by system paths
         if is_covered_by_set(&canonical, &system_read_set) {
             if show_all {
@@ -481,25 +1481,24 @@ fn process_accesses(
 
         // Categorize by access type
         // Collapse to parent directories for cleaner output
-        let collapsed = collapse_to_parent(&canonical);
+        categorize_access(&mut result, collapse_to_parent(&canonical), access.is_write);
+    }
 
-        if access.is_write {
-            // Check if already in read, upgrade to readwrite
-            if result.read_paths.contains(&collapsed) {
-                result.read_paths.remove(&collapsed);
-                result.readwrite_paths.insert(collapsed);
-            } else if !result.readwrite_paths.contains(&collapsed) {
-                result.write_paths.insert(collapsed);
-            }
-        } else {
-            // Read access
-            if result.write_paths.contains(&collapsed) {
-                result.write_paths.remove(&collapsed);
-                result.readwrite_paths.insert(collapsed);
-            } else if !result.readwrite_paths.contains(&collapsed) {
-                result.read_paths.insert(collapsed);
-            }
-        }
+    // Drop destinations the profile already records
+    if let Some(prof) = profile {
+        let observed = &prof.network.observed;
+        let known_hosts: HashSet<&str> = observed.hosts.iter().map(|h| h.as_str()).collect();
+        let ports = &observed.connect_ports;
+        result.connect_endpoints.retain(|e| {
+            !(known_hosts.contains(e.host_label().as_str())
+                && (ports.is_empty() || ports.contains(&e.port)))
+        });
+        result
+            .dns_queries
+            .retain(|q| !known_hosts.contains(q.as_str()));
+        result
+            .bind_endpoints
+            .retain(|e| !observed.listen_ports.contains(&e.port));
     }
 
     Ok(result)
@@ -524,18 +1523,16 @@ fn is_covered_by_set(path: &Path, allowed: &HashSet<&str>) -> bool {
     false
 }
 
-/// Check if a path is covered by profile paths
+/// Check if a path is covered by (expanded) profile paths
 #[cfg(target_os = "linux")]
-fn is_covered_by_profile(path: &Path, profile_paths: &HashSet<String>) -> bool {
+fn is_covered_by_profile(path: &Path, profile_paths: &[PathBuf]) -> bool {
     for profile_path in profile_paths {
-        let expanded = expand_home(profile_path);
-        if let Ok(canonical) = std::fs::canonicalize(&expanded) {
+        if let Ok(canonical) = std::fs::canonicalize(profile_path) {
             if path.starts_with(&canonical) {
                 return true;
             }
         }
-        let path_buf = PathBuf::from(&expanded);
-        if path.starts_with(&path_buf) {
+        if path.starts_with(profile_path) {
             return true;
         }
     }
@@ -608,6 +1605,310 @@ mod tests {
         assert!(!access.is_write);
     }
 
+    #[test]
+    fn test_parse_exec_line() {
+        let line =
+            r#"[pid 812] execve("/usr/bin/cp", ["cp", "a", "b"], 0x7ffd... /* 20 vars */) = 0"#;
+        let access = parse_strace_line(line).unwrap();
+        assert_eq!(
+            parse_exec_line(line, &access),
+            Some(PathBuf::from("/usr/bin/cp"))
+        );
+
+        // PATH search misses are not executions
+        let line = r#"execve("/usr/local/bin/cp", ["cp"], 0x7ffd...) = -1 ENOENT (No such file or directory)"#;
+        let access = parse_strace_line(line).unwrap();
+        assert!(parse_exec_line(line, &access).is_none());
+
+        // Other syscalls are ignored
+        let line = r#"openat(AT_FDCWD, "/usr/bin/cp", O_RDONLY) = 3"#;
+        let access = parse_strace_line(line).unwrap();
+        assert!(parse_exec_line(line, &access).is_none());
+    }
+
+    #[test]
+    fn test_sandbox_denials_bypass_coverage() {
+        assert!(is_sandbox_denial(
+            r#"openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied)"#
+        ));
+        assert!(!is_sandbox_denial(
+            r#"openat(AT_FDCWD, "/nope", O_RDONLY) = -1 ENOENT (No such file or directory)"#
+        ));
+
+        // /usr is a system read path, but a denied write must still be reported
+        let events = vec![TraceEvent::File(FileAccess {
+            path: PathBuf::from("/usr/lib/nono-learn-test"),
+            is_write: true,
+            sandbox_denied: true,
+        })];
+        let result = process_accesses(
+            events,
+            None,
+            false,
+            &HashMap::new(),
+            Path::new("/work"),
+            None,
+        )
+        .unwrap();
+        assert!(result.write_paths.contains(Path::new("/usr/lib")));
+    }
+
+    #[test]
+    fn test_profile_variables_cover_accesses() {
+        let workdir = tempfile::tempdir().unwrap();
+        let tmpdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workdir.path().join("src")).unwrap();
+        std::fs::write(workdir.path().join("notes.txt"), "").unwrap();
+        std::fs::create_dir(tmpdir.path().join("cache")).unwrap();
+        std::fs::create_dir(workdir.path().join("uncovered")).unwrap();
+
+        // Entries in the forms `--update` writes them
+        let mut profile = Profile::default();
+        profile.filesystem.read = vec!["$WORKDIR/src".to_string()];
+        profile.filesystem.read_file = vec!["$WORKDIR/notes.txt".to_string()];
+        profile.filesystem.allow = vec!["$TMPDIR/cache".to_string()];
+
+        let access = |path: PathBuf| {
+            TraceEvent::File(FileAccess {
+                path,
+                is_write: false,
+                sandbox_denied: false,
+            })
+        };
+        let events = vec![
+            access(workdir.path().join("src")),
+            access(workdir.path().join("notes.txt")),
+            access(tmpdir.path().join("cache")),
+            access(workdir.path().join("uncovered")),
+        ];
+
+        let result = process_accesses(
+            events,
+            Some(&profile),
+            false,
+            &HashMap::new(),
+            workdir.path(),
+            Some(tmpdir.path()),
+        )
+        .unwrap();
+        let uncovered = workdir.path().join("uncovered").canonicalize().unwrap();
+        assert_eq!(result.read_paths, BTreeSet::from([uncovered]));
+    }
+
+    #[test]
+    fn test_process_exec_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let rm = dir.path().join("rm");
+        let tool = dir.path().join("safe-tool");
+        std::fs::write(&rm, "").unwrap();
+        std::fs::write(&tool, "").unwrap();
+
+        let events = vec![
+            TraceEvent::Exec(rm.clone()),
+            TraceEvent::Exec(tool.clone()),
+            // Never existed: a PATH search miss from an unfinished exec line
+            TraceEvent::Exec(dir.path().join("cp")),
+        ];
+        let result = process_accesses(
+            events.clone(),
+            None,
+            false,
+            &HashMap::new(),
+            Path::new("/work"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.executed.len(), 2);
+        assert!(result.blocked_commands.contains("rm"));
+        assert!(!result.blocked_commands.contains("cp"));
+        assert!(result.to_toml().contains("\"rm\","));
+
+        // A profile that already allows the command does not flag it
+        let mut profile = Profile::default();
+        profile.commands.allow = vec!["rm".to_string()];
+        let result = process_accesses(
+            events,
+            Some(&profile),
+            false,
+            &HashMap::new(),
+            Path::new("/work"),
+            None,
+        )
+        .unwrap();
+        assert!(result.blocked_commands.is_empty());
+        assert_eq!(result.executed.len(), 2);
+    }
+
+    #[test]
+    fn test_abstract_path() {
+        let vars = PathVariables {
+            dirs: vec![
+                ("$WORKDIR", PathBuf::from("/home/alice/project")),
+                ("$XDG_CONFIG_HOME", PathBuf::from("/home/alice/.config")),
+                ("$XDG_DATA_HOME", PathBuf::from("/home/alice/.local/share")),
+                ("$HOME", PathBuf::from("/home/alice")),
+            ],
+            uid: Some("1000".to_string()),
+        };
+        // The most specific variable wins
+        assert_eq!(
+            vars.abstract_path(Path::new("/home/alice/project/src")),
+            "$WORKDIR/src"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/home/alice/.cache/pip")),
+            "$HOME/.cache/pip"
+        );
+        assert_eq!(vars.abstract_path(Path::new("/home/alice")), "$HOME");
+        assert_eq!(
+            vars.abstract_path(Path::new("/home/alice/.config/gh")),
+            "$XDG_CONFIG_HOME/gh"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/home/alice/.local/share/nvim")),
+            "$XDG_DATA_HOME/nvim"
+        );
+        // Temp paths stay literal
+        assert_eq!(vars.abstract_path(Path::new("/tmp/build")), "/tmp/build");
+        // The user ID is replaced only in per-user locations
+        assert_eq!(
+            vars.abstract_path(Path::new("/run/user/1000/bus")),
+            "/run/user/$UID/bus"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/tmp/tmux-1000/default")),
+            "/tmp/tmux-$UID/default"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/tmp/1000/cache-1000")),
+            "/tmp/1000/cache-1000"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/tmp/tmux-10001")),
+            "/tmp/tmux-10001"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/run/user/10001")),
+            "/run/user/10001"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/srv/1000/data")),
+            "/srv/1000/data"
+        );
+        // Component-wise matching: no partial directory names
+        assert_eq!(
+            vars.abstract_path(Path::new("/home/alicebob")),
+            "/home/alicebob"
+        );
+        assert_eq!(vars.abstract_path(Path::new("/opt/tool")), "/opt/tool");
+    }
+
+    #[test]
+    fn test_abstract_path_root_uid() {
+        // For root every `0` component would match the user ID
+        let vars = PathVariables {
+            dirs: vec![("$HOME", PathBuf::from("/root"))],
+            uid: Some("0".to_string()),
+        };
+        assert_eq!(vars.abstract_path(Path::new("/dev/pts/0")), "/dev/pts/0");
+        assert_eq!(
+            vars.abstract_path(Path::new("/sys/devices/system/cpu/cpu0/cache/index0")),
+            "/sys/devices/system/cpu/cpu0/cache/index0"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/run/user/0")),
+            "/run/user/$UID"
+        );
+        assert_eq!(
+            vars.abstract_path(Path::new("/tmp/tmux-0/default")),
+            "/tmp/tmux-$UID/default"
+        );
+    }
+
+    #[test]
+    fn test_to_toml_uses_variables() {
+        let mut result = LearnResult::new();
+        result.path_vars = PathVariables {
+            dirs: vec![("$HOME", PathBuf::from("/home/alice"))],
+            uid: None,
+        };
+        result
+            .read_paths
+            .insert(PathBuf::from("/home/alice/.cache/pip"));
+
+        let toml = result.to_toml();
+        assert!(toml.contains("    \"$HOME/.cache/pip\",\n"));
+    }
+
+    #[test]
+    fn test_merge_into_profile_preserves_formatting() {
+        let original = r#"# My agent profile
+[meta]
+name = "my-agent"
+
+[filesystem]
+# Config directories
+read = [
+  "/opt/existing",  # keep this comment
+]
+write = []
+
+[network]
+block = false
+"#;
+
+        let mut result = LearnResult::new();
+        result.read_paths.insert(PathBuf::from("/opt/existing"));
+        result.read_paths.insert(PathBuf::from("/opt/new-read"));
+        result.write_paths.insert(PathBuf::from("/var/opt/out"));
+        result.blocked_commands.insert("cp".to_string());
+        result.connect_endpoints.insert(NetworkEndpoint {
+            addr: "93.184.216.34".parse().unwrap(),
+            port: 443,
+            host: Some("example.com".to_string()),
+        });
+
+        let updated = merge_into_profile(original, &result).unwrap();
+
+        // Comments and existing layout survive
+        assert!(updated.starts_with("# My agent profile\n"));
+        assert!(updated.contains("# Config directories"));
+        assert!(updated.contains("\"/opt/existing\",  # keep this comment\n"));
+        // New entries follow the existing indentation, without duplicates
+        assert!(updated.contains("\n  \"/opt/new-read\""));
+        assert_eq!(updated.matches("/opt/existing").count(), 1);
+        assert!(updated.contains("write = [\n    \"/var/opt/out\",\n]"));
+        // Missing tables and keys are created
+        assert!(updated.contains("[commands]"));
+        assert!(updated.contains("[network.observed]"));
+        assert!(updated.contains("connect_ports = [443]"));
+        assert!(updated.contains("block = false"));
+
+        let profile: Profile = toml::from_str(&updated).unwrap();
+        assert_eq!(
+            profile.filesystem.read,
+            vec!["/opt/existing", "/opt/new-read"]
+        );
+        assert_eq!(profile.commands.allow, vec!["cp"]);
+        assert_eq!(profile.network.observed.hosts, vec!["example.com"]);
+
+        // Merging the same result again is a no-op
+        let again = merge_into_profile(&updated, &result).unwrap();
+        assert_eq!(again, updated);
+    }
+
+    #[test]
+    fn test_merge_into_profile_rejects_wrong_types() {
+        let mut result = LearnResult::new();
+        result.read_paths.insert(PathBuf::from("/opt/data"));
+
+        let original = "[filesystem]\nread = \"/opt\"\n";
+        assert!(matches!(
+            merge_into_profile(original, &result),
+            Err(NonoError::ProfileParse(_))
+        ));
+    }
+
     #[test]
     fn test_extract_path_from_openat() {
         let line = r#"openat(AT_FDCWD, "/some/path", O_RDONLY) = 3"#;
@@ -655,6 +1956,289 @@ mod tests {
         assert!(toml.contains("/some/write/path"));
     }
 
+    #[test]
+    fn test_learn_result_to_toml_network() {
+        let mut result = LearnResult::new();
+        result.connect_endpoints.insert(NetworkEndpoint {
+            addr: "93.184.216.34".parse().unwrap(),
+            port: 443,
+            host: Some("example.com".to_string()),
+        });
+        result.bind_endpoints.insert(NetworkEndpoint {
+            addr: "127.0.0.1".parse().unwrap(),
+            port: 3000,
+            host: None,
+        });
+
+        let toml = result.to_toml();
+        assert!(toml.contains("[network]"));
+        assert!(toml.contains("block = false"));
+        assert!(toml.contains("\"example.com\","));
+        assert!(toml.contains("[network.observed]"));
+        assert!(toml.contains("connect_ports = [443]"));
+        assert!(toml.contains("listen_ports = [3000]"));
+
+        // The fragment must parse as a profile
+        let profile: Profile = toml::from_str(&toml).unwrap();
+        assert_eq!(profile.network.observed.hosts, vec!["example.com"]);
+    }
+
+    #[test]
+    fn test_learn_result_to_toml_no_network() {
+        let result = LearnResult::new();
+        let toml = result.to_toml();
+        assert!(toml.contains("[network]"));
+        assert!(toml.contains("block = true"));
+    }
+
+    #[test]
+    fn test_syscall_name_pid_prefix() {
+        assert_eq!(
+            syscall_name(r#"connect(3, {...}, 16) = 0"#),
+            Some("connect")
+        );
+        assert_eq!(
+            syscall_name(r#"12345 connect(3, {...}, 16) = 0"#),
+            Some("connect")
+        );
+        assert_eq!(
+            syscall_name(r#"[pid  4242] bind(3, {...}, 16) = 0"#),
+            Some("bind")
+        );
+        assert_eq!(syscall_name("+++ exited with 0 +++"), None);
+    }
+
+    #[test]
+    fn test_parse_connect_ipv4() {
+        let line = r#"connect(3, {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("93.184.216.34")}, 16) = 0"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::Connect(addr)) => {
+                assert_eq!(addr, "93.184.216.34:443".parse().unwrap());
+            }
+            other => panic!("Expected Connect, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_connect_ipv6() {
+        let line = r#"[pid 77] connect(5, {sa_family=AF_INET6, sin6_port=htons(8443), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, "2606:2800:220:1::1", &sin6_addr), sin6_scope_id=0}, 28) = -1 EINPROGRESS (Operation now in progress)"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::Connect(addr)) => {
+                assert_eq!(addr, "[2606:2800:220:1::1]:8443".parse().unwrap());
+            }
+            other => panic!("Expected Connect, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_bind() {
+        let line = r#"bind(3, {sa_family=AF_INET, sin_port=htons(3000), sin_addr=inet_addr("127.0.0.1")}, 16) = 0"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::Bind(addr)) => {
+                assert_eq!(addr, "127.0.0.1:3000".parse().unwrap());
+            }
+            other => panic!("Expected Bind, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_connect_unix_socket() {
+        let line = r#"connect(3, {sa_family=AF_UNIX, sun_path="/run/user/1000/bus"}, 110) = 0"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::File(access)) => {
+                assert_eq!(access.path, PathBuf::from("/run/user/1000/bus"));
+                assert!(access.is_write);
+            }
+            other => panic!("Expected File, got {:?}", other),
+        }
+
+        // Abstract sockets have no filesystem path
+        let line = r#"connect(3, {sa_family=AF_UNIX, sun_path=@"/tmp/.X11-unix/X0"}, 20) = 0"#;
+        assert!(parse_network_line(line).is_none());
+    }
+
+    #[test]
+    fn test_parse_sendto_dns_query() {
+        let line = r#"sendto(3, "\253\315\1\0\0\1\0\0\0\0\0\0\3api\7example\3com\0\0\1\0\1", 33, MSG_NOSIGNAL, NULL, 0) = 33"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::DnsQuery(name)) => assert_eq!(name, "api.example.com"),
+            other => panic!("Expected DnsQuery, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_sendmmsg_dns_query() {
+        let line = r#"sendmmsg(3, [{msg_hdr={msg_name=NULL, msg_namelen=0, msg_iov=[{iov_base="\22\64\1\0\0\1\0\0\0\0\0\0\fregistry-npm\3org\0\0\1\0\1", iov_len=34}], msg_iovlen=1, msg_controllen=0, msg_flags=0}, msg_len=34}], 1, MSG_NOSIGNAL) = 1"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::DnsQuery(name)) => assert_eq!(name, "registry-npm.org"),
+            other => panic!("Expected DnsQuery, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_dns_query_rejects_non_queries() {
+        // DNS response (QR bit set)
+        let response =
+            unescape_strace_string(r#"\253\315\201\200\0\1\0\1\0\0\0\0\7example\3com\0\0\1\0\1"#);
+        assert!(parse_dns_query(&response).is_none());
+
+        // Arbitrary payload
+        assert!(parse_dns_query("GET / HTTP/1.1\r\n").is_none());
+
+        // Truncated question (no QTYPE/QCLASS)
+        let truncated = unescape_strace_string(r#"\1\2\1\0\0\1\0\0\0\0\0\0\7example\0"#);
+        assert!(parse_dns_query(&truncated).is_none());
+    }
+
+    #[test]
+    fn test_parse_recvfrom_dns_answer() {
+        // www.example.com CNAME (compressed) to an A record for 93.184.216.34
+        let line = r#"recvfrom(3, "\253\315\201\200\0\1\0\2\0\0\0\0\3www\7example\3com\0\0\1\0\1\300\f\0\5\0\1\0\0\0<\0\2\300\20\300\20\0\1\0\1\0\0\0<\0\4]\270\330\"", 2048, 0, {sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr("127.0.0.53")}, [28 => 16]) = 65"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::DnsAnswer(name, addrs)) => {
+                assert_eq!(name, "www.example.com");
+                assert_eq!(addrs, vec!["93.184.216.34".parse::<IpAddr>().unwrap()]);
+            }
+            other => panic!("Expected DnsAnswer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_recvmsg_dns_answer() {
+        // The peer address comes first and contains quotes of its own
+        let line = r#"recvmsg(3, {msg_name={sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr("127.0.0.53")}, msg_namelen=16, msg_iov=[{iov_base="\1\2\201\200\0\1\0\1\0\0\0\0\7example\3com\0\0\34\0\1\300\f\0\34\0\1\0\0\0<\0\20 \1\r\270\0\0\0\0\0\0\0\0\0\0\0\1", iov_len=2048}], msg_iovlen=1, msg_controllen=0, msg_flags=0}, 0) = 57"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::DnsAnswer(name, addrs)) => {
+                assert_eq!(name, "example.com");
+                assert_eq!(addrs, vec!["2001:db8::1".parse::<IpAddr>().unwrap()]);
+            }
+            other => panic!("Expected DnsAnswer, got {:?}", other),
+        }
+
+        // The second half of a syscall split by -f carries the buffer
+        let line = r#"[pid  4242] <... recvfrom resumed>"\1\2\201\200\0\1\0\1\0\0\0\0\7example\3com\0\0\1\0\1\300\f\0\1\0\1\0\0\0<\0\4\n\0\0\1", 2048, 0, {...}, [16]) = 43"#;
+        match parse_network_line(line) {
+            Some(TraceEvent::DnsAnswer(name, addrs)) => {
+                assert_eq!(name, "example.com");
+                assert_eq!(addrs, vec!["10.0.0.1".parse::<IpAddr>().unwrap()]);
+            }
+            other => panic!("Expected DnsAnswer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_dns_answer_rejects_non_answers() {
+        // Query (QR bit clear)
+        let query =
+            unescape_strace_string(r#"\253\315\1\0\0\1\0\0\0\0\0\0\7example\3com\0\0\1\0\1"#);
+        assert!(parse_dns_answer(&query).is_none());
+
+        // NXDOMAIN (RCODE 3)
+        let nxdomain =
+            unescape_strace_string(r#"\253\315\201\203\0\1\0\0\0\0\0\0\7example\3com\0\0\1\0\1"#);
+        assert!(parse_dns_answer(&nxdomain).is_none());
+
+        // Arbitrary payload
+        assert!(parse_dns_answer("HTTP/1.1 200 OK\r\n").is_none());
+    }
+
+    #[test]
+    fn test_dns_answers_first_name_wins() {
+        let events = vec![
+            TraceEvent::DnsAnswer(
+                "example.com".to_string(),
+                vec!["93.184.216.34".parse().unwrap()],
+            ),
+            TraceEvent::DnsAnswer(
+                "cdn.example.net".to_string(),
+                vec![
+                    "93.184.216.34".parse().unwrap(),
+                    "93.184.216.35".parse().unwrap(),
+                ],
+            ),
+        ];
+
+        let resolved = dns_answers(&events);
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(
+            resolved[&"93.184.216.34".parse::<IpAddr>().unwrap()],
+            "example.com"
+        );
+        assert_eq!(
+            resolved[&"93.184.216.35".parse::<IpAddr>().unwrap()],
+            "cdn.example.net"
+        );
+    }
+
+    #[test]
+    fn test_process_network_events() {
+        let mut resolved = HashMap::new();
+        resolved.insert(
+            "93.184.216.34".parse::<IpAddr>().unwrap(),
+            "example.com".to_string(),
+        );
+
+        let events = vec![
+            TraceEvent::DnsQuery("example.com".to_string()),
+            TraceEvent::Connect("93.184.216.34:443".parse().unwrap()),
+            TraceEvent::Connect("10.0.0.5:5432".parse().unwrap()),
+            // Resolver traffic and address-selection probes are not destinations
+            TraceEvent::Connect("127.0.0.53:53".parse().unwrap()),
+            TraceEvent::Connect("93.184.216.34:0".parse().unwrap()),
+            TraceEvent::Bind("0.0.0.0:0".parse().unwrap()),
+            TraceEvent::Bind("127.0.0.1:8080".parse().unwrap()),
+        ];
+
+        let result =
+            process_accesses(events, None, false, &resolved, Path::new("/work"), None).unwrap();
+        assert_eq!(result.connect_endpoints.len(), 2);
+        assert!(result
+            .connect_endpoints
+            .iter()
+            .any(|e| e.port == 443 && e.host.as_deref() == Some("example.com")));
+        assert!(result
+            .connect_endpoints
+            .iter()
+            .any(|e| e.port == 5432 && e.host.is_none()));
+        assert_eq!(result.bind_endpoints.len(), 1);
+        assert_eq!(
+            result.suggested_hosts().into_iter().collect::<Vec<_>>(),
+            vec!["10.0.0.5", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_process_network_events_profile_covered() {
+        let mut profile = Profile::default();
+        profile.network.observed.hosts = vec!["example.com".to_string()];
+
+        let mut resolved = HashMap::new();
+        resolved.insert(
+            "93.184.216.34".parse::<IpAddr>().unwrap(),
+            "example.com".to_string(),
+        );
+        let events = vec![
+            TraceEvent::DnsQuery("example.com".to_string()),
+            TraceEvent::Connect("93.184.216.34:443".parse().unwrap()),
+        ];
+
+        let result = process_accesses(
+            events,
+            Some(&profile),
+            false,
+            &resolved,
+            Path::new("/work"),
+            None,
+        )
+        .unwrap();
+        assert!(!result.has_network());
+    }
+
+    #[test]
+    fn test_unescape_form_feed_and_vertical_tab() {
+        assert_eq!(unescape_strace_string(r#"\f\v"#), "\x0c\x0b");
+    }
+
     #[test]
     fn test_unescape_simple() {
         assert_eq!(unescape_strace_string(r#"hello"#), "hello");
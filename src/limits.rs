@@ -0,0 +1,464 @@
+//! Resource limits for sandboxed processes (`[limits]` profile section, `--max-*` flags)
+//!
+//! Per-process limits are applied with `setrlimit()` in the child just before
+//! exec. The soft and hard limits are set to the same value, so the sandboxed
+//! command cannot raise them again. Session-wide limits (`memory`, `pids`,
+//! `cpus`) are enforced by a cgroup, see [`crate::cgroup`].
+
+use crate::cli::LimitArgs;
+use crate::error::{NonoError, Result};
+use nix::sys::resource::{getrlimit, setrlimit, Resource, RLIM_INFINITY};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// Resource limits for the sandboxed command. Unset limits are inherited.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// CPU time in seconds (RLIMIT_CPU)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<u64>,
+    /// Virtual memory in bytes (RLIMIT_AS)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub address_space: Option<u64>,
+    /// Open file descriptors (RLIMIT_NOFILE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files: Option<u64>,
+    /// Processes of the user, including ones outside the sandbox (RLIMIT_NPROC)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processes: Option<u64>,
+    /// Core dump size in bytes; 0 disables core dumps (RLIMIT_CORE)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub core_size: Option<u64>,
+    /// Size of files the command may write, in bytes (RLIMIT_FSIZE)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub file_size: Option<u64>,
+    /// Memory of the whole session in bytes (cgroup memory.max)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub memory: Option<u64>,
+    /// Processes in the session (cgroup pids.max)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u64>,
+    /// CPU bandwidth in CPUs, e.g. 1.5 (cgroup cpu.max)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_cpus",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cpus: Option<f64>,
+}
+
+impl From<&LimitArgs> for ResourceLimits {
+    fn from(args: &LimitArgs) -> Self {
+        Self {
+            cpu_time: args.max_cpu_time,
+            address_space: args.max_address_space,
+            open_files: args.max_open_files,
+            processes: args.max_processes,
+            core_size: args.max_core_size,
+            file_size: args.max_file_size,
+            memory: args.max_memory,
+            pids: args.max_pids,
+            cpus: args.max_cpus,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Check if no limit is set
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty() && !self.needs_cgroup()
+    }
+
+    /// Check if any session-wide limit is set, which needs a cgroup
+    pub fn needs_cgroup(&self) -> bool {
+        self.memory.is_some() || self.pids.is_some() || self.cpus.is_some()
+    }
+
+    /// Combine with lower-priority limits: unset values are taken from `fallback`
+    #[must_use]
+    pub fn or(self, fallback: &ResourceLimits) -> Self {
+        Self {
+            cpu_time: self.cpu_time.or(fallback.cpu_time),
+            address_space: self.address_space.or(fallback.address_space),
+            open_files: self.open_files.or(fallback.open_files),
+            processes: self.processes.or(fallback.processes),
+            core_size: self.core_size.or(fallback.core_size),
+            file_size: self.file_size.or(fallback.file_size),
+            memory: self.memory.or(fallback.memory),
+            pids: self.pids.or(fallback.pids),
+            cpus: self.cpus.or(fallback.cpus),
+        }
+    }
+
+    /// Human-readable description of each set limit, e.g. `("address space", "4G")`
+    pub fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut described: Vec<_> = self
+            .entries()
+            .into_iter()
+            .map(|(label, resource, value)| {
+                let value = match resource {
+                    Resource::RLIMIT_CPU => format!("{}s", value),
+                    Resource::RLIMIT_AS | Resource::RLIMIT_CORE | Resource::RLIMIT_FSIZE => {
+                        format_size(value)
+                    }
+                    _ => value.to_string(),
+                };
+                (label, value)
+            })
+            .collect();
+
+        if let Some(memory) = self.memory {
+            described.push(("session memory", format_size(memory)));
+        }
+        if let Some(pids) = self.pids {
+            described.push(("session processes", pids.to_string()));
+        }
+        if let Some(cpus) = self.cpus {
+            described.push(("session CPUs", cpus.to_string()));
+        }
+        described
+    }
+
+    /// Validate the limits against the current hard limits and prepare them
+    /// for [`PreparedLimits::apply`].
+    ///
+    /// Unprivileged processes cannot raise a hard limit, so asking for more
+    /// than the current one is an error rather than a silent no-op.
+    pub fn prepare(&self) -> Result<PreparedLimits> {
+        let mut limits = Vec::new();
+        for (label, resource, value) in self.entries() {
+            let (_, hard) = getrlimit(resource).map_err(|e| {
+                NonoError::SandboxInit(format!("Cannot read {} limit: {}", label, e))
+            })?;
+            if hard != RLIM_INFINITY && value > hard {
+                return Err(NonoError::SandboxInit(format!(
+                    "Requested {} limit {} exceeds the current hard limit {}",
+                    label, value, hard
+                )));
+            }
+            limits.push((resource, value));
+        }
+        Ok(PreparedLimits(limits))
+    }
+
+    fn entries(&self) -> Vec<(&'static str, Resource, u64)> {
+        [
+            ("cpu time", Resource::RLIMIT_CPU, self.cpu_time),
+            ("address space", Resource::RLIMIT_AS, self.address_space),
+            ("open files", Resource::RLIMIT_NOFILE, self.open_files),
+            ("processes", Resource::RLIMIT_NPROC, self.processes),
+            ("core size", Resource::RLIMIT_CORE, self.core_size),
+            ("file size", Resource::RLIMIT_FSIZE, self.file_size),
+        ]
+        .into_iter()
+        .filter_map(|(label, resource, value)| value.map(|v| (label, resource, v)))
+        .collect()
+    }
+}
+
+/// Validated limits, ready to apply in a forked child.
+pub struct PreparedLimits(Vec<(Resource, u64)>);
+
+impl PreparedLimits {
+    /// Apply the limits to the current process.
+    ///
+    /// Only calls `setrlimit()`, so it is async-signal-safe and may be used
+    /// between fork() and exec().
+    pub fn apply(&self) -> nix::Result<()> {
+        for &(resource, value) in &self.0 {
+            setrlimit(resource, value, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a size such as `4096`, `512K`, `64M`, `4G` or `1TiB` (binary multiples).
+pub fn parse_size(s: &str) -> std::result::Result<u64, String> {
+    let s = s.trim();
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}': expected a number like 512M or 4G", s))?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit
+        .strip_suffix("IB")
+        .or_else(|| unit.strip_suffix('B'))
+        .unwrap_or(&unit);
+    let shift = match unit {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("invalid size '{}': unknown unit", s)),
+    };
+
+    number
+        .checked_mul(1u64 << shift)
+        .ok_or_else(|| format!("invalid size '{}': too large", s))
+}
+
+/// Format a size with the largest unit that divides it exactly (inverse of [`parse_size`])
+pub fn format_size(bytes: u64) -> String {
+    for (shift, unit) in [(40, "T"), (30, "G"), (20, "M"), (10, "K")] {
+        if bytes != 0 && bytes % (1u64 << shift) == 0 {
+            return format!("{}{}", bytes >> shift, unit);
+        }
+    }
+    bytes.to_string()
+}
+
+/// Parse a duration such as `90`, `30s`, `5m`, `1h30m` or `1.5s`.
+///
+/// A bare number is in seconds.
+pub fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}': expected e.g. 30s, 5m or 1h30m", s);
+    let text = s.trim();
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = text.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).map_err(|_| invalid());
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(invalid)?;
+        let (number, tail) = rest.split_at(number_end);
+        let unit_end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let unit_secs = match unit {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return Err(invalid()),
+        };
+        let part = Duration::try_from_secs_f64(number * unit_secs).map_err(|_| invalid())?;
+        total = total.checked_add(part).ok_or_else(invalid)?;
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Format a duration compactly, e.g. `1h30m` or `1.5s` (inverse of [`parse_duration`])
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        let millis = duration.subsec_millis();
+        return match (secs, millis) {
+            (0, millis) => format!("{}ms", millis),
+            (secs, 0) => format!("{}s", secs),
+            (secs, millis) => format!("{}s", secs as f64 + f64::from(millis) / 1000.0),
+        };
+    }
+
+    let mut text = String::new();
+    for (count, unit) in [(secs / 3600, "h"), (secs / 60 % 60, "m"), (secs % 60, "s")] {
+        if count > 0 {
+            text.push_str(&format!("{}{}", count, unit));
+        }
+    }
+    text
+}
+
+/// Parse a CPU bandwidth such as `2` or `0.5`, in CPUs.
+pub fn parse_cpus(s: &str) -> std::result::Result<f64, String> {
+    let cpus: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid CPU count '{}': expected a number like 1.5", s))?;
+    validate_cpus(cpus)
+}
+
+fn validate_cpus(cpus: f64) -> std::result::Result<f64, String> {
+    // cpu.max quotas below 1ms per 100ms period are rejected by the kernel
+    if cpus.is_finite() && cpus >= 0.01 {
+        Ok(cpus)
+    } else {
+        Err(format!("invalid CPU count {}: must be at least 0.01", cpus))
+    }
+}
+
+/// Accept sizes as integers (bytes) or strings with a unit
+fn deserialize_size<'de, D>(deserializer: D) -> std::result::Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match Option::<Size>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Size::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(Size::Text(text)) => parse_size(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_cpus<'de, D>(deserializer: D) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<f64>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(cpus) => validate_cpus(cpus)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("64m"), Ok(64 << 20));
+        assert_eq!(parse_size("4G"), Ok(4 << 30));
+        assert_eq!(parse_size("4GB"), Ok(4 << 30));
+        assert_eq!(parse_size("1TiB"), Ok(1 << 40));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("4X").is_err());
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("99999999999T").is_err());
+
+        for bytes in [0, 1000, 4096, 64 << 20, 4 << 30] {
+            assert_eq!(parse_size(&format_size(bytes)), Ok(bytes));
+        }
+    }
+
+    #[test]
+    fn test_limits_from_toml() {
+        let limits: ResourceLimits = toml::from_str(
+            r#"
+            cpu_time = 600
+            address_space = "4G"
+            processes = 256
+            core_size = 0
+            file_size = 1073741824
+            "#,
+        )
+        .unwrap();
+        assert_eq!(limits.cpu_time, Some(600));
+        assert_eq!(limits.address_space, Some(4 << 30));
+        assert_eq!(limits.open_files, None);
+        assert_eq!(limits.core_size, Some(0));
+        assert_eq!(limits.file_size, Some(1 << 30));
+
+        let described = limits.describe();
+        assert!(described.contains(&("address space", "4G".to_string())));
+        assert!(described.contains(&("cpu time", "600s".to_string())));
+
+        assert!(toml::from_str::<ResourceLimits>("address_space = \"lots\"").is_err());
+        assert!(toml::from_str::<ResourceLimits>("memory_max = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-5").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("m").is_err());
+
+        for text in ["250ms", "1.5s", "45s", "5m", "1h30m", "2h0m5s"] {
+            let duration = parse_duration(text).unwrap();
+            assert_eq!(parse_duration(&format_duration(duration)), Ok(duration));
+        }
+        assert_eq!(format_duration(Duration::from_secs(3605)), "1h5s");
+    }
+
+    #[test]
+    fn test_session_limits() {
+        let limits: ResourceLimits = toml::from_str(
+            r#"
+            memory = "2G"
+            pids = 128
+            cpus = 1.5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(limits.memory, Some(2 << 30));
+        assert_eq!(limits.pids, Some(128));
+        assert_eq!(limits.cpus, Some(1.5));
+        assert!(limits.needs_cgroup());
+        assert!(!limits.is_empty());
+        assert!(limits.prepare().unwrap().0.is_empty());
+
+        assert!(toml::from_str::<ResourceLimits>("cpus = 0").is_err());
+        assert!(toml::from_str::<ResourceLimits>("cpus = 2").is_ok());
+        assert!(parse_cpus("abc").is_err());
+        assert!(parse_cpus("-1").is_err());
+        assert_eq!(parse_cpus("0.5"), Ok(0.5));
+    }
+
+    #[test]
+    fn test_limits_cli_overrides_profile() {
+        let profile = ResourceLimits {
+            cpu_time: Some(60),
+            open_files: Some(256),
+            ..Default::default()
+        };
+        let cli = ResourceLimits {
+            cpu_time: Some(10),
+            ..Default::default()
+        };
+        let merged = cli.or(&profile);
+        assert_eq!(merged.cpu_time, Some(10));
+        assert_eq!(merged.open_files, Some(256));
+        assert!(ResourceLimits::default().is_empty());
+    }
+
+    #[test]
+    fn test_prepare_rejects_raising_hard_limit() {
+        let (_, hard) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+        if hard == RLIM_INFINITY {
+            return;
+        }
+        let limits = ResourceLimits {
+            open_files: Some(hard + 1),
+            ..Default::default()
+        };
+        assert!(limits.prepare().is_err());
+    }
+}
@@ -1,22 +1,31 @@
 mod capability;
+mod cgroup;
+mod cleanup;
 mod cli;
 mod config;
 mod diagnostic;
 mod error;
+mod exec_guard;
 mod exec_strategy;
 mod hooks;
+mod isolate;
 mod keystore;
 mod learn;
+mod limits;
 mod output;
 mod profile;
 mod query;
+mod recording;
 mod sandbox;
 mod sandbox_state;
+mod session_tmp;
 mod setup;
 
-use capability::{CapabilitySet, FsAccess, FsCapability};
+use capability::{CapabilitySet, CapabilitySource, FsAccess, FsCapability};
 use clap::Parser;
-use cli::{Cli, Commands, LearnArgs, SandboxArgs, SetupArgs, ShellArgs, WhyArgs, WhyOp};
+use cli::{
+    Cli, Commands, LearnArgs, ReplayArgs, RunArgs, SandboxArgs, SetupArgs, ShellArgs, WhyArgs,
+};
 use colored::Colorize;
 use error::{NonoError, Result};
 use profile::WorkdirAccess;
@@ -36,6 +45,7 @@ fn main() {
         Commands::Learn(args) => args.verbose,
         Commands::Setup(args) => args.verbose,
         Commands::Why(_) => 0,
+        Commands::Replay(_) => 0,
     };
 
     let env_filter = match verbose {
@@ -66,13 +76,7 @@ fn run(cli: Cli) -> Result<()> {
         Commands::Run(args) => {
             // Print banner for run command (unless silent)
             output::print_banner(cli.silent);
-            run_sandbox(
-                args.sandbox,
-                args.command,
-                args.direct_exec,
-                args.no_diagnostics,
-                cli.silent,
-            )
+            run_sandbox(*args, cli.silent)
         }
         Commands::Shell(args) => {
             // Print banner for shell command (unless silent)
@@ -87,19 +91,25 @@ fn run(cli: Cli) -> Result<()> {
             // Setup prints its own banner
             run_setup(args)
         }
+        Commands::Replay(args) => run_replay(args),
     }
 }
 
+/// Play back a session recorded with --record
+fn run_replay(args: ReplayArgs) -> Result<()> {
+    recording::replay(&args.file, args.speed, args.idle_limit)
+}
+
 /// Set up nono on this system
 fn run_setup(args: SetupArgs) -> Result<()> {
     let runner = setup::SetupRunner::new(&args);
     runner.run()
 }
 
-/// Learn mode: trace file accesses to discover required paths
+/// Learn mode: trace file and network accesses to discover required paths
 fn run_learn(args: LearnArgs, silent: bool) -> Result<()> {
     // Warn user that the command runs unrestricted
-    if !silent {
+    if !silent && !args.sandboxed {
         eprintln!(
             "{}",
             "WARNING: nono learn runs the command WITHOUT any sandbox restrictions.".yellow()
@@ -124,9 +134,37 @@ fn run_learn(args: LearnArgs, silent: bool) -> Result<()> {
         eprintln!();
     }
 
-    eprintln!("nono learn - Tracing file accesses...\n");
+    if args.sandboxed {
+        eprintln!("nono learn - Tracing inside the sandbox, reporting denied accesses...\n");
+    } else {
+        eprintln!("nono learn - Tracing file and network accesses...\n");
+    }
+
+    // With --sandboxed, trace under the sandbox `nono run --allow-cwd` would
+    // build from the profile, session temp directory included
+    let prepared = if args.sandboxed {
+        let sandbox_args = SandboxArgs {
+            profile: args.profile.clone(),
+            allow_cwd: true,
+            ..Default::default()
+        };
+        Some(prepare_sandbox(&sandbox_args, false, silent)?)
+    } else {
+        None
+    };
+    let tmp = prepared.as_ref().and_then(|p| p.tmp.as_ref());
 
-    let result = learn::run_learn(&args)?;
+    let result = learn::run_learn(
+        &args,
+        prepared.as_ref().map(|p| &p.caps),
+        tmp.map(|t| t.path()),
+    )?;
+
+    if args.update {
+        if let Some(ref profile_name) = args.profile {
+            return update_learned_profile(profile_name, &result, silent);
+        }
+    }
 
     if args.toml {
         println!("{}", result.to_toml());
@@ -140,31 +178,116 @@ fn run_learn(args: LearnArgs, silent: bool) -> Result<()> {
         );
     }
 
+    if result.has_network() && !args.toml {
+        eprintln!("Use --toml to generate a [network] section with the observed destinations.");
+    }
+
+    Ok(())
+}
+
+/// Merge learn results into the user's profile file after showing a diff
+fn update_learned_profile(
+    profile_name: &str,
+    result: &learn::LearnResult,
+    silent: bool,
+) -> Result<()> {
+    let path = profile::user_profile_path(profile_name)?;
+    if !path.exists() {
+        return Err(NonoError::LearnError(format!(
+            "--update only edits user profiles, and {} does not exist. \
+             Create it (for example from `nono learn --toml` output) and run again.",
+            path.display()
+        )));
+    }
+
+    let original = std::fs::read_to_string(&path).map_err(|e| NonoError::ProfileRead {
+        path: path.clone(),
+        source: e,
+    })?;
+    let updated = learn::merge_into_profile(&original, result)?;
+
+    if updated == original {
+        eprintln!(
+            "Profile {} already covers everything observed.",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if silent {
+        return Err(NonoError::LearnError(
+            "Cannot confirm profile update in silent mode".to_string(),
+        ));
+    }
+
+    output::print_diff(&original, &updated, &path);
+    eprintln!();
+
+    if !output::prompt_profile_update(&path)? {
+        eprintln!("Profile not modified.");
+        return Ok(());
+    }
+
+    profile::save_profile_file(&path, &updated)?;
+    eprintln!("Updated {}", path.display());
     Ok(())
 }
 
 /// Check why a path or network operation would be allowed or denied
 fn run_why(args: WhyArgs) -> Result<()> {
-    use query::{print_result, query_network, query_path, QueryResult};
+    use query::{print_result, print_trace, Query, QueryResponse, QueryResult, TraceStep};
     use sandbox_state::load_sandbox_state;
 
+    if args.schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&query::json_schema()).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    let query = Query {
+        path: args.path.clone(),
+        op: args.path.as_ref().and(args.op.clone()),
+        host: args.host.clone(),
+        port: args.host.as_ref().map(|_| args.port),
+    };
+    let print = |result: &QueryResult, trace: &[TraceStep]| {
+        if args.json {
+            let mut response = QueryResponse::new(Some(&query), result);
+            if args.explain {
+                response = response.with_trace(trace);
+            }
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&response).unwrap_or_default()
+            );
+        } else {
+            print_result(result);
+            if args.explain && !trace.is_empty() {
+                print_trace(trace);
+            }
+        }
+    };
+
     // Build capability set from args or load from sandbox state
-    let caps = if args.self_query {
+    let mut caps = if args.self_query {
         // Inside sandbox - load from state file
         match load_sandbox_state() {
             Some(state) => state.to_caps(),
+            None if args.batch => {
+                return query::run_batch(
+                    std::io::stdin().lock(),
+                    std::io::stdout().lock(),
+                    None,
+                    args.explain,
+                );
+            }
             None => {
                 let result = QueryResult::NotSandboxed {
                     message: "Not running inside a nono sandbox".to_string(),
                 };
-                if args.json {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&result).unwrap_or_default()
-                    );
-                } else {
-                    print_result(&result);
-                }
+                print(&result, &[]);
                 return Ok(());
             }
         }
@@ -185,19 +308,22 @@ fn run_why(args: WhyArgs) -> Result<()> {
             allow_file: args.allow_file.clone(),
             read_file: args.read_file.clone(),
             write_file: args.write_file.clone(),
+            read_noexec: args.read_noexec.clone(),
+            exec_dir: args.exec_dir.clone(),
             net_block: args.net_block,
-            allow_command: vec![],
-            block_command: vec![],
-            secrets: None,
-            profile: None,
-            allow_cwd: false,
             workdir: args.workdir.clone(),
-            config: None,
-            verbose: 0,
-            dry_run: false,
+            isolate: args.isolate,
+            net: args.net,
+            ..Default::default()
         };
 
-        CapabilitySet::from_profile(&prof, &workdir, &sandbox_args)?
+        let mut caps = CapabilitySet::from_profile(&prof, &workdir, None, &sandbox_args)?;
+        // The working directory, as `nono run --allow-cwd` would share it
+        if let Some(cap) = FsCapability::for_workdir(&prof, &workdir)? {
+            caps.add_fs(cap);
+            caps.deduplicate();
+        }
+        caps
     } else {
         // Build from CLI args
         let sandbox_args = SandboxArgs {
@@ -207,59 +333,54 @@ fn run_why(args: WhyArgs) -> Result<()> {
             allow_file: args.allow_file.clone(),
             read_file: args.read_file.clone(),
             write_file: args.write_file.clone(),
+            read_noexec: args.read_noexec.clone(),
+            exec_dir: args.exec_dir.clone(),
             net_block: args.net_block,
-            allow_command: vec![],
-            block_command: vec![],
-            secrets: None,
-            profile: None,
-            allow_cwd: false,
             workdir: args.workdir.clone(),
-            config: None,
-            verbose: 0,
-            dry_run: false,
+            isolate: args.isolate,
+            net: args.net,
+            ..Default::default()
         };
 
         CapabilitySet::from_args(&sandbox_args)?
     };
+    if !args.self_query {
+        caps.net_block_degraded = caps.net_block && !sandbox::supports_net_block();
+    }
+
+    if args.batch {
+        return query::run_batch(
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+            Some(&caps),
+            args.explain,
+        );
+    }
 
     // Execute the query
-    let result = if let Some(ref path) = args.path {
-        let op = match args.op {
-            Some(WhyOp::Read) => FsAccess::Read,
-            Some(WhyOp::Write) => FsAccess::Write,
-            Some(WhyOp::ReadWrite) => FsAccess::ReadWrite,
-            None => FsAccess::Read, // Default to read
-        };
-        query_path(path, op, &caps)?
-    } else if let Some(ref host) = args.host {
-        query_network(host, args.port, &caps)
-    } else {
+    if query.path.is_none() && query.host.is_none() {
         return Err(NonoError::ConfigParse(
             "--path or --host is required".to_string(),
         ));
-    };
-
-    // Output result
-    if args.json {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&result).unwrap_or_default()
-        );
-    } else {
-        print_result(&result);
     }
+    let (result, trace) = query.explain(&caps)?;
+    print(&result, &trace);
 
     Ok(())
 }
 
 /// Run a command inside the sandbox
-fn run_sandbox(
-    args: SandboxArgs,
-    command: Vec<String>,
-    direct_exec: bool,
-    no_diagnostics: bool,
-    silent: bool,
-) -> Result<()> {
+fn run_sandbox(run_args: RunArgs, silent: bool) -> Result<()> {
+    let RunArgs {
+        sandbox: args,
+        no_diagnostics,
+        record,
+        timeout,
+        kill_after,
+        direct_exec,
+        command,
+    } = run_args;
+
     // Check if we have a command to run
     if command.is_empty() {
         return Err(NonoError::NoCommand);
@@ -275,7 +396,7 @@ fn run_sandbox(
 
     // Dry run mode - just show what would happen
     if args.dry_run {
-        let prepared = prepare_sandbox(&args, silent)?;
+        let prepared = prepare_sandbox(&args, false, silent)?;
         if !prepared.secrets.is_empty() && !silent {
             eprintln!(
                 "  Would inject {} secret(s) as environment variables",
@@ -286,18 +407,19 @@ fn run_sandbox(
         return Ok(());
     }
 
-    let prepared = prepare_sandbox(&args, silent)?;
-    // --exec flag forces Direct mode (TTY preservation), overriding profile
-    let interactive = direct_exec || prepared.interactive;
-    execute_sandboxed(
-        program,
-        cmd_args,
-        &prepared.caps,
-        prepared.secrets,
-        interactive,
+    let prepared = prepare_sandbox(&args, false, silent)?;
+    let options = ExecOptions {
+        strategy: select_exec_strategy(direct_exec, prepared.interactive),
         silent,
         no_diagnostics,
-    )
+        record,
+        timeout: timeout.map(|duration| exec_strategy::Timeout {
+            duration,
+            kill_after: kill_after.unwrap_or(exec_strategy::DEFAULT_KILL_AFTER),
+        }),
+        kill_after,
+    };
+    execute_sandboxed(program, cmd_args, prepared, options)
 }
 
 /// Run an interactive shell inside the sandbox
@@ -314,7 +436,7 @@ fn run_shell(args: ShellArgs, silent: bool) -> Result<()> {
 
     // Dry run mode - just show what would happen
     if args.sandbox.dry_run {
-        let prepared = prepare_sandbox(&args.sandbox, silent)?;
+        let prepared = prepare_sandbox(&args.sandbox, true, silent)?;
         if !prepared.secrets.is_empty() && !silent {
             eprintln!(
                 "  Would inject {} secret(s) as environment variables",
@@ -325,7 +447,7 @@ fn run_shell(args: ShellArgs, silent: bool) -> Result<()> {
         return Ok(());
     }
 
-    let prepared = prepare_sandbox(&args.sandbox, silent)?;
+    let prepared = prepare_sandbox(&args.sandbox, true, silent)?;
 
     if !silent {
         eprintln!(
@@ -336,36 +458,104 @@ fn run_shell(args: ShellArgs, silent: bool) -> Result<()> {
     }
 
     // Shell is always interactive - needs TTY preservation
-    execute_sandboxed(
-        shell_path.into_os_string(),
-        vec![],
-        &prepared.caps,
-        prepared.secrets,
-        true, // Force interactive for shell
+    let options = ExecOptions {
+        strategy: select_exec_strategy(false, true),
         silent,
-        false, // Shell doesn't support --no-diagnostics
-    )
+        no_diagnostics: false, // Shell doesn't support --no-diagnostics
+        record: args.record,
+        timeout: None,
+        kill_after: args.kill_after,
+    };
+    execute_sandboxed(shell_path.into_os_string(), vec![], prepared, options)
+}
+
+/// Choose how to run the sandboxed command.
+///
+/// `--exec` forces Direct mode. Interactive commands (profile `interactive`,
+/// `nono shell`) get a proxied pseudo-terminal when attached to one, and fall
+/// back to Direct mode otherwise; everything else uses Monitor mode.
+fn select_exec_strategy(direct_exec: bool, interactive: bool) -> exec_strategy::ExecStrategy {
+    use std::io::IsTerminal;
+
+    if direct_exec {
+        exec_strategy::ExecStrategy::Direct
+    } else if interactive {
+        if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+            exec_strategy::ExecStrategy::MonitorPty
+        } else {
+            exec_strategy::ExecStrategy::Direct
+        }
+    } else {
+        exec_strategy::ExecStrategy::Monitor
+    }
+}
+
+/// How to run a sandboxed command
+struct ExecOptions {
+    strategy: exec_strategy::ExecStrategy,
+    silent: bool,
+    no_diagnostics: bool,
+    /// Record the session to this file (asciicast v2)
+    record: Option<std::path::PathBuf>,
+    /// Wall-clock timeout (`--timeout`)
+    timeout: Option<exec_strategy::Timeout>,
+    /// Grace period before SIGKILL for leftover processes (`--kill-after`)
+    kill_after: Option<std::time::Duration>,
 }
 
 fn execute_sandboxed(
     program: OsString,
     cmd_args: Vec<OsString>,
-    caps: &CapabilitySet,
-    loaded_secrets: Vec<keystore::LoadedSecret>,
-    interactive: bool,
-    silent: bool,
-    no_diagnostics: bool,
+    prepared: PreparedSandbox,
+    options: ExecOptions,
 ) -> Result<()> {
-    // Check if command is blocked using config module
-    if let Some(blocked) =
-        config::check_blocked_command(&program, &caps.allowed_commands, &caps.blocked_commands)
-    {
-        return Err(NonoError::BlockedCommand {
-            command: blocked,
-            reason: "This command is blocked by default due to destructive potential. \
-                     Use --allow-command to override if you understand the risks."
+    let PreparedSandbox {
+        caps,
+        secrets: loaded_secrets,
+        tmp,
+        ..
+    } = prepared;
+    let caps = &caps;
+    let ExecOptions {
+        strategy,
+        silent,
+        no_diagnostics,
+        record,
+        timeout,
+        kill_after,
+    } = options;
+
+    if timeout.is_some() && strategy == exec_strategy::ExecStrategy::Direct {
+        return Err(NonoError::SandboxInit(
+            "--timeout is not supported in direct exec mode (--exec, or an interactive \
+             profile without a terminal): nono must stay attached to the command"
                 .to_string(),
-        });
+        ));
+    }
+
+    if kill_after.is_some() && strategy == exec_strategy::ExecStrategy::Direct {
+        return Err(NonoError::SandboxInit(
+            "--kill-after is not supported in direct exec mode (--exec, or an interactive \
+             profile without a terminal): nono must stay attached to the command"
+                .to_string(),
+        ));
+    }
+
+    if caps.exec_guard && strategy == exec_strategy::ExecStrategy::Direct {
+        return Err(NonoError::SandboxInit(
+            "--exec-guard is not supported in direct exec mode (--exec, or an interactive \
+             profile without a terminal): nono must stay attached to the command"
+                .to_string(),
+        ));
+    }
+
+    let namespaces = caps.isolate || caps.net_mode != capability::NetMode::Host;
+    if namespaces && strategy == exec_strategy::ExecStrategy::Direct {
+        return Err(NonoError::SandboxInit(
+            "--isolate and --net are not supported in direct exec mode (--exec, or an \
+             interactive profile without a terminal): nono must stay attached to the command"
+                .to_string(),
+        ));
     }
 
     // Convert OsString command to String for exec_strategy
@@ -374,6 +564,23 @@ fn execute_sandboxed(
         .chain(cmd_args.iter().map(|s| s.to_string_lossy().into_owned()))
         .collect();
 
+    // Check if command is blocked using config module
+    if let Some(blocked) = config::check_blocked_command(
+        &command,
+        &caps.allowed_commands,
+        &caps.blocked_commands,
+        &caps.command_rules,
+    ) {
+        return Err(NonoError::BlockedCommand {
+            reason: format!(
+                "{} Use {} to override if you understand the risks.",
+                blocked.reason,
+                blocked.override_hint()
+            ),
+            command: blocked.command,
+        });
+    }
+
     // Dry run mode - just show what would happen
     if command.is_empty() {
         return Err(NonoError::NoCommand);
@@ -384,30 +591,102 @@ fn execute_sandboxed(
     // is not in the sandbox's allowed paths.
     let resolved_program = exec_strategy::resolve_program(&command[0])?;
 
-    // Write capability state file BEFORE applying sandbox.
-    // This file goes to /tmp which may not be in the sandbox's allowed paths.
+    // With [commands] allow_only, the sandbox would refuse to run anything else
+    if let Some(allowlist) = &caps.exec_allowlist {
+        let program = resolved_program
+            .canonicalize()
+            .unwrap_or_else(|_| resolved_program.clone());
+        if !allowlist.contains(&program) {
+            return Err(NonoError::BlockedCommand {
+                command: command[0].clone(),
+                reason: format!(
+                    "{} is not in the profile's [commands] allow_only list.",
+                    program.display()
+                ),
+            });
+        }
+    }
+
+    // Write capability state file BEFORE applying sandbox. It goes to the
+    // system temp directory rather than the session's, which the command can
+    // write to, and the command may only read it.
     let cap_file = write_capability_state_file(caps, silent);
+    let mut caps = caps.clone();
+    if let Some(ref path) = cap_file {
+        let mut cap = FsCapability::new_file(path.clone(), FsAccess::Read)?;
+        cap.source = CapabilitySource::StateFile;
+        caps.add_fs(cap);
+        caps.deduplicate();
+    }
+    let caps = &caps;
     let cap_file_path = cap_file.unwrap_or_else(|| std::path::PathBuf::from("/dev/null"));
 
-    // Apply the sandbox
+    // Open the recording while its directory is still writable
+    let recorder = match record {
+        Some(ref path) if strategy == exec_strategy::ExecStrategy::Direct => {
+            return Err(NonoError::Recording(format!(
+                "cannot record to {} in direct exec mode (--exec, or an interactive \
+                 profile without a terminal): nono must stay attached to the command",
+                path.display()
+            )));
+        }
+        Some(ref path) => Some(std::sync::Arc::new(recording::Recorder::create(
+            path, &command,
+        )?)),
+        None => None,
+    };
+
+    // Create the session cgroup while the cgroup filesystem is still writable
+    let cgroup = if caps.limits.needs_cgroup() {
+        if strategy == exec_strategy::ExecStrategy::Direct {
+            return Err(NonoError::SandboxInit(
+                "session limits (memory, pids, cpus) are not supported in direct exec mode \
+                 (--exec, or an interactive profile without a terminal): nono must stay \
+                 attached to the command"
+                    .to_string(),
+            ));
+        }
+        let cgroup = cgroup::SessionCgroup::create(&caps.limits)?;
+        info!("Session cgroup: {}", cgroup.path().display());
+        Some(cgroup)
+    } else {
+        None
+    };
+
+    // Allocate the pseudo-terminal while /dev/ptmx is still reachable
+    let pty = if strategy == exec_strategy::ExecStrategy::MonitorPty {
+        Some(exec_strategy::open_pty()?)
+    } else {
+        None
+    };
+
+    // With --exec-guard, check the programs the command runs against the
+    // blocklist too, not just the command itself
+    let exec_guard = if caps.exec_guard {
+        exec_guard::ExecGuard::prepare(caps)?
+    } else {
+        None
+    };
+
+    // Apply the sandbox. With --isolate or --net, nono is sandboxed once the
+    // child has set up its namespaces, since Landlock forbids changing mounts
     output::print_applying_sandbox(silent);
-    sandbox::apply(caps)?;
+    let isolation = if namespaces {
+        Some(isolate::Isolation::prepare(caps)?)
+    } else {
+        sandbox::apply(caps)?;
+        None
+    };
     output::print_sandbox_active(silent);
 
     // Build environment variables for the command
-    let env_vars: Vec<(&str, &str)> = loaded_secrets
+    let mut env_vars: Vec<(&str, &str)> = loaded_secrets
         .iter()
         .map(|s| (s.env_var.as_str(), s.value.as_str()))
         .collect();
-
-    // Determine execution strategy
-    // Interactive mode (shell, TUI apps): use Direct exec for TTY preservation
-    // Non-interactive: use Monitor mode for diagnostic output on failure
-    let strategy = if interactive {
-        exec_strategy::ExecStrategy::Direct
-    } else {
-        exec_strategy::ExecStrategy::Monitor
-    };
+    if let Some(dir) = tmp.as_ref().and_then(|t| t.path().to_str()) {
+        env_vars.push(("TMPDIR", dir));
+    }
 
     // Determine threading context for fork safety
     // If secrets were loaded, keyring may have spawned threads
@@ -431,12 +710,20 @@ fn execute_sandboxed(
         cap_file: &cap_file_path,
         no_diagnostics: silent || no_diagnostics,
         threading,
+        recorder,
+        cgroup: cgroup.as_ref(),
+        timeout,
+        kill_after: kill_after.unwrap_or(exec_strategy::DEFAULT_KILL_AFTER),
+        isolation: isolation.as_ref(),
+        exec_guard: exec_guard.as_ref(),
     };
 
     // Execute based on strategy
     match strategy {
         exec_strategy::ExecStrategy::Direct => {
-            // Direct exec: nono disappears after exec
+            // Direct exec: nono disappears after exec. The private temp
+            // directory is removed once the command, which keeps nono's
+            // process, exits
             exec_strategy::execute_direct(&config)?;
             // Note: loaded_secrets will be dropped here, zeroizing the secret values
             unreachable!("execute_direct only returns on error");
@@ -444,6 +731,19 @@ fn execute_sandboxed(
         exec_strategy::ExecStrategy::Monitor => {
             // Monitor mode: fork+wait with diagnostic on failure
             let exit_code = exec_strategy::execute_monitor(&config)?;
+            print_session_usage(&config);
+            print_kept_tmp(tmp.as_ref(), config.no_diagnostics);
+            // Note: loaded_secrets will be dropped here, zeroizing the secret values
+            std::process::exit(exit_code);
+        }
+        exec_strategy::ExecStrategy::MonitorPty => {
+            // Monitor mode on a proxied pseudo-terminal for interactive apps
+            let pty = pty.ok_or_else(|| {
+                NonoError::SandboxInit("pseudo-terminal was not allocated".to_string())
+            })?;
+            let exit_code = exec_strategy::execute_monitor_pty(&config, pty)?;
+            print_session_usage(&config);
+            print_kept_tmp(tmp.as_ref(), config.no_diagnostics);
             // Note: loaded_secrets will be dropped here, zeroizing the secret values
             std::process::exit(exit_code);
         }
@@ -456,15 +756,33 @@ fn execute_sandboxed(
     }
 }
 
+/// Report the session cgroup's resource usage after the command exits
+fn print_session_usage(config: &exec_strategy::ExecConfig<'_>) {
+    if let Some(cgroup) = config.cgroup {
+        output::print_resource_usage(&cgroup.usage(), config.no_diagnostics);
+    }
+}
+
+/// Point at the private temp directory when `--keep-tmp` kept it
+fn print_kept_tmp(tmp: Option<&session_tmp::SessionTmp>, silent: bool) {
+    if let Some(tmp) = tmp.filter(|t| t.is_kept()) {
+        output::print_kept_tmp(tmp.path(), silent);
+    }
+}
+
 /// Result of sandbox preparation
 struct PreparedSandbox {
     caps: CapabilitySet,
     secrets: Vec<keystore::LoadedSecret>,
-    /// Whether the profile indicates interactive mode (needs TTY)
+    /// Whether the command is interactive (`nono shell` or the profile says so)
     interactive: bool,
+    /// The session's private temp directory (not created for dry runs)
+    tmp: Option<session_tmp::SessionTmp>,
 }
 
-fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox> {
+/// Build the capabilities and load secrets. `shell` marks the command as
+/// interactive regardless of the profile.
+fn prepare_sandbox(args: &SandboxArgs, shell: bool, silent: bool) -> Result<PreparedSandbox> {
     // Clean up stale state files from previous nono runs
     // This prevents disk space exhaustion and information disclosure
     sandbox_state::cleanup_stale_state_files();
@@ -522,18 +840,40 @@ fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox>
 
     // Extract config before profile is consumed for secrets
     let profile_workdir_access = loaded_profile.as_ref().map(|p| p.workdir.access.clone());
-    let profile_interactive = loaded_profile
-        .as_ref()
-        .map(|p| p.interactive)
-        .unwrap_or(false);
+    let workdir_noexec = loaded_profile.as_ref().is_some_and(|p| p.workdir.noexec);
+    let interactive = shell
+        || loaded_profile
+            .as_ref()
+            .map(|p| p.interactive)
+            .unwrap_or(false);
+    let min_landlock_abi = loaded_profile.as_ref().and_then(|p| p.min_landlock_abi);
+
+    // Create the private temp directory first, so profiles' $TMPDIR refers to it
+    let tmp = if args.dry_run {
+        None
+    } else {
+        Some(session_tmp::SessionTmp::create(args.keep_tmp)?)
+    };
+    let tmpdir = tmp.as_ref().map(|t| t.path());
 
     // Build capabilities from profile or arguments
     let mut caps = if let Some(ref prof) = loaded_profile {
-        CapabilitySet::from_profile(prof, &workdir, args)?
+        CapabilitySet::from_profile(prof, &workdir, tmpdir, args)?
     } else {
         CapabilitySet::from_args(args)?
     };
 
+    if let Some(dir) = tmpdir {
+        let mut cap = FsCapability::new_dir(dir.to_path_buf(), FsAccess::ReadWrite)?;
+        // Otherwise a program could be copied to $TMPDIR and run from there
+        if workdir_noexec {
+            cap = cap.without_exec();
+        }
+        cap.source = CapabilitySource::SessionTmp;
+        caps.add_fs(cap);
+        caps.deduplicate();
+    }
+
     // Auto-include CWD based on profile [workdir] config or default behavior
     let cwd_access = if let Some(ref access) = profile_workdir_access {
         // Profile loaded: use its [workdir] config
@@ -563,7 +903,9 @@ fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox>
             if args.allow_cwd {
                 // --allow-cwd: add without prompting
                 info!("Auto-including CWD with {} access (--allow-cwd)", access);
-                let cap = FsCapability::new_dir(workdir.clone(), access)?;
+                let mut cap = FsCapability::new_dir(workdir.clone(), access)?;
+                cap.noexec = workdir_noexec;
+                cap.source = CapabilitySource::Workdir;
                 caps.add_fs(cap);
             } else if silent {
                 // Silent mode: cannot prompt, require --allow-cwd
@@ -572,7 +914,9 @@ fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox>
                 // Interactive: prompt user for confirmation
                 let confirmed = output::prompt_cwd_sharing(&cwd_canonical, &access)?;
                 if confirmed {
-                    let cap = FsCapability::new_dir(workdir.clone(), access)?;
+                    let mut cap = FsCapability::new_dir(workdir.clone(), access)?;
+                    cap.noexec = workdir_noexec;
+                    cap.source = CapabilitySource::Workdir;
                     caps.add_fs(cap);
                 } else {
                     info!("User declined CWD sharing. Continuing without automatic CWD access.");
@@ -588,6 +932,21 @@ fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox>
         return Err(NonoError::NoCapabilities);
     }
 
+    // Landlock can only block the network from ABI V4 on; record when it
+    // can't so the summary and `nono why --self` don't claim a block. With
+    // --require-net-block the sandbox setup fails instead.
+    caps.net_block_degraded = caps.net_block && !sandbox::supports_net_block();
+
+    // Interactive commands may open the terminal themselves (/dev/tty)
+    if interactive {
+        for dev in capability::TTY_DEVICES {
+            caps.add_ioctl_device(std::path::Path::new(dev));
+        }
+    }
+
+    // IPC scoping needs Landlock ABI V6; keep only what this kernel enforces
+    caps.scopes.retain(|scope| sandbox::supports_scope(*scope));
+
     // Build secret mappings from profile and/or CLI
     let profile_secrets = loaded_profile
         .map(|p| p.secrets.mappings)
@@ -624,17 +983,30 @@ fn prepare_sandbox(args: &SandboxArgs, silent: bool) -> Result<PreparedSandbox>
 
     info!("{}", sandbox::support_info());
 
+    // The profile may need features older kernels can't enforce
+    if let (Some(min), Some(abi)) = (min_landlock_abi, sandbox::capabilities().abi) {
+        if abi < min {
+            return Err(NonoError::SandboxInit(format!(
+                "profile requires Landlock ABI v{} (min_landlock_abi), but this kernel \
+                 only supports v{}; run `nono setup --check-only` for details",
+                min, abi
+            )));
+        }
+    }
+
     Ok(PreparedSandbox {
         caps,
         secrets: loaded_secrets,
-        interactive: profile_interactive,
+        interactive,
+        tmp,
     })
 }
 
 fn write_capability_state_file(caps: &CapabilitySet, silent: bool) -> Option<std::path::PathBuf> {
     // Write sandbox state for `nono why --self`.
     // This allows sandboxed processes to query their own capabilities.
-    let cap_file = std::env::temp_dir().join(format!(".nono-{}.json", std::process::id()));
+    let cap_file =
+        sandbox_state::system_temp_dir().join(format!(".nono-{}.json", std::process::id()));
     let state = sandbox_state::SandboxState::from_caps(caps);
     if let Err(e) = state.write_to_file(&cap_file) {
         error!(
@@ -678,75 +1050,76 @@ mod tests {
     #[test]
     fn test_check_blocked_command_basic() {
         // Blocked commands should be detected
-        assert!(config::check_blocked_command("rm", &[], &[]).is_some());
-        assert!(config::check_blocked_command("dd", &[], &[]).is_some());
-        assert!(config::check_blocked_command("chmod", &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["rm"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["dd"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["chmod"], &[], &[], &[]).is_some());
 
         // Safe commands should not be blocked
-        assert!(config::check_blocked_command("echo", &[], &[]).is_none());
-        assert!(config::check_blocked_command("ls", &[], &[]).is_none());
-        assert!(config::check_blocked_command("cat", &[], &[]).is_none());
+        assert!(config::check_blocked_command(&["echo"], &[], &[], &[]).is_none());
+        assert!(config::check_blocked_command(&["ls"], &[], &[], &[]).is_none());
+        assert!(config::check_blocked_command(&["cat"], &[], &[], &[]).is_none());
     }
 
     #[test]
     fn test_check_blocked_command_with_path() {
         // Full paths should still be detected
-        assert!(config::check_blocked_command("/bin/rm", &[], &[]).is_some());
-        assert!(config::check_blocked_command("/usr/bin/dd", &[], &[]).is_some());
-        assert!(config::check_blocked_command("./rm", &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["/bin/rm"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["/usr/bin/dd"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["./rm"], &[], &[], &[]).is_some());
     }
 
     #[test]
     fn test_check_blocked_command_allow_override() {
         // Explicitly allowed commands should not be blocked
         let allowed = vec!["rm".to_string()];
-        assert!(config::check_blocked_command("rm", &allowed, &[]).is_none());
+        assert!(config::check_blocked_command(&["rm"], &allowed, &[], &[]).is_none());
 
         // Other commands still blocked
-        assert!(config::check_blocked_command("dd", &allowed, &[]).is_some());
+        assert!(config::check_blocked_command(&["dd"], &allowed, &[], &[]).is_some());
     }
 
     #[test]
     fn test_check_blocked_command_extra_blocked() {
         // Extra blocked commands should be detected
         let extra = vec!["custom-dangerous".to_string()];
-        assert!(config::check_blocked_command("custom-dangerous", &[], &extra).is_some());
+        assert!(config::check_blocked_command(&["custom-dangerous"], &[], &extra, &[]).is_some());
 
         // Default blocked still works
-        assert!(config::check_blocked_command("rm", &[], &extra).is_some());
+        assert!(config::check_blocked_command(&["rm"], &[], &extra, &[]).is_some());
     }
 
     #[test]
     fn test_check_blocked_command_no_file_name() {
         // Edge case: path with no file name (e.g., just "/")
         // Should fall back to using the full path and not crash
-        assert!(config::check_blocked_command("/", &[], &[]).is_none());
-        assert!(config::check_blocked_command("", &[], &[]).is_none());
+        assert!(config::check_blocked_command(&["/"], &[], &[], &[]).is_none());
+        assert!(config::check_blocked_command(&[""], &[], &[], &[]).is_none());
     }
 
     #[test]
     fn test_check_blocked_command_osstr_comparison() {
         // Verify OsStr comparison works correctly for various path formats
-        assert!(config::check_blocked_command("rm", &[], &[]).is_some());
-        assert!(config::check_blocked_command("./rm", &[], &[]).is_some());
-        assert!(config::check_blocked_command("../rm", &[], &[]).is_some());
-        assert!(config::check_blocked_command("/usr/local/bin/rm", &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["rm"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["./rm"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["../rm"], &[], &[], &[]).is_some());
+        assert!(config::check_blocked_command(&["/usr/local/bin/rm"], &[], &[], &[]).is_some());
 
         // Nested paths should still extract correct binary name
         assert!(
-            config::check_blocked_command("/some/deeply/nested/path/to/rm", &[], &[]).is_some()
+            config::check_blocked_command(&["/some/deeply/nested/path/to/rm"], &[], &[], &[])
+                .is_some()
         );
     }
 
     #[test]
-    fn test_check_sensitive_path() {
+    fn test_find_sensitive_path() {
         // Verify sensitive path checking works
-        assert!(config::check_sensitive_path("~/.ssh").is_some());
-        assert!(config::check_sensitive_path("~/.aws").is_some());
-        assert!(config::check_sensitive_path("~/.bashrc").is_some());
+        assert!(config::find_sensitive_path("~/.ssh").is_some());
+        assert!(config::find_sensitive_path("~/.aws").is_some());
+        assert!(config::find_sensitive_path("~/.bashrc").is_some());
 
         // Non-sensitive paths should return None
-        assert!(config::check_sensitive_path("/tmp").is_none());
-        assert!(config::check_sensitive_path("~/Documents").is_none());
+        assert!(config::find_sensitive_path("/tmp").is_none());
+        assert!(config::find_sensitive_path("~/Documents").is_none());
     }
 }
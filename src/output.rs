@@ -1,6 +1,7 @@
 //! CLI output styling for nono
 
-use crate::capability::{CapabilitySet, FsAccess};
+use crate::capability::{CapabilitySet, FsAccess, NetMode};
+use crate::cgroup::ResourceUsage;
 use crate::error::{NonoError, Result};
 use colored::Colorize;
 use rand::seq::IndexedRandom;
@@ -49,7 +50,11 @@ pub fn print_capabilities(caps: &CapabilitySet, silent: bool) {
         eprintln!("  {}", "Filesystem:".white());
         for cap in &caps.fs {
             let kind = cap.kind_label();
-            let access_str = format!("{}", cap.access);
+            let access_str = if cap.noexec {
+                format!("{}, noexec", cap.access)
+            } else {
+                format!("{}", cap.access)
+            };
             let access_colored = match cap.access {
                 crate::capability::FsAccess::Read => access_str.green(),
                 crate::capability::FsAccess::Write => access_str.yellow(),
@@ -66,11 +71,65 @@ pub fn print_capabilities(caps: &CapabilitySet, silent: bool) {
 
     // Network status
     eprintln!("  {}", "Network:".white());
-    if caps.net_block {
+    if caps.net_block_degraded {
+        eprintln!(
+            "    outbound: {}",
+            "allowed (--net-block not enforced by this kernel)".yellow()
+        );
+    } else if caps.net_block {
         eprintln!("    outbound: {}", "blocked".red());
     } else {
         eprintln!("    outbound: {}", "allowed".green());
     }
+    match caps.net_mode {
+        NetMode::Host => {}
+        NetMode::None => eprintln!("    namespace: {}", "none (no interfaces)".red()),
+        NetMode::Loopback => eprintln!("    namespace: {}", "loopback only".yellow()),
+    }
+
+    if let Some(allowlist) = &caps.exec_allowlist {
+        eprintln!("  {}", "Executables:".white());
+        for program in allowlist {
+            eprintln!(
+                "    {} [{}]",
+                program.display().to_string().white(),
+                "exec".yellow()
+            );
+        }
+    }
+
+    if !caps.ioctl_devices.is_empty() {
+        eprintln!("  {}", "Devices:".white());
+        for dev in &caps.ioctl_devices {
+            eprintln!(
+                "    {} [{}]",
+                dev.display().to_string().white(),
+                "ioctl".yellow()
+            );
+        }
+    }
+
+    if !caps.scopes.is_empty() {
+        let scopes: Vec<String> = caps.scopes.iter().map(|s| s.to_string()).collect();
+        eprintln!("  {}", "IPC:".white());
+        eprintln!("    scoped to sandbox: {}", scopes.join(", ").green());
+    }
+
+    if caps.isolate {
+        eprintln!("  {}", "Isolation:".white());
+        eprintln!(
+            "    root: {}",
+            "system and granted paths only (--isolate)".green()
+        );
+    }
+
+    // Resource limits
+    if !caps.limits.is_empty() {
+        eprintln!("  {}", "Limits:".white());
+        for (label, value) in caps.limits.describe() {
+            eprintln!("    {}: {}", label, value.yellow());
+        }
+    }
 
     eprintln!();
 }
@@ -98,6 +157,65 @@ pub fn print_sandbox_active(silent: bool) {
     eprintln!();
 }
 
+/// Print the session's resource usage after the command exits
+pub fn print_resource_usage(usage: &ResourceUsage, silent: bool) {
+    if silent {
+        return;
+    }
+
+    let mut parts = Vec::new();
+    if let Some(peak) = usage.peak_memory {
+        parts.push(format!("peak memory {}", format_bytes(peak)));
+    }
+    if let Some(cpu) = usage.cpu_time {
+        let mut part = format!("CPU {:.1}s", cpu.as_secs_f64());
+        if let (Some(user), Some(system)) = (usage.user_time, usage.system_time) {
+            part.push_str(&format!(
+                " (user {:.1}s, system {:.1}s)",
+                user.as_secs_f64(),
+                system.as_secs_f64()
+            ));
+        }
+        parts.push(part);
+    }
+    if parts.is_empty() {
+        return;
+    }
+
+    eprintln!("{} {}", "[nono] Resource usage:".white(), parts.join(", "));
+}
+
+/// Print where the kept private temp directory is (`--keep-tmp`)
+pub fn print_kept_tmp(path: &Path, silent: bool) {
+    if silent {
+        return;
+    }
+
+    eprintln!(
+        "{} {}",
+        "[nono] Kept private temp directory:".white(),
+        path.display()
+    );
+}
+
+/// Format a byte count with a binary unit, e.g. `512.0 MiB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
 /// Print dry run message
 pub fn print_dry_run(program: &OsStr, cmd_args: &[OsString], silent: bool) {
     if silent {
@@ -118,6 +236,61 @@ pub fn print_dry_run(program: &OsStr, cmd_args: &[OsString], silent: bool) {
     eprintln!("Command: {:?}", command);
 }
 
+/// Print a unified diff between two versions of a file
+pub fn print_diff(old: &str, new: &str, path: &Path) {
+    let name = path.display().to_string();
+    let diff = similar::TextDiff::from_lines(old, new);
+    let rendered = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&name, &name)
+        .to_string();
+
+    for line in rendered.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            eprintln!("{}", line.white().bold());
+        } else if line.starts_with('+') {
+            eprintln!("{}", line.green());
+        } else if line.starts_with('-') {
+            eprintln!("{}", line.red());
+        } else if line.starts_with("@@") {
+            eprintln!("{}", line.truecolor(150, 150, 150));
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+/// Prompt the user to confirm writing an updated profile.
+///
+/// Returns `Ok(false)` with a hint if stdin is not a TTY.
+pub fn prompt_profile_update(path: &Path) -> Result<bool> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        eprintln!(
+            "{}",
+            "Not updating profile (non-interactive). Run in a terminal to confirm the change."
+                .truecolor(150, 150, 150),
+        );
+        return Ok(false);
+    }
+
+    eprint!(
+        "  {} ",
+        format!("Write changes to {}? [y/N]:", path.display()).white()
+    );
+    std::io::stderr().flush().ok();
+
+    let mut input = String::new();
+    stdin
+        .lock()
+        .read_line(&mut input)
+        .map_err(NonoError::CommandExecution)?;
+
+    let answer = input.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
 /// Prompt the user to confirm sharing the current working directory.
 ///
 /// Returns `Ok(true)` if user confirms, `Ok(false)` if user declines.
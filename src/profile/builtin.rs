@@ -1,9 +1,11 @@
 //! Built-in profiles compiled into the nono binary
 
 use super::{
-    FilesystemConfig, HookConfig, HooksConfig, NetworkConfig, Profile, ProfileMeta, SecretsConfig,
-    WorkdirAccess, WorkdirConfig,
+    CommandsConfig, DevicesConfig, FilesystemConfig, HookConfig, HooksConfig, IpcConfig,
+    NetworkConfig, Profile, ProfileMeta, ProfileOrigin, SecretsConfig, WorkdirAccess,
+    WorkdirConfig,
 };
+use crate::limits::ResourceLimits;
 use std::collections::HashMap;
 
 /// Get a built-in profile by name
@@ -12,6 +14,7 @@ pub fn get_builtin(name: &str) -> Option<Profile> {
         "claude-code" => Some(claude_code()),
         "openclaw" => Some(openclaw()),
         "opencode" => Some(opencode()),
+        "hardened" => Some(hardened()),
         _ => None,
     }
 }
@@ -23,6 +26,7 @@ pub fn list_builtin() -> Vec<String> {
         "claude-code".to_string(),
         "openclaw".to_string(),
         "opencode".to_string(),
+        "hardened".to_string(),
     ]
 }
 
@@ -66,14 +70,26 @@ fn claude_code() -> Profile {
                 "$HOME/.gitignore_global".to_string(),
             ],
             write_file: vec![],
+            read_noexec: vec![],
+            exec_dir: vec![],
         },
-        network: NetworkConfig { block: false },
+        network: NetworkConfig {
+            block: false,
+            ..Default::default()
+        },
+        ipc: IpcConfig::default(),
+        devices: DevicesConfig::default(),
+        commands: CommandsConfig::default(),
         secrets: SecretsConfig::default(),
         workdir: WorkdirConfig {
             access: WorkdirAccess::ReadWrite,
+            noexec: false,
         },
         hooks: HooksConfig { hooks },
+        limits: ResourceLimits::default(),
         interactive: true, // Claude Code has interactive TUI
+        min_landlock_abi: None,
+        origin: ProfileOrigin::default(),
     }
 }
 
@@ -91,21 +107,32 @@ fn openclaw() -> Profile {
                 "$HOME/.openclaw".to_string(),
                 "$HOME/.config/openclaw".to_string(),
                 "$HOME/.local".to_string(),
-                "$TMPDIR/openclaw-$UID".to_string(),
             ],
             read: vec![],
             write: vec![],
             allow_file: vec![],
             read_file: vec![],
             write_file: vec![],
+            read_noexec: vec![],
+            exec_dir: vec![],
+        },
+        network: NetworkConfig {
+            block: false,
+            ..Default::default()
         },
-        network: NetworkConfig { block: false },
+        ipc: IpcConfig::default(),
+        devices: DevicesConfig::default(),
+        commands: CommandsConfig::default(),
         secrets: SecretsConfig::default(),
         workdir: WorkdirConfig {
             access: WorkdirAccess::Read,
+            noexec: false,
         },
         hooks: HooksConfig::default(),
+        limits: ResourceLimits::default(),
         interactive: false,
+        min_landlock_abi: None,
+        origin: ProfileOrigin::default(),
     }
 }
 
@@ -127,24 +154,67 @@ fn opencode() -> Profile {
                 "$HOME/.opencode".to_string(),
                 "$HOME/.npm".to_string(),
                 "$HOME/.nvm".to_string(),
-                // /tmp required: opencode writes directly to $TMPDIR with dynamic
-                // filenames (e.g., {timestamp}.md, opencode-clipboard.png) rather
-                // than using a subdirectory. Cannot grant specific paths.
-                "/tmp".to_string(),
+                // opencode writes directly to $TMPDIR with dynamic filenames
+                // (e.g., {timestamp}.md, opencode-clipboard.png); the session's
+                // private temp directory covers these.
             ],
             read: vec!["$HOME/.config/git".to_string()],
             write: vec![],
             allow_file: vec![],
             read_file: vec![],
             write_file: vec![],
+            read_noexec: vec![],
+            exec_dir: vec![],
+        },
+        network: NetworkConfig {
+            block: false,
+            ..Default::default()
         },
-        network: NetworkConfig { block: false },
+        ipc: IpcConfig::default(),
+        devices: DevicesConfig::default(),
+        commands: CommandsConfig::default(),
         secrets: SecretsConfig::default(),
         workdir: WorkdirConfig {
             access: WorkdirAccess::ReadWrite,
+            noexec: false,
         },
         hooks: HooksConfig::default(),
+        limits: ResourceLimits::default(),
         interactive: true,
+        min_landlock_abi: None,
+        origin: ProfileOrigin::default(),
+    }
+}
+
+/// Untrusted code in the working directory, e.g. a freshly cloned repository
+fn hardened() -> Profile {
+    Profile {
+        meta: ProfileMeta {
+            name: "hardened".to_string(),
+            version: "1.0.0".to_string(),
+            description: Some(
+                "Untrusted working directory: writable, but nothing in it runs".to_string(),
+            ),
+            author: Some("nono-project".to_string()),
+        },
+        filesystem: FilesystemConfig::default(),
+        network: NetworkConfig {
+            block: true,
+            ..Default::default()
+        },
+        ipc: IpcConfig::default(),
+        devices: DevicesConfig::default(),
+        commands: CommandsConfig::default(),
+        secrets: SecretsConfig::default(),
+        workdir: WorkdirConfig {
+            access: WorkdirAccess::ReadWrite,
+            noexec: true,
+        },
+        hooks: HooksConfig::default(),
+        limits: ResourceLimits::default(),
+        interactive: false,
+        min_landlock_abi: None,
+        origin: ProfileOrigin::default(),
     }
 }
 
@@ -173,6 +243,14 @@ mod tests {
             .contains(&"$HOME/.openclaw".to_string()));
     }
 
+    #[test]
+    fn test_get_builtin_hardened() {
+        let profile = get_builtin("hardened").unwrap();
+        assert_eq!(profile.workdir.access, WorkdirAccess::ReadWrite);
+        assert!(profile.workdir.noexec);
+        assert!(profile.network.block);
+    }
+
     #[test]
     fn test_get_builtin_nonexistent() {
         assert!(get_builtin("nonexistent").is_none());
@@ -184,5 +262,6 @@ mod tests {
         assert!(profiles.contains(&"claude-code".to_string()));
         assert!(profiles.contains(&"openclaw".to_string()));
         assert!(profiles.contains(&"opencode".to_string()));
+        assert!(profiles.contains(&"hardened".to_string()));
     }
 }
@@ -6,7 +6,9 @@
 
 mod builtin;
 
+use crate::config::CommandRule;
 use crate::error::{NonoError, Result};
+use crate::limits::ResourceLimits;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
@@ -46,17 +48,116 @@ pub struct FilesystemConfig {
     /// Single files with write-only access
     #[serde(default)]
     pub write_file: Vec<String>,
+    /// Directories with read-only access where nothing may be executed
+    #[serde(default)]
+    pub read_noexec: Vec<String>,
+    /// Directories whose programs may be executed, with read access
+    #[serde(default)]
+    pub exec_dir: Vec<String>,
 }
 
 /// Network configuration in a profile
 #[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct NetworkConfig {
     /// Block network access (network allowed by default; true = blocked)
     #[serde(default)]
     pub block: bool,
+    /// What to do when `block` can't be enforced by the kernel
+    #[serde(default)]
+    pub enforcement: NetEnforcement,
+    /// Destinations recorded by `nono learn` (not enforced)
+    #[serde(default)]
+    pub observed: ObservedNetwork,
     // Future: dns_only, proxy_allow
 }
 
+/// Network destinations `nono learn` saw the application use
+///
+/// Advisory only: the sandbox enforces network on/off, not per-host or
+/// per-port rules. `nono learn --profile` uses these to report only new
+/// destinations.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObservedNetwork {
+    /// Hosts the application contacted or looked up
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Outbound ports the application connected to
+    #[serde(default)]
+    pub connect_ports: Vec<u16>,
+    /// Local ports the application listened on
+    #[serde(default)]
+    pub listen_ports: Vec<u16>,
+}
+
+/// How a profile's `network.block` is enforced
+///
+/// Landlock only blocks TCP from ABI V4 (Linux 6.7) on. On older kernels
+/// `best_effort` runs with the network open and a warning, `strict` refuses
+/// to start the sandbox.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetEnforcement {
+    /// Warn and continue without network blocking
+    #[default]
+    BestEffort,
+    /// Fail sandbox setup (same as `--require-net-block`)
+    Strict,
+}
+
+/// Device configuration in a profile
+///
+/// Landlock (ABI V5+) denies ioctls on device files opened in the sandbox,
+/// except on the devices listed here. The command still needs read or write
+/// access to open them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DevicesConfig {
+    /// Device files or directories of devices the command may issue ioctls on
+    #[serde(default)]
+    pub ioctl: Vec<String>,
+}
+
+/// IPC configuration in a profile
+///
+/// On kernels with Landlock ABI V6+, the command cannot connect to abstract
+/// Unix sockets or signal processes outside the sandbox. These opt out.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IpcConfig {
+    /// Allow connecting to abstract Unix sockets outside the sandbox (D-Bus, X11)
+    #[serde(default)]
+    pub allow_abstract_unix_sockets: bool,
+    /// Allow signalling processes outside the sandbox
+    #[serde(default)]
+    pub allow_signals: bool,
+}
+
+/// Command configuration in a profile
+///
+/// Adjusts the default dangerous command blocklist for this profile.
+/// CLI flags (--allow-command / --block-command) extend these lists.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommandsConfig {
+    /// Normally-blocked commands this profile needs (overrides the default blocklist)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Additional commands to block beyond the default blocklist
+    #[serde(default)]
+    pub block: Vec<String>,
+    /// Commands to block only with certain arguments (`[[commands.rules]]`)
+    #[serde(default)]
+    pub rules: Vec<CommandRule>,
+    /// The only programs the command may execute, by name or path
+    #[serde(default)]
+    pub allow_only: Option<Vec<String>>,
+    /// Check every program the command runs against the blocklist, as
+    /// `--exec-guard` does
+    #[serde(default)]
+    pub guard: bool,
+}
+
 /// Secrets configuration in a profile
 ///
 /// Maps keystore account names to environment variable names.
@@ -121,6 +222,9 @@ pub struct WorkdirConfig {
     /// Access level for the current working directory
     #[serde(default)]
     pub access: WorkdirAccess,
+    /// Programs in the working directory may not be executed
+    #[serde(default)]
+    pub noexec: bool,
 }
 
 /// A complete profile definition
@@ -133,14 +237,69 @@ pub struct Profile {
     #[serde(default)]
     pub network: NetworkConfig,
     #[serde(default)]
+    pub ipc: IpcConfig,
+    #[serde(default)]
+    pub devices: DevicesConfig,
+    #[serde(default)]
+    pub commands: CommandsConfig,
+    #[serde(default)]
     pub secrets: SecretsConfig,
     #[serde(default)]
     pub workdir: WorkdirConfig,
     #[serde(default)]
     pub hooks: HooksConfig,
-    /// App has interactive UI that needs TTY preserved (implies --exec mode)
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    /// App has interactive UI that needs a terminal (runs on a proxied pty)
     #[serde(default)]
     pub interactive: bool,
+    /// Refuse to run on kernels with an older Landlock ABI (Linux only)
+    #[serde(default)]
+    pub min_landlock_abi: Option<u32>,
+    /// Where the profile was loaded from
+    #[serde(skip)]
+    pub origin: ProfileOrigin,
+}
+
+/// Where a profile was loaded from, so `nono why --explain` can point at
+/// the entry that granted a path
+#[derive(Debug, Clone, Default)]
+pub struct ProfileOrigin {
+    /// The profile file (`None` for built-in profiles)
+    pub file: Option<PathBuf>,
+    /// Line of each `[filesystem]` entry, by key and index in its list
+    lines: HashMap<(String, usize), usize>,
+}
+
+impl ProfileOrigin {
+    /// Record the lines of the `[filesystem]` entries in a profile file
+    fn parse(file: &Path, content: &str) -> Self {
+        let mut lines = HashMap::new();
+        if let Ok(doc) = toml_edit::Document::parse(content) {
+            if let Some(filesystem) = doc.get("filesystem").and_then(|t| t.as_table_like()) {
+                for (key, item) in filesystem.iter() {
+                    let Some(entries) = item.as_array() else {
+                        continue;
+                    };
+                    for (index, entry) in entries.iter().enumerate() {
+                        if let Some(span) = entry.span() {
+                            let line = content[..span.start].matches('\n').count() + 1;
+                            lines.insert((key.to_string(), index), line);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            file: Some(file.to_path_buf()),
+            lines,
+        }
+    }
+
+    /// Line of entry `index` of `[filesystem]` key `key` in the profile file
+    pub fn line(&self, key: &str, index: usize) -> Option<usize> {
+        self.lines.get(&(key.to_string(), index)).copied()
+    }
 }
 
 /// Load a profile by name
@@ -180,7 +339,39 @@ fn load_from_file(path: &Path) -> Result<Profile> {
         source: e,
     })?;
 
-    toml::from_str(&content).map_err(|e| NonoError::ProfileParse(e.to_string()))
+    let mut profile: Profile =
+        toml::from_str(&content).map_err(|e| NonoError::ProfileParse(e.to_string()))?;
+    profile.origin = ProfileOrigin::parse(path, &content);
+    Ok(profile)
+}
+
+/// Path of the user profile file for `name` (which may not exist)
+pub fn user_profile_path(name: &str) -> Result<PathBuf> {
+    if !is_valid_profile_name(name) {
+        return Err(NonoError::ProfileParse(format!(
+            "Invalid profile name '{}': must be alphanumeric with hyphens only",
+            name
+        )));
+    }
+    get_user_profile_path(name)
+}
+
+/// Write a user profile file, replacing it atomically.
+///
+/// The content is validated as a profile before anything is written.
+pub fn save_profile_file(path: &Path, content: &str) -> Result<()> {
+    toml::from_str::<Profile>(content).map_err(|e| NonoError::ProfileParse(e.to_string()))?;
+
+    let tmp_path = path.with_extension("toml.tmp");
+    fs::write(&tmp_path, content)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            NonoError::ConfigWrite {
+                path: path.to_path_buf(),
+                source: e,
+            }
+        })
 }
 
 /// Get the path to a user profile
@@ -216,19 +407,23 @@ fn is_valid_profile_name(name: &str) -> bool {
 /// - $HOME: User's home directory
 /// - $XDG_CONFIG_HOME: XDG config directory
 /// - $XDG_DATA_HOME: XDG data directory
-/// - $TMPDIR: System temporary directory
+/// - $TMPDIR: The session's private temp directory, or the system temporary directory
+///   when `tmpdir` is `None`
 /// - $UID: Current user ID
 ///
 /// If $HOME cannot be determined and the path uses $HOME, $XDG_CONFIG_HOME, or $XDG_DATA_HOME,
 /// the unexpanded variable is left in place (which will cause the path to not exist).
-pub fn expand_vars(path: &str, workdir: &Path) -> PathBuf {
+pub fn expand_vars(path: &str, workdir: &Path, tmpdir: Option<&Path>) -> PathBuf {
     let home = xdg_home::home_dir().map(|p| p.to_string_lossy().to_string());
 
     let expanded = path.replace("$WORKDIR", &workdir.to_string_lossy());
 
     // Expand $TMPDIR and $UID
-    let tmpdir = std::env::var("TMPDIR")
-        .unwrap_or_else(|_| std::env::temp_dir().to_string_lossy().to_string());
+    let tmpdir = match tmpdir {
+        Some(dir) => dir.to_string_lossy().to_string(),
+        None => std::env::var("TMPDIR")
+            .unwrap_or_else(|_| std::env::temp_dir().to_string_lossy().to_string()),
+    };
     let uid = nix::unistd::getuid().to_string();
     let expanded = expanded
         .replace("$TMPDIR", tmpdir.trim_end_matches('/'))
@@ -307,11 +502,52 @@ mod tests {
         let workdir = PathBuf::from("/projects/myapp");
         env::set_var("HOME", "/home/user");
 
-        let expanded = expand_vars("$WORKDIR/src", &workdir);
+        let expanded = expand_vars("$WORKDIR/src", &workdir, None);
         assert_eq!(expanded, PathBuf::from("/projects/myapp/src"));
 
-        let expanded = expand_vars("$HOME/.config", &workdir);
+        let expanded = expand_vars("$HOME/.config", &workdir, None);
         assert_eq!(expanded, PathBuf::from("/home/user/.config"));
+
+        let tmpdir = PathBuf::from("/tmp/nono-abc123");
+        let expanded = expand_vars("$TMPDIR/cache", &workdir, Some(&tmpdir));
+        assert_eq!(expanded, PathBuf::from("/tmp/nono-abc123/cache"));
+    }
+
+    #[test]
+    fn test_load_from_file_records_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.toml");
+        fs::write(
+            &path,
+            "[meta]\nname = \"test\"\n\n[filesystem]\nallow = [\n  \"/a\",\n  \"/b\",\n]\nread = [\"/c\"]\n",
+        )
+        .unwrap();
+
+        let profile = load_from_file(&path).unwrap();
+        assert_eq!(profile.origin.file, Some(path));
+        assert_eq!(profile.origin.line("allow", 0), Some(6));
+        assert_eq!(profile.origin.line("allow", 1), Some(7));
+        assert_eq!(profile.origin.line("read", 0), Some(9));
+        assert_eq!(profile.origin.line("write", 0), None);
+    }
+
+    #[test]
+    fn test_save_profile_file_rejects_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.toml");
+        fs::write(&path, "[meta]\nname = \"test\"\n").unwrap();
+
+        // Invalid profiles are refused and the original is untouched
+        let result = save_profile_file(&path, "[workdir]\naccess = \"everything\"\n");
+        assert!(matches!(result, Err(NonoError::ProfileParse(_))));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[meta]\nname = \"test\"\n"
+        );
+
+        save_profile_file(&path, "[meta]\nname = \"updated\"\n").unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("updated"));
+        assert!(!path.with_extension("toml.tmp").exists());
     }
 
     #[test]
@@ -369,6 +605,127 @@ mod tests {
         assert!(profile.secrets.mappings.is_empty());
     }
 
+    #[test]
+    fn test_commands_config_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [commands]
+            allow = ["cp", "rm"]
+            guard = true
+            block = ["curl"]
+
+            [[commands.rules]]
+            pattern = "npm publish"
+            reason = "Releases are published by CI"
+
+            [[commands.rules]]
+            pattern = "git push * --force*"
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert_eq!(profile.commands.allow, vec!["cp", "rm"]);
+        assert!(profile.commands.guard);
+        assert_eq!(profile.commands.block, vec!["curl"]);
+        assert_eq!(profile.commands.rules.len(), 2);
+        assert_eq!(profile.commands.rules[0].pattern, "npm publish");
+        assert_eq!(
+            profile.commands.rules[0].reason,
+            "Releases are published by CI"
+        );
+        assert!(profile.commands.rules[1].reason.is_empty());
+    }
+
+    #[test]
+    fn test_commands_allow_only_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [commands]
+            allow_only = ["node", "git", "$HOME/.cargo/bin/rg"]
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            profile.commands.allow_only,
+            Some(vec![
+                "node".to_string(),
+                "git".to_string(),
+                "$HOME/.cargo/bin/rg".to_string()
+            ])
+        );
+
+        let profile: Profile = toml::from_str("[meta]\nname = \"x\"").unwrap();
+        assert_eq!(profile.commands.allow_only, None);
+    }
+
+    #[test]
+    fn test_network_enforcement_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [network]
+            block = true
+            enforcement = "strict"
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert!(profile.network.block);
+        assert_eq!(profile.network.enforcement, NetEnforcement::Strict);
+
+        let profile: Profile = toml::from_str("[meta]\nname = \"test\"\n").unwrap();
+        assert_eq!(profile.network.enforcement, NetEnforcement::BestEffort);
+    }
+
+    #[test]
+    fn test_network_observed_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [network.observed]
+            hosts = ["example.com"]
+            connect_ports = [443]
+            listen_ports = [3000]
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert_eq!(profile.network.observed.hosts, vec!["example.com"]);
+        assert_eq!(profile.network.observed.connect_ports, vec![443]);
+        assert_eq!(profile.network.observed.listen_ports, vec![3000]);
+
+        // Per-host rules aren't enforced, so they can't be written as if they were
+        let toml_str = "[meta]\nname = \"test\"\n[network]\nallow_hosts = [\"example.com\"]\n";
+        assert!(toml::from_str::<Profile>(toml_str).is_err());
+    }
+
+    #[test]
+    fn test_min_landlock_abi_parsing() {
+        let profile: Profile =
+            toml::from_str("min_landlock_abi = 4\n[meta]\nname = \"test\"\n").unwrap();
+        assert_eq!(profile.min_landlock_abi, Some(4));
+
+        let profile: Profile = toml::from_str("[meta]\nname = \"test\"\n").unwrap();
+        assert_eq!(profile.min_landlock_abi, None);
+    }
+
+    #[test]
+    fn test_devices_config_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [devices]
+            ioctl = ["/dev/tty", "/dev/kvm"]
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert_eq!(profile.devices.ioctl, vec!["/dev/tty", "/dev/kvm"]);
+    }
+
     #[test]
     fn test_workdir_config_readwrite() {
         let toml_str = r#"
@@ -383,6 +740,30 @@ mod tests {
         assert_eq!(profile.workdir.access, WorkdirAccess::ReadWrite);
     }
 
+    #[test]
+    fn test_noexec_config_parsing() {
+        let toml_str = r#"
+            [meta]
+            name = "test-profile"
+
+            [filesystem]
+            read_noexec = ["$HOME/Downloads"]
+            exec_dir = ["$WORKDIR/node_modules/.bin"]
+
+            [workdir]
+            access = "readwrite"
+            noexec = true
+        "#;
+
+        let profile: Profile = toml::from_str(toml_str).unwrap();
+        assert_eq!(profile.filesystem.read_noexec, vec!["$HOME/Downloads"]);
+        assert_eq!(
+            profile.filesystem.exec_dir,
+            vec!["$WORKDIR/node_modules/.bin"]
+        );
+        assert!(profile.workdir.noexec);
+    }
+
     #[test]
     fn test_workdir_config_read() {
         let toml_str = r#"
@@ -5,12 +5,96 @@
 //! error, it can call `nono query` to get a structured JSON response explaining
 //! why and how to fix it.
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
 
-use crate::capability::{CapabilitySet, FsAccess};
+use crate::capability::{CapabilitySet, CapabilitySource, FsAccess, FsCapability, NetMode};
+use crate::cli::WhyOp;
 use crate::config;
 use crate::error::{NonoError, Result};
+use crate::sandbox;
+
+/// Version of the JSON printed by `nono why --json` and `--batch`.
+///
+/// Bumped when a field is removed or changes meaning; new fields and
+/// reasons may be added without a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A path or network query, as read by `nono why --batch`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Query {
+    /// Path to check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Operation to check on the path (default: read)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<WhyOp>,
+    /// Network host to check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Network port (default: 443)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+impl Query {
+    /// Check the query against a capability set, with every rule considered
+    ///
+    /// # Errors
+    /// Returns `NonoError::ConfigParse` if the query names neither a path nor a host
+    pub fn explain(&self, caps: &CapabilitySet) -> Result<(QueryResult, Vec<TraceStep>)> {
+        if let Some(ref path) = self.path {
+            let op = match self.op {
+                Some(WhyOp::Read) | None => FsAccess::Read,
+                Some(WhyOp::Write) => FsAccess::Write,
+                Some(WhyOp::ReadWrite) => FsAccess::ReadWrite,
+            };
+            explain_path(path, op, caps)
+        } else if let Some(ref host) = self.host {
+            Ok(explain_network(host, self.port.unwrap_or(443), caps))
+        } else {
+            Err(NonoError::ConfigParse(
+                "a query needs a path or a host".to_string(),
+            ))
+        }
+    }
+}
+
+/// A result as printed by `nono why --json` and `--batch`
+#[derive(Debug, Serialize)]
+pub struct QueryResponse<'a> {
+    /// Always `SCHEMA_VERSION`
+    pub schema_version: u32,
+    /// The query answered (absent if it could not be parsed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<&'a Query>,
+    #[serde(flatten)]
+    pub result: &'a QueryResult,
+    /// Every rule considered (`--explain` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<&'a [TraceStep]>,
+}
+
+impl<'a> QueryResponse<'a> {
+    pub fn new(query: Option<&'a Query>, result: &'a QueryResult) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            query,
+            result,
+            trace: None,
+        }
+    }
+
+    /// Include the evaluation trace
+    pub fn with_trace(mut self, trace: &'a [TraceStep]) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+}
 
 /// Result of a query operation
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -41,55 +125,190 @@ pub enum QueryResult {
         /// Explanation message
         message: String,
     },
+    /// The query could not be answered (`--batch` only)
+    #[serde(rename = "error")]
+    Error {
+        /// What was wrong with the query
+        message: String,
+    },
 }
 
-/// Reason why an operation is allowed
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum AllowReason {
-    /// Explicitly granted via --allow, --read, or --write
-    ExplicitGrant,
-    /// Within the working directory ($WORKDIR)
-    WithinWorkdir,
-    /// System path allowed for executables
-    SystemPath,
-    /// Network allowed by default
-    NetworkAllowedByDefault,
+/// Declare an enum together with `ALL`, its variants in declaration order.
+///
+/// The JSON schema lists the reasons and trace rules from `ALL`, so a
+/// variant can't be added without showing up there.
+macro_rules! enum_with_all {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $( $(#[$variant_meta:meta])* $variant:ident, )*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $( $(#[$variant_meta])* $variant, )*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),*];
+        }
+    };
 }
 
-/// Reason why an operation is denied
+enum_with_all! {
+    /// Reason why an operation is allowed
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum AllowReason {
+        /// Explicitly granted via --allow, --read, or --write
+        ExplicitGrant,
+        /// Within the working directory ($WORKDIR)
+        WithinWorkdir,
+        /// System path allowed for executables
+        SystemPath,
+        /// Network allowed by default
+        NetworkAllowedByDefault,
+        /// Loopback host inside a loopback-only network namespace (`--net loopback`)
+        LoopbackOnly,
+        /// `--net-block` was requested but the kernel can't enforce it
+        NetBlockNotEnforced,
+    }
+}
+
+enum_with_all! {
+    /// Reason why an operation is denied
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum DenyReason {
+        /// Path is in the sensitive paths list
+        SensitivePath,
+        /// Path is not in the list of allowed paths
+        NotInAllowedPaths,
+        /// Network access is blocked
+        NetworkBlocked,
+        /// Path does not exist in the isolated root (`--isolate`)
+        HiddenByIsolation,
+        /// Host is unreachable from the command's network namespace (`--net`)
+        NetworkNamespace,
+    }
+}
+
+/// One rule considered while answering a query (`nono why --explain`)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum DenyReason {
-    /// Path is in the sensitive paths list
-    SensitivePath,
-    /// Path is not in the list of allowed paths
-    NotInAllowedPaths,
-    /// Network access is blocked
-    NetworkBlocked,
+pub struct TraceStep {
+    /// The kind of rule
+    pub rule: TraceRule,
+    /// The rule itself, e.g. the granted path and its access
+    pub detail: String,
+    /// Where the rule comes from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The rule covers the queried path or host
+    pub matches: bool,
+    /// This rule decided the result
+    pub decisive: bool,
+}
+
+enum_with_all! {
+    /// Kind of rule in an evaluation trace
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum TraceRule {
+        /// The sensitive paths list
+        SensitivePath,
+        /// A grant at or below a sensitive path, which lifts its protection
+        UserOverride,
+        /// A filesystem capability
+        Capability,
+        /// A system path readable by every command
+        SystemPath,
+        /// The isolated root (`--isolate`)
+        Isolation,
+        /// The network namespace (`--net`)
+        NetworkNamespace,
+        /// `--net-block`
+        NetworkBlock,
+        /// The default when no other rule decides
+        Default,
+    }
+}
+
+/// Source of the sensitive and system path rules
+const SECURITY_LISTS: &str = "security-lists.toml";
+
+/// Trace being built, and the result of the first rule that decides
+struct Evaluation {
+    trace: Vec<TraceStep>,
+    result: Option<QueryResult>,
+}
+
+impl Evaluation {
+    fn new() -> Self {
+        Self {
+            trace: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Record a rule; `decision` is the result if the rule decides the query
+    fn consider(
+        &mut self,
+        rule: TraceRule,
+        detail: String,
+        source: Option<String>,
+        matches: bool,
+        decision: Option<QueryResult>,
+    ) {
+        let decisive = self.result.is_none() && decision.is_some();
+        if decisive {
+            self.result = decision;
+        }
+        self.trace.push(TraceStep {
+            rule,
+            detail,
+            source,
+            matches,
+            decisive,
+        });
+    }
+
+    fn finish(self) -> (QueryResult, Vec<TraceStep>) {
+        let result = self
+            .result
+            .expect("evaluation ends with a rule that always decides");
+        (result, self.trace)
+    }
 }
 
 /// Query if a path operation would be allowed
 ///
-/// Checks the path against:
-/// 1. Sensitive paths list (always denied unless explicitly overridden)
-/// 2. Granted capabilities from CLI args or profile
+/// See `explain_path` for the rules checked.
 ///
 /// # Errors
 /// Returns `NonoError::EnvVarValidation` if tilde expansion is needed but HOME is missing or invalid
+#[allow(dead_code)]
 pub fn query_path(path: &Path, op: FsAccess, caps: &CapabilitySet) -> Result<QueryResult> {
-    let path_str = path.display().to_string();
+    explain_path(path, op, caps).map(|(result, _)| result)
+}
 
-    // First check sensitive paths - these are blocked by default
-    if let Some(category) = config::check_sensitive_path(&path_str) {
-        return Ok(QueryResult::Denied {
-            reason: DenyReason::SensitivePath,
-            category: Some(category.to_string()),
-            // SECURITY: Do not call path.is_file() here - it leaks metadata about denied paths
-            // (reveals whether path exists and its type). Always use directory-level flags.
-            suggestion: suggest_flag(path, op),
-        });
-    }
+/// Query if a path operation would be allowed, with every rule considered
+///
+/// Checks the path against, in order:
+/// 1. Sensitive paths list (denied unless a grant at or below the sensitive
+///    path overrides it)
+/// 2. Granted capabilities from CLI args, profile, and working directory
+/// 3. System read paths (read only), skipping those the sandbox skips
+/// 4. With `--isolate`, whether the path exists in the isolated root at all
+///
+/// # Errors
+/// Returns `NonoError::EnvVarValidation` if tilde expansion is needed but HOME is missing or invalid
+pub fn explain_path(
+    path: &Path,
+    op: FsAccess,
+    caps: &CapabilitySet,
+) -> Result<(QueryResult, Vec<TraceStep>)> {
+    let path_str = path.display().to_string();
+    let mut eval = Evaluation::new();
 
     // Expand ~ in path for comparison
     // SECURITY: Validate HOME environment variable before use
@@ -122,63 +341,279 @@ pub fn query_path(path: &Path, op: FsAccess, caps: &CapabilitySet) -> Result<Que
     // SECURITY: Using Path::starts_with() instead of String::starts_with()
     // to prevent path traversal attacks like "/homeevil" matching "/home"
     let expanded_path = Path::new(&expanded_path_str);
-    let query_path = Path::new(&path_str);
+    // Landlock checks the file actually opened, after symlinks are followed
+    let resolved_path = resolve_existing(expanded_path);
+
+    // First check sensitive paths - these are blocked unless the user
+    // granted the sensitive path itself (or a path inside it): granting a
+    // parent such as ~ does not expose ~/.ssh
+    match config::find_sensitive_path(&path_str) {
+        Some((category, sensitive)) => {
+            let overrides: Vec<_> = caps
+                .fs
+                .iter()
+                .filter(|cap| cap.resolved.starts_with(&sensitive))
+                .collect();
+            let denial = QueryResult::Denied {
+                reason: DenyReason::SensitivePath,
+                category: Some(category.to_string()),
+                // SECURITY: Do not call path.is_file() here - it leaks metadata about denied paths
+                // (reveals whether path exists and its type). Always use directory-level flags.
+                suggestion: suggest_flag(path, op),
+            };
+            eval.consider(
+                TraceRule::SensitivePath,
+                format!("{} ({})", sensitive, category),
+                Some(SECURITY_LISTS.to_string()),
+                true,
+                overrides.is_empty().then_some(denial),
+            );
+            for cap in overrides {
+                eval.consider(
+                    TraceRule::UserOverride,
+                    grant(cap),
+                    Some(cap.source.to_string()),
+                    true,
+                    None,
+                );
+            }
+        }
+        None => eval.consider(
+            TraceRule::SensitivePath,
+            "not a sensitive path".to_string(),
+            Some(SECURITY_LISTS.to_string()),
+            false,
+            None,
+        ),
+    }
 
     // Check against granted capabilities
+    let mut visible = false;
     for cap in &caps.fs {
         // Check if the path matches or is under the capability path
         // SECURITY: Path::starts_with() compares path components, not strings
         // e.g., Path("/homeevil").starts_with("/home") == false
         //       String "/homeevil".starts_with("/home") == true (VULNERABLE!)
+        // Compare the resolved path, like Landlock: a symlink inside a
+        // granted directory that points outside it grants nothing
         let matches = if cap.is_file {
             // File capability - exact match only
-            expanded_path == cap.resolved
+            resolved_path == cap.resolved
         } else {
             // Directory capability - path is under this directory
-            // Check both resolved (canonicalized) and original paths
-            expanded_path == cap.resolved
-                || expanded_path.starts_with(&cap.resolved)
-                || query_path == cap.original
-                || query_path.starts_with(&cap.original)
+            resolved_path.starts_with(&cap.resolved)
         };
 
-        if matches && access_allows(&cap.access, op) {
-            return Ok(QueryResult::Allowed {
-                reason: AllowReason::ExplicitGrant,
-                granted_by: format!(
-                    "--{} {}",
-                    access_to_flag(&cap.access),
-                    cap.original.display()
-                ),
+        visible |= matches;
+        let decision = (matches && access_allows(&cap.access, op)).then(|| {
+            let reason = if cap.source == CapabilitySource::Workdir {
+                AllowReason::WithinWorkdir
+            } else {
+                AllowReason::ExplicitGrant
+            };
+            let granted_by = match cap.source {
+                CapabilitySource::Cli => grant(cap),
+                CapabilitySource::Workdir => {
+                    format!("working directory {}", cap.original.display())
+                }
+                _ => format!("{} from {}", grant(cap), cap.source),
+            };
+            QueryResult::Allowed { reason, granted_by }
+        });
+        eval.consider(
+            TraceRule::Capability,
+            format!("{} [{}]", grant(cap), cap.access),
+            Some(cap.source.to_string()),
+            matches,
+            decision,
+        );
+    }
+
+    // Every command may read the system paths the sandbox adds rules for
+    for rule in sandbox::system_read_rules() {
+        let system = rule.path.display();
+        let Some(skip) = rule.skipped else {
+            let matches = resolved_path.starts_with(resolve_existing(&rule.path));
+            let decision = (matches && op == FsAccess::Read).then(|| QueryResult::Allowed {
+                reason: AllowReason::SystemPath,
+                granted_by: format!("system path {}", system),
             });
-        }
+            eval.consider(
+                TraceRule::SystemPath,
+                format!("{} [read]", system),
+                Some(SECURITY_LISTS.to_string()),
+                matches,
+                decision,
+            );
+            continue;
+        };
+        eval.consider(
+            TraceRule::SystemPath,
+            format!("{} (skipped: {})", system, skip),
+            Some(SECURITY_LISTS.to_string()),
+            false,
+            None,
+        );
+    }
+
+    // With --isolate, only granted paths and system paths are mounted
+    if caps.isolate {
+        let hidden = !visible && !is_system_path(expanded_path);
+        eval.consider(
+            TraceRule::Isolation,
+            "--isolate: only granted and system paths are mounted".to_string(),
+            None,
+            hidden,
+            hidden.then(|| QueryResult::Denied {
+                reason: DenyReason::HiddenByIsolation,
+                category: None,
+                suggestion: suggest_flag(path, op),
+            }),
+        );
     }
 
     // Not allowed
-    Ok(QueryResult::Denied {
-        reason: DenyReason::NotInAllowedPaths,
-        category: None,
-        // SECURITY: Do not call path.is_file() here - it leaks metadata about denied paths
-        suggestion: suggest_flag(path, op),
-    })
+    eval.consider(
+        TraceRule::Default,
+        "paths not granted are denied".to_string(),
+        None,
+        true,
+        Some(QueryResult::Denied {
+            reason: DenyReason::NotInAllowedPaths,
+            category: None,
+            // SECURITY: Do not call path.is_file() here - it leaks metadata about denied paths
+            suggestion: suggest_flag(path, op),
+        }),
+    );
+
+    Ok(eval.finish())
 }
 
 /// Query if network access would be allowed
-pub fn query_network(_host: &str, _port: u16, caps: &CapabilitySet) -> QueryResult {
-    if caps.net_block {
-        QueryResult::Denied {
-            reason: DenyReason::NetworkBlocked,
+///
+/// See `explain_network` for the rules checked.
+#[allow(dead_code)]
+pub fn query_network(host: &str, port: u16, caps: &CapabilitySet) -> QueryResult {
+    explain_network(host, port, caps).0
+}
+
+/// Query if network access would be allowed, with every rule considered
+///
+/// A network namespace (`--net`) is checked first: it holds on any kernel,
+/// while `--net-block` also applies to loopback connections.
+pub fn explain_network(
+    host: &str,
+    _port: u16,
+    caps: &CapabilitySet,
+) -> (QueryResult, Vec<TraceStep>) {
+    let mut eval = Evaluation::new();
+
+    let reachable = match caps.net_mode {
+        NetMode::Host => true,
+        NetMode::None => false,
+        NetMode::Loopback => is_loopback_host(host),
+    };
+    eval.consider(
+        TraceRule::NetworkNamespace,
+        format!("--net {}", caps.net_mode),
+        None,
+        !reachable,
+        (!reachable).then(|| QueryResult::Denied {
+            reason: DenyReason::NetworkNamespace,
             category: None,
-            suggestion: "remove --net-block flag".to_string(),
-        }
+            suggestion: format!("remove --net {}", caps.net_mode),
+        }),
+    );
+
+    if caps.net_block_degraded {
+        eval.consider(
+            TraceRule::NetworkBlock,
+            "--net-block (not enforced by this kernel)".to_string(),
+            None,
+            true,
+            Some(QueryResult::Allowed {
+                reason: AllowReason::NetBlockNotEnforced,
+                granted_by: "--net-block not enforced (needs Landlock ABI V4+)".to_string(),
+            }),
+        );
     } else {
-        QueryResult::Allowed {
+        eval.consider(
+            TraceRule::NetworkBlock,
+            "--net-block".to_string(),
+            None,
+            caps.net_block,
+            caps.net_block.then(|| QueryResult::Denied {
+                reason: DenyReason::NetworkBlocked,
+                category: None,
+                suggestion: "remove --net-block flag".to_string(),
+            }),
+        );
+    }
+
+    if caps.net_mode == NetMode::Loopback {
+        eval.consider(
+            TraceRule::NetworkNamespace,
+            "loopback hosts inside --net loopback".to_string(),
+            None,
+            reachable,
+            reachable.then(|| QueryResult::Allowed {
+                reason: AllowReason::LoopbackOnly,
+                granted_by: "--net loopback".to_string(),
+            }),
+        );
+    }
+
+    eval.consider(
+        TraceRule::Default,
+        "network allowed by default".to_string(),
+        None,
+        true,
+        Some(QueryResult::Allowed {
             reason: AllowReason::NetworkAllowedByDefault,
             granted_by: "network allowed by default".to_string(),
+        }),
+    );
+
+    eval.finish()
+}
+
+/// Check if a host names the loopback interface (`localhost`, 127.0.0.0/8, ::1)
+fn is_loopback_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Resolve symlinks in the longest existing prefix of `path`
+///
+/// The rest of the path is appended as-is, so a file that would be created
+/// resolves to where the kernel would create it.
+fn resolve_existing(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return rest.iter().rev().fold(resolved, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
         }
     }
 }
 
+/// Check if a path is under one of the system read paths
+fn is_system_path(path: &Path) -> bool {
+    config::get_system_read_paths()
+        .iter()
+        .any(|system| path.starts_with(system))
+}
+
 /// Check if a capability's access level allows the requested operation
 fn access_allows(cap_access: &FsAccess, requested: FsAccess) -> bool {
     match (cap_access, requested) {
@@ -193,6 +628,16 @@ fn access_allows(cap_access: &FsAccess, requested: FsAccess) -> bool {
     }
 }
 
+/// The flag that grants a capability, e.g. `--read /home/user/src`
+fn grant(cap: &FsCapability) -> String {
+    let flag = if cap.noexec && cap.access == FsAccess::Read {
+        "read-noexec"
+    } else {
+        access_to_flag(&cap.access)
+    };
+    format!("--{} {}", flag, cap.original.display())
+}
+
 /// Convert access level to CLI flag name
 fn access_to_flag(access: &FsAccess) -> &'static str {
     match access {
@@ -220,6 +665,179 @@ fn suggest_flag(path: &Path, op: FsAccess) -> String {
     format!("{} {}", flag, path.display())
 }
 
+/// Answer newline-delimited JSON queries, writing one result per line
+///
+/// Each result is flushed as soon as it is ready, so a caller can send a
+/// query and wait for its answer. Blank lines are skipped, and a line that
+/// is not a valid query gets an `error` result. Without capabilities (not
+/// running inside a sandbox) every query is answered `not_sandboxed`. With
+/// `explain`, each result includes its evaluation trace.
+///
+/// # Errors
+/// Returns `NonoError::CommandExecution` if reading or writing fails
+pub fn run_batch(
+    input: impl BufRead,
+    mut output: impl Write,
+    caps: Option<&CapabilitySet>,
+    explain: bool,
+) -> Result<()> {
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let query = serde_json::from_str::<Query>(&line);
+        let mut trace = Vec::new();
+        let result = match (&query, caps) {
+            (Err(e), _) => QueryResult::Error {
+                message: format!("line {}: {}", index + 1, e),
+            },
+            (Ok(query), _) if query.path.is_none() && query.host.is_none() => QueryResult::Error {
+                message: format!("line {}: a query needs a path or a host", index + 1),
+            },
+            (Ok(_), None) => QueryResult::NotSandboxed {
+                message: "Not running inside a nono sandbox".to_string(),
+            },
+            (Ok(query), Some(caps)) => match query.explain(caps) {
+                Ok((result, steps)) => {
+                    trace = steps;
+                    result
+                }
+                Err(e) => QueryResult::Error {
+                    message: format!("line {}: {}", index + 1, e),
+                },
+            },
+        };
+        let mut response = QueryResponse::new(query.as_ref().ok(), &result);
+        if explain {
+            response = response.with_trace(&trace);
+        }
+        let json = serde_json::to_string(&response).map_err(|e| {
+            NonoError::ConfigParse(format!("Failed to serialize query result: {}", e))
+        })?;
+        writeln!(output, "{}", json)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// JSON Schema (draft 2020-12) of the `nono why --json` and `--batch` output
+///
+/// The reasons and trace rules are listed from their types' `ALL`, which
+/// [`enum_with_all`] keeps complete, so the schema can't fall behind them.
+/// It is published as `docs/schemas/why.schema.json`.
+pub fn json_schema() -> Value {
+    fn names<T: Serialize>(values: &[T]) -> Vec<Value> {
+        values
+            .iter()
+            .map(|v| serde_json::to_value(v).unwrap_or_default())
+            .collect()
+    }
+    let string = json!({ "type": "string" });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "nono why result",
+        "description": "Whether a path or network operation is allowed in a nono sandbox. `nono why --batch` prints one per line.",
+        "type": "object",
+        "required": ["schema_version", "status"],
+        "properties": {
+            "schema_version": { "const": SCHEMA_VERSION },
+            "query": { "$ref": "#/$defs/query" },
+            "status": { "enum": ["allowed", "denied", "not_sandboxed", "error"] },
+            "trace": {
+                "description": "Every rule considered, in order (`--explain` only)",
+                "type": "array",
+                "items": { "$ref": "#/$defs/trace_step" }
+            }
+        },
+        "oneOf": [
+            {
+                "properties": {
+                    "status": { "const": "allowed" },
+                    "reason": { "enum": names(AllowReason::ALL) },
+                    "granted_by": string
+                },
+                "required": ["reason", "granted_by"]
+            },
+            {
+                "properties": {
+                    "status": { "const": "denied" },
+                    "reason": { "enum": names(DenyReason::ALL) },
+                    "category": string,
+                    "suggestion": string
+                },
+                "required": ["reason", "suggestion"]
+            },
+            {
+                "properties": {
+                    "status": { "const": "not_sandboxed" },
+                    "message": string
+                },
+                "required": ["message"]
+            },
+            {
+                "properties": {
+                    "status": { "const": "error" },
+                    "message": string
+                },
+                "required": ["message"]
+            }
+        ],
+        "$defs": {
+            "query": {
+                "description": "A query, as read by `nono why --batch`: a path (with an operation) or a host (with a port)",
+                "type": "object",
+                "properties": {
+                    "path": string,
+                    "op": { "enum": names(WhyOp::value_variants()), "default": "read" },
+                    "host": string,
+                    "port": { "type": "integer", "minimum": 0, "maximum": 65535, "default": 443 }
+                },
+                "additionalProperties": false
+            },
+            "trace_step": {
+                "type": "object",
+                "required": ["rule", "detail", "matches", "decisive"],
+                "properties": {
+                    "rule": { "enum": names(TraceRule::ALL) },
+                    "detail": string,
+                    "source": string,
+                    "matches": { "type": "boolean" },
+                    "decisive": { "type": "boolean" }
+                }
+            }
+        }
+    })
+}
+
+/// Print an evaluation trace in human-readable format
+pub fn print_trace(trace: &[TraceStep]) {
+    println!("  Rules considered:");
+    for step in trace {
+        let mark = if step.decisive {
+            "decides"
+        } else if step.matches {
+            "matches"
+        } else {
+            "-"
+        };
+        let rule = serde_json::to_value(step.rule).unwrap_or_default();
+        let source = step
+            .source
+            .as_ref()
+            .map(|s| format!(" ({})", s))
+            .unwrap_or_default();
+        println!(
+            "    [{:^7}] {}: {}{}",
+            mark,
+            rule.as_str().unwrap_or_default().replace('_', " "),
+            step.detail,
+            source
+        );
+    }
+}
+
 /// Print a query result in human-readable format
 pub fn print_result(result: &QueryResult) {
     match result {
@@ -244,6 +862,10 @@ pub fn print_result(result: &QueryResult) {
             println!("NOT SANDBOXED");
             println!("  {}", message);
         }
+        QueryResult::Error { message } => {
+            println!("ERROR");
+            println!("  {}", message);
+        }
     }
 }
 
@@ -283,6 +905,284 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_query_system_path() {
+        let caps = CapabilitySet::default();
+        let system = sandbox::system_read_rules()
+            .into_iter()
+            .find(|rule| rule.skipped.is_none())
+            .expect("some system path should exist");
+        let system = system.path.join("some-file");
+
+        let result = query_path(&system, FsAccess::Read, &caps).expect("query should succeed");
+        assert!(matches!(
+            result,
+            QueryResult::Allowed {
+                reason: AllowReason::SystemPath,
+                ..
+            }
+        ));
+
+        // System paths are read-only
+        let result = query_path(&system, FsAccess::Write, &caps).expect("query should succeed");
+        assert!(matches!(
+            result,
+            QueryResult::Denied {
+                reason: DenyReason::NotInAllowedPaths,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_query_skipped_system_path() {
+        let caps = CapabilitySet::default();
+        let (_, trace) = explain_path(Path::new("/nonexistent"), FsAccess::Read, &caps).unwrap();
+        for rule in sandbox::system_read_rules() {
+            let Some(skip) = rule.skipped else {
+                continue;
+            };
+            // A skipped system path grants nothing by itself
+            let step = trace
+                .iter()
+                .find(|step| step.detail == format!("{} (skipped: {})", rule.path.display(), skip))
+                .expect("skipped system path should be traced");
+            assert!(!step.matches);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_query_follows_symlinks() {
+        let granted = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), granted.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(granted.path(), outside.path().join("alias")).unwrap();
+
+        let mut caps = CapabilitySet::default();
+        caps.add_fs(FsCapability::new_dir(granted.path().to_path_buf(), FsAccess::Read).unwrap());
+
+        // A symlink out of a granted directory grants nothing
+        let result =
+            query_path(&granted.path().join("escape/file"), FsAccess::Read, &caps).unwrap();
+        assert!(matches!(
+            result,
+            QueryResult::Denied {
+                reason: DenyReason::NotInAllowedPaths,
+                ..
+            }
+        ));
+
+        // A symlink into one reaches it
+        let result = query_path(&outside.path().join("alias/file"), FsAccess::Read, &caps).unwrap();
+        assert!(matches!(result, QueryResult::Allowed { .. }));
+    }
+
+    /// Compare `query_path` with what a real sandbox allows
+    ///
+    /// Landlock restricts only the calling thread, so the sandbox is applied
+    /// on a thread of its own and the rest of the tests are unaffected.
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore] // Needs Landlock. Run with: cargo test -- --ignored test_query_landlock_parity
+    fn test_query_landlock_parity() {
+        use std::fs::{self, File, OpenOptions};
+
+        assert!(
+            sandbox::is_supported(),
+            "Landlock is not available on this kernel"
+        );
+
+        let granted = tempfile::tempdir().unwrap();
+        let read_only = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        for dir in [&granted, &read_only, &outside] {
+            fs::write(dir.path().join("file"), "data").unwrap();
+        }
+        std::os::unix::fs::symlink(outside.path(), granted.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(read_only.path(), outside.path().join("alias")).unwrap();
+
+        let mut caps = CapabilitySet::default();
+        caps.add_fs(
+            FsCapability::new_dir(granted.path().to_path_buf(), FsAccess::ReadWrite).unwrap(),
+        );
+        caps.add_fs(FsCapability::new_dir(read_only.path().to_path_buf(), FsAccess::Read).unwrap());
+
+        let mut probes = Vec::new();
+        for dir in [granted.path(), read_only.path(), outside.path()] {
+            probes.push((dir.join("file"), FsAccess::Read));
+            probes.push((dir.join("file"), FsAccess::Write));
+            probes.push((dir.join("new"), FsAccess::Write));
+        }
+        probes.push((granted.path().join("escape/file"), FsAccess::Read));
+        probes.push((outside.path().join("alias/file"), FsAccess::Read));
+
+        // Every system path, skipped or not, that is a plain file or
+        // directory; devices and procfs answer opens in their own ways
+        for rule in sandbox::system_read_rules() {
+            let skip_kernel_fs = ["/dev", "/proc", "/sys", "/run"]
+                .iter()
+                .any(|prefix| resolve_existing(&rule.path).starts_with(prefix));
+            if fs::metadata(&rule.path).is_ok() && !skip_kernel_fs {
+                probes.push((rule.path, FsAccess::Read));
+            }
+        }
+
+        let predicted: Vec<bool> = probes
+            .iter()
+            .map(|(path, op)| {
+                matches!(
+                    query_path(path, *op, &caps).unwrap(),
+                    QueryResult::Allowed { .. }
+                )
+            })
+            .collect();
+        assert!(predicted.contains(&true) && predicted.contains(&false));
+
+        let actual = std::thread::spawn(move || {
+            sandbox::apply(&caps).expect("sandbox should apply");
+            probes
+                .into_iter()
+                .map(|(path, op)| {
+                    let outcome = match op {
+                        FsAccess::Write => OpenOptions::new().append(true).create(true).open(&path),
+                        _ if path.is_dir() => fs::read_dir(&path).map(|_| File::open("/dev/null").unwrap()),
+                        _ => File::open(&path),
+                    };
+                    let denied = matches!(&outcome, Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied);
+                    (path, op, !denied)
+                })
+                .collect::<Vec<_>>()
+        })
+        .join()
+        .unwrap();
+
+        for ((path, op, allowed), predicted) in actual.into_iter().zip(predicted) {
+            assert_eq!(
+                allowed,
+                predicted,
+                "{} [{}]: sandbox {} it, nono why predicted {}",
+                path.display(),
+                op,
+                if allowed { "allowed" } else { "denied" },
+                if predicted { "allowed" } else { "denied" },
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain_path_trace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut caps = CapabilitySet::default();
+        let mut workdir = FsCapability::new_dir(dir.path().to_path_buf(), FsAccess::Read).unwrap();
+        workdir.source = CapabilitySource::Workdir;
+        caps.add_fs(workdir);
+
+        let file = dir.path().canonicalize().unwrap().join("file");
+        let (result, trace) = explain_path(&file, FsAccess::Read, &caps).unwrap();
+        assert!(matches!(
+            result,
+            QueryResult::Allowed {
+                reason: AllowReason::WithinWorkdir,
+                ..
+            }
+        ));
+        assert_eq!(trace[0].rule, TraceRule::SensitivePath);
+        assert!(!trace[0].matches);
+        let decisive: Vec<_> = trace.iter().filter(|step| step.decisive).collect();
+        assert_eq!(decisive.len(), 1);
+        assert_eq!(decisive[0].rule, TraceRule::Capability);
+        assert_eq!(decisive[0].source.as_deref(), Some("working directory"));
+        assert_eq!(trace.last().unwrap().rule, TraceRule::Default);
+
+        // The capability matches but can't write, so the default decides
+        let (result, trace) = explain_path(&file, FsAccess::Write, &caps).unwrap();
+        assert!(matches!(result, QueryResult::Denied { .. }));
+        let capability = trace
+            .iter()
+            .find(|step| step.rule == TraceRule::Capability)
+            .unwrap();
+        assert!(capability.matches && !capability.decisive);
+        assert!(trace.last().unwrap().decisive);
+    }
+
+    #[test]
+    fn test_explain_sensitive_override() {
+        let home = match std::env::var("HOME") {
+            Ok(home) => PathBuf::from(home),
+            Err(_) => return,
+        };
+        let ssh = home.join(".ssh");
+        let mut caps = CapabilitySet::default();
+        caps.fs.push(FsCapability {
+            original: home.clone(),
+            resolved: home.clone(),
+            access: FsAccess::Read,
+            is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
+        });
+
+        // Granting a parent directory doesn't expose the sensitive path
+        let (result, trace) = explain_path(&ssh.join("id_rsa"), FsAccess::Read, &caps).unwrap();
+        assert!(matches!(
+            result,
+            QueryResult::Denied {
+                reason: DenyReason::SensitivePath,
+                ..
+            }
+        ));
+        assert!(trace[0].decisive);
+
+        // Granting the sensitive path itself does
+        caps.fs.push(FsCapability {
+            original: ssh.clone(),
+            resolved: ssh.clone(),
+            access: FsAccess::Read,
+            is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
+        });
+        let (result, trace) = explain_path(&ssh.join("id_rsa"), FsAccess::Read, &caps).unwrap();
+        assert!(matches!(
+            result,
+            QueryResult::Allowed {
+                reason: AllowReason::ExplicitGrant,
+                ..
+            }
+        ));
+        assert!(!trace[0].decisive);
+        assert_eq!(trace[1].rule, TraceRule::UserOverride);
+    }
+
+    #[test]
+    fn test_query_hidden_by_isolation() {
+        let caps = CapabilitySet {
+            isolate: true,
+            ..Default::default()
+        };
+        let result = query_path(Path::new("/srv/data/file"), FsAccess::Read, &caps)
+            .expect("query should succeed");
+        assert!(matches!(
+            result,
+            QueryResult::Denied {
+                reason: DenyReason::HiddenByIsolation,
+                ..
+            }
+        ));
+
+        // System paths are mounted, so only Landlock decides
+        let result = query_path(Path::new("/usr/bin/env"), FsAccess::Write, &caps)
+            .expect("query should succeed");
+        assert!(matches!(
+            result,
+            QueryResult::Denied {
+                reason: DenyReason::NotInAllowedPaths,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_query_network_allowed() {
         let caps = CapabilitySet::default();
@@ -315,6 +1215,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_query_network_block_not_enforced() {
+        let caps = CapabilitySet {
+            net_block: true,
+            net_block_degraded: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            query_network("api.openai.com", 443, &caps),
+            QueryResult::Allowed {
+                reason: AllowReason::NetBlockNotEnforced,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_query_network_namespace() {
+        let mut caps = CapabilitySet {
+            net_mode: NetMode::Loopback,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            query_network("127.0.0.1", 8080, &caps),
+            QueryResult::Allowed {
+                reason: AllowReason::LoopbackOnly,
+                ..
+            }
+        ));
+        assert!(matches!(
+            query_network("[::1]", 8080, &caps),
+            QueryResult::Allowed { .. }
+        ));
+        assert!(matches!(
+            query_network("api.openai.com", 443, &caps),
+            QueryResult::Denied {
+                reason: DenyReason::NetworkNamespace,
+                ..
+            }
+        ));
+
+        caps.net_mode = NetMode::None;
+        assert!(matches!(
+            query_network("localhost", 8080, &caps),
+            QueryResult::Denied {
+                reason: DenyReason::NetworkNamespace,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_access_allows() {
         // ReadWrite allows anything
@@ -368,6 +1321,8 @@ mod tests {
             resolved: PathBuf::from("/home"),
             access: FsAccess::ReadWrite,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         // /home/user should be allowed (legitimate child path)
@@ -564,4 +1519,59 @@ mod tests {
             _ => panic!("Expected denial for /etc"),
         }
     }
+
+    #[test]
+    fn test_run_batch() {
+        let caps = CapabilitySet {
+            net_block: true,
+            ..Default::default()
+        };
+        let input = concat!(
+            "{\"path\": \"~/.ssh/id_rsa\"}\n",
+            "\n",
+            "{\"host\": \"example.com\", \"port\": 80}\n",
+            "not json\n",
+            "{}\n",
+        );
+        let mut output = Vec::new();
+        run_batch(input.as_bytes(), &mut output, Some(&caps), false).unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|l| l["schema_version"] == SCHEMA_VERSION));
+        assert_eq!(lines[0]["query"]["path"], "~/.ssh/id_rsa");
+        assert_eq!(lines[0]["reason"], "sensitive_path");
+        assert_eq!(lines[1]["query"]["port"], 80);
+        assert_eq!(lines[1]["reason"], "network_blocked");
+        assert_eq!(lines[2]["status"], "error");
+        assert!(lines[2]["message"].as_str().unwrap().starts_with("line 4:"));
+        assert_eq!(lines[3]["status"], "error");
+
+        let mut output = Vec::new();
+        run_batch(
+            "{\"path\": \"/tmp\"}\n".as_bytes(),
+            &mut output,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("\"not_sandboxed\""));
+    }
+
+    #[test]
+    fn test_json_schema_published() {
+        let schema = serde_json::to_string_pretty(&json_schema()).unwrap();
+        let published = include_str!("../docs/schemas/why.schema.json");
+        assert_eq!(
+            schema.trim(),
+            published.trim(),
+            "docs/schemas/why.schema.json is stale, regenerate it with `nono why --schema`"
+        );
+    }
 }
@@ -0,0 +1,324 @@
+//! Session recording and replay (`--record` / `nono replay`)
+//!
+//! Recordings use the asciicast v2 format (<https://docs.asciinema.org/manual/asciicast/v2/>):
+//! a JSON header line followed by one `[time, code, data]` event per line.
+//! Everything the sandboxed command printed is stored as `"o"` (output)
+//! events. Diagnostics injected by nono are preceded by an `"m"` (marker)
+//! event labelled [`DIAGNOSTIC_MARKER`], so they can be told apart from the
+//! command's own output. Keyboard input is not recorded.
+
+use crate::error::{NonoError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Marker label for diagnostics injected by nono
+pub const DIAGNOSTIC_MARKER: &str = "nono diagnostic";
+
+/// asciicast v2 header (first line of a recording)
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    width: u16,
+    height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+}
+
+/// Writes an asciicast v2 recording of a sandboxed session.
+///
+/// Events are written a line at a time, so a recording is usable even if
+/// nono is killed mid-session. Safe to share between output threads.
+pub struct Recorder {
+    start: Instant,
+    inner: Mutex<RecorderInner>,
+}
+
+struct RecorderInner {
+    out: LineWriter<File>,
+    /// Trailing bytes of an incomplete UTF-8 sequence from the last chunk
+    pending: Vec<u8>,
+    /// Set after the first write error, to avoid logging one per event
+    failed: bool,
+}
+
+impl Recorder {
+    /// Create the recording file and write the header.
+    ///
+    /// Must be called BEFORE the sandbox is applied, since the file is
+    /// usually outside the paths the command may write.
+    pub fn create(path: &Path, command: &[String]) -> Result<Self> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Recordings may contain secrets the command printed
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let file = options.open(path).map_err(|e| {
+            NonoError::Recording(format!("cannot create {}: {}", path.display(), e))
+        })?;
+
+        let (width, height) = terminal_size();
+        let env = ["SHELL", "TERM"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok().map(|v| (key.to_string(), v)))
+            .collect();
+        let command = command.join(" ");
+        let header = Header {
+            version: 2,
+            width,
+            height,
+            timestamp: Some(chrono::Utc::now().timestamp()),
+            title: Some(format!("nono: {}", command)),
+            command: Some(command),
+            env,
+        };
+
+        let mut out = LineWriter::new(file);
+        let line = serde_json::to_string(&header)
+            .map_err(|e| NonoError::Recording(format!("cannot encode header: {}", e)))?;
+        writeln!(out, "{}", line)
+            .map_err(|e| NonoError::Recording(format!("cannot write {}: {}", path.display(), e)))?;
+
+        Ok(Self {
+            start: Instant::now(),
+            inner: Mutex::new(RecorderInner {
+                out,
+                pending: Vec::new(),
+                failed: false,
+            }),
+        })
+    }
+
+    /// Record text the command printed.
+    pub fn output(&self, data: &str) {
+        self.event("o", data);
+    }
+
+    /// Record raw terminal output, which may split UTF-8 sequences across chunks.
+    pub fn output_bytes(&self, data: &[u8]) {
+        let text = {
+            let mut inner = match self.inner.lock() {
+                Ok(inner) => inner,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            let mut bytes = std::mem::take(&mut inner.pending);
+            bytes.extend_from_slice(data);
+
+            let valid_up_to = match std::str::from_utf8(&bytes) {
+                Ok(_) => bytes.len(),
+                // An incomplete sequence at the end: keep it for the next chunk
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                // Invalid bytes: record them lossily rather than stalling
+                Err(_) => bytes.len(),
+            };
+            inner.pending = bytes.split_off(valid_up_to);
+            String::from_utf8_lossy(&bytes).into_owned()
+        };
+
+        if !text.is_empty() {
+            self.output(&text);
+        }
+    }
+
+    /// Record a diagnostic injected by nono, tagged with a marker.
+    pub fn diagnostic(&self, text: &str) {
+        self.event("m", DIAGNOSTIC_MARKER);
+        self.output(text);
+    }
+
+    fn event(&self, code: &str, data: &str) {
+        let time = self.start.elapsed().as_secs_f64();
+        // Microsecond precision, as written by asciinema
+        let time = (time * 1_000_000.0).round() / 1_000_000.0;
+
+        let mut inner = match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if inner.failed {
+            return;
+        }
+
+        let line = serde_json::to_string(&(time, code, data)).unwrap_or_default();
+        if let Err(e) = writeln!(inner.out, "{}", line) {
+            tracing::warn!("Failed to write session recording: {}", e);
+            inner.failed = true;
+        }
+    }
+}
+
+/// Size of the terminal on stdout, or 80x24 when not attached to one
+fn terminal_size() -> (u16, u16) {
+    #[cfg(unix)]
+    {
+        use nix::libc;
+        // SAFETY: winsize is plain data and TIOCGWINSZ only writes into it
+        let mut winsize: libc::winsize = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut winsize) };
+        if ret == 0 && winsize.ws_col > 0 && winsize.ws_row > 0 {
+            return (winsize.ws_col, winsize.ws_row);
+        }
+    }
+    (80, 24)
+}
+
+/// Play back a recording on stdout with its original timing.
+///
+/// `speed` scales the playback rate; `idle_limit` caps pauses between
+/// events (useful for long unattended runs).
+pub fn replay(path: &Path, speed: f64, idle_limit: Option<f64>) -> Result<()> {
+    if !(speed > 0.0 && speed.is_finite()) {
+        return Err(NonoError::Recording(format!(
+            "invalid speed {}: must be a positive number",
+            speed
+        )));
+    }
+
+    let file = File::open(path)
+        .map_err(|e| NonoError::Recording(format!("cannot open {}: {}", path.display(), e)))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header_line = lines
+        .next()
+        .transpose()
+        .map_err(|e| NonoError::Recording(format!("cannot read {}: {}", path.display(), e)))?
+        .ok_or_else(|| NonoError::Recording(format!("{} is empty", path.display())))?;
+    let header: Header = serde_json::from_str(&header_line)
+        .map_err(|e| NonoError::Recording(format!("invalid asciicast header: {}", e)))?;
+    if header.version != 2 {
+        return Err(NonoError::Recording(format!(
+            "unsupported asciicast version {} (expected 2)",
+            header.version
+        )));
+    }
+
+    let mut stdout = std::io::stdout();
+    let mut previous = 0.0;
+    for (index, line) in lines.enumerate() {
+        let line = line
+            .map_err(|e| NonoError::Recording(format!("cannot read {}: {}", path.display(), e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (time, code, data) = parse_event(&line).map_err(|e| {
+            // Line 1 is the header
+            NonoError::Recording(format!("invalid event on line {}: {}", index + 2, e))
+        })?;
+
+        let mut delay = (time - previous).max(0.0);
+        if let Some(limit) = idle_limit {
+            delay = delay.min(limit.max(0.0));
+        }
+        previous = time;
+        std::thread::sleep(Duration::from_secs_f64(delay / speed));
+
+        if code == "o" {
+            stdout
+                .write_all(data.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(NonoError::CommandExecution)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse one `[time, code, data]` event line
+fn parse_event(line: &str) -> std::result::Result<(f64, String, String), serde_json::Error> {
+    serde_json::from_str(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_events(path: &Path) -> Vec<(f64, String, String)> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|l| parse_event(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_recording_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+
+        let recorder = Recorder::create(&path, &["echo".to_string(), "hi".to_string()]).unwrap();
+        recorder.output("hi\r\n");
+        recorder.diagnostic("[nono] Command exited with code 1.\r\n");
+        drop(recorder);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let header: Header = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(header.version, 2);
+        assert_eq!(header.command.as_deref(), Some("echo hi"));
+
+        let events = read_events(&path);
+        let codes: Vec<&str> = events.iter().map(|(_, c, _)| c.as_str()).collect();
+        assert_eq!(codes, vec!["o", "m", "o"]);
+        assert_eq!(events[0].2, "hi\r\n");
+        assert_eq!(events[1].2, DIAGNOSTIC_MARKER);
+        assert!(events.windows(2).all(|w| w[0].0 <= w[1].0));
+    }
+
+    #[test]
+    fn test_output_bytes_keeps_utf8_sequences_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+
+        let recorder = Recorder::create(&path, &["app".to_string()]).unwrap();
+        let text = "caf\u{e9}!".as_bytes();
+        // Split in the middle of the two-byte "é"
+        recorder.output_bytes(&text[..4]);
+        recorder.output_bytes(&text[4..]);
+        drop(recorder);
+
+        let events = read_events(&path);
+        let output: String = events.into_iter().map(|(_, _, d)| d).collect();
+        assert_eq!(output, "caf\u{e9}!");
+    }
+
+    #[test]
+    fn test_replay_rejects_invalid_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.cast");
+
+        std::fs::write(&path, "{\"version\": 1, \"width\": 80, \"height\": 24}\n").unwrap();
+        assert!(matches!(
+            replay(&path, 1.0, None),
+            Err(NonoError::Recording(_))
+        ));
+
+        std::fs::write(
+            &path,
+            "{\"version\": 2, \"width\": 80, \"height\": 24}\nnot json\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            replay(&path, 1.0, None),
+            Err(NonoError::Recording(_))
+        ));
+
+        assert!(matches!(
+            replay(&path, 0.0, None),
+            Err(NonoError::Recording(_))
+        ));
+    }
+}
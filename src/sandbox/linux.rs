@@ -1,16 +1,15 @@
-use crate::capability::{CapabilitySet, FsAccess};
-use crate::config;
+use crate::capability::{CapabilitySet, FsAccess, IpcScope};
 use crate::error::{NonoError, Result};
+use crate::sandbox::{Feature, FeatureStatus, SandboxCapabilities, SystemPathSkip};
 use landlock::{
-    Access, AccessFs, AccessNet, BitFlags, PathBeneath, PathFd, Ruleset, RulesetAttr,
-    RulesetCreatedAttr, ABI,
+    Access, AccessFs, AccessNet, BitFlags, CompatLevel, Compatible, PathBeneath, PathFd, Ruleset,
+    RulesetAttr, RulesetCreated, RulesetCreatedAttr, Scope, ABI,
 };
-use std::fs;
-use std::path::Path;
+use std::os::fd::OwnedFd;
 use tracing::{debug, info, warn};
 
 /// The target ABI version we support (highest we know about)
-const TARGET_ABI: ABI = ABI::V5;
+const TARGET_ABI: ABI = ABI::V6;
 
 /// Check if Landlock is supported on this system
 pub fn is_supported() -> bool {
@@ -21,6 +20,110 @@ pub fn is_supported() -> bool {
         .is_ok()
 }
 
+/// nono's features with the Landlock ABI they need, and their status on
+/// older kernels
+const FEATURES: [(&str, u32, FeatureStatus, &str); 6] = [
+    (
+        "filesystem",
+        1,
+        FeatureStatus::Unavailable,
+        "read, write and execute control",
+    ),
+    (
+        "refer",
+        2,
+        FeatureStatus::Degraded,
+        "renames and links across directories (always denied without it)",
+    ),
+    (
+        "truncate",
+        3,
+        FeatureStatus::Unavailable,
+        "truncation control",
+    ),
+    (
+        "net",
+        4,
+        FeatureStatus::Unavailable,
+        "TCP bind and connect control (--net-block)",
+    ),
+    (
+        "ioctl",
+        5,
+        FeatureStatus::Unavailable,
+        "device ioctl control ([devices])",
+    ),
+    (
+        "scoping",
+        6,
+        FeatureStatus::Unavailable,
+        "abstract Unix socket and signal scoping ([ipc])",
+    ),
+];
+
+/// Landlock ABI version of the running kernel, 0 without Landlock
+pub fn kernel_abi() -> u32 {
+    use nix::libc;
+
+    /// `LANDLOCK_CREATE_RULESET_VERSION`
+    const CREATE_RULESET_VERSION: libc::c_uint = 1;
+
+    let version = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<libc::c_void>(),
+            0usize,
+            CREATE_RULESET_VERSION,
+        )
+    };
+    u32::try_from(version).unwrap_or(0)
+}
+
+/// Report what Landlock enforces on the running kernel
+pub fn capabilities() -> SandboxCapabilities {
+    let abi = kernel_abi();
+    SandboxCapabilities {
+        mechanism: "landlock",
+        available: abi > 0,
+        abi: Some(abi),
+        target_abi: Some(TARGET_ABI as u32),
+        features: FEATURES
+            .iter()
+            .map(|&(name, min_abi, missing, description)| Feature {
+                name,
+                status: if abi >= min_abi {
+                    FeatureStatus::Enforced
+                } else {
+                    missing
+                },
+                min_abi: Some(min_abi),
+                description,
+            })
+            .collect(),
+    }
+}
+
+/// Check if the kernel enforces Landlock network rules (ABI V4+)
+///
+/// `Ruleset::default()` silently drops access rights the kernel doesn't know
+/// about, so probe with a hard requirement instead of checking `from_all()`.
+pub fn supports_net_block() -> bool {
+    Ruleset::default()
+        .set_compatibility(CompatLevel::HardRequirement)
+        .handle_access(AccessNet::from_all(ABI::V4))
+        .and_then(|r| r.create())
+        .is_ok()
+}
+
+/// Check if the kernel supports scoping `scope` to the sandbox (ABI V6+)
+pub fn supports_scope(scope: IpcScope) -> bool {
+    Ruleset::default()
+        .set_compatibility(CompatLevel::HardRequirement)
+        .scope(scopes_to_landlock(&[scope]))
+        .and_then(|r| r.create())
+        .is_ok()
+}
+
 /// Get information about Landlock support
 pub fn support_info() -> String {
     // Try to create a ruleset and check the status
@@ -28,7 +131,24 @@ pub fn support_info() -> String {
         .handle_access(AccessFs::from_all(TARGET_ABI))
         .and_then(|r| r.create())
     {
-        Ok(_) => format!("Landlock available (targeting ABI v{:?})", TARGET_ABI),
+        Ok(_) => {
+            let scopes: Vec<String> = IpcScope::ALL
+                .into_iter()
+                .filter(|s| supports_scope(*s))
+                .map(|s| s.to_string())
+                .collect();
+            let scoping = if scopes.is_empty() {
+                "unavailable (requires ABI V6+)".to_string()
+            } else {
+                scopes.join(", ")
+            };
+            format!(
+                "Landlock available (kernel ABI v{}, targeting ABI v{}; IPC scoping: {})",
+                kernel_abi(),
+                TARGET_ABI as u32,
+                scoping
+            )
+        }
         Err(_) => {
             "Landlock not available. Requires Linux kernel 5.13+ with Landlock enabled.".to_string()
         }
@@ -39,9 +159,14 @@ pub fn support_info() -> String {
 /// RemoveFile, RemoveDir, Truncate, and Refer are included to support atomic writes
 /// (write to .tmp → rename to target), which is the standard pattern used by
 /// most applications for safe config updates.
+///
+/// IoctlDev is handled (ABI V5+) but never part of these: device ioctls are
+/// only granted on `caps.ioctl_devices`, see `create_ruleset`. Neither is
+/// Execute, which `create_ruleset` adds to read access unless
+/// `[commands] allow_only` lists the only executables.
 fn access_to_landlock(access: FsAccess, _abi: ABI) -> BitFlags<AccessFs> {
     match access {
-        FsAccess::Read => AccessFs::ReadFile | AccessFs::ReadDir | AccessFs::Execute,
+        FsAccess::Read => AccessFs::ReadFile | AccessFs::ReadDir,
         FsAccess::Write => {
             // Write access includes all operations needed for normal file manipulation:
             // - WriteFile: modify file contents
@@ -74,8 +199,79 @@ fn access_to_landlock(access: FsAccess, _abi: ABI) -> BitFlags<AccessFs> {
     }
 }
 
+/// Convert IPC scopes to Landlock scope flags
+fn scopes_to_landlock(scopes: &[IpcScope]) -> BitFlags<Scope> {
+    scopes
+        .iter()
+        .map(|scope| match scope {
+            IpcScope::AbstractUnixSocket => Scope::AbstractUnixSocket,
+            IpcScope::Signal => Scope::Signal,
+        })
+        .collect()
+}
+
 /// Apply Landlock sandbox with the given capabilities
 pub fn apply(caps: &CapabilitySet) -> Result<()> {
+    let ruleset = create_ruleset(caps, scopes_to_landlock(&caps.scopes))?;
+
+    // Apply the ruleset - THIS IS IRREVERSIBLE
+    let status = ruleset
+        .restrict_self()
+        .map_err(|e| NonoError::SandboxInit(format!("Failed to restrict self: {}", e)))?;
+
+    match status.ruleset {
+        landlock::RulesetStatus::FullyEnforced => {
+            info!("Landlock sandbox fully enforced");
+        }
+        landlock::RulesetStatus::PartiallyEnforced => {
+            // This is normal - the kernel supports a subset of features we requested.
+            // The sandbox is still active and enforcing restrictions.
+            debug!("Landlock sandbox enforced in best-effort mode");
+        }
+        landlock::RulesetStatus::NotEnforced => {
+            return Err(NonoError::SandboxInit(
+                "Landlock sandbox was not enforced".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the Landlock ruleset for `caps` without enforcing it, for a process
+/// that restricts itself later with `landlock_restrict_self()` (`--isolate`).
+///
+/// nono and the command restrict themselves separately, into sibling
+/// domains, so signal scoping would keep nono from signalling the command.
+/// It is left out here and stacked by the command alone, see
+/// [`prepare_signal_scope`].
+pub fn prepare_ruleset(caps: &CapabilitySet) -> Result<OwnedFd> {
+    let mut scopes = scopes_to_landlock(&caps.scopes);
+    scopes.remove(Scope::Signal);
+    let fd: Option<OwnedFd> = create_ruleset(caps, scopes)?.into();
+    fd.ok_or_else(|| NonoError::SandboxInit("Landlock sandbox was not enforced".to_string()))
+}
+
+/// Build a ruleset that only scopes signals, if `caps` asks for it
+pub fn prepare_signal_scope(caps: &CapabilitySet) -> Result<Option<OwnedFd>> {
+    if !caps.scopes.contains(&IpcScope::Signal) || !supports_scope(IpcScope::Signal) {
+        return Ok(None);
+    }
+    let ruleset = Ruleset::default()
+        .scope(Scope::Signal)
+        .and_then(|r| r.create())
+        .map_err(|e| NonoError::SandboxInit(format!("Failed to create ruleset: {}", e)))?;
+    Ok(ruleset.into())
+}
+
+/// Landlock access rights granted for read access, as raw flags (without
+/// `Execute`, which depends on the allowlist)
+pub fn read_access_bits() -> u64 {
+    access_to_landlock(FsAccess::Read, TARGET_ABI).bits()
+}
+
+/// Create the Landlock ruleset with system paths and the capabilities' rules
+fn create_ruleset(caps: &CapabilitySet, scopes: BitFlags<Scope>) -> Result<RulesetCreated> {
     info!("Using Landlock ABI {:?}", TARGET_ABI);
 
     // Determine which access rights to handle based on ABI
@@ -92,11 +288,17 @@ pub fn apply(caps: &CapabilitySet) -> Result<()> {
     // Add network access handling if blocking network (ABI V4+ required)
     let ruleset_builder = if caps.net_block {
         let handled_net = AccessNet::from_all(TARGET_ABI);
-        if !handled_net.is_empty() {
+        if supports_net_block() {
             debug!("Handling network access (blocking): {:?}", handled_net);
             ruleset_builder.handle_access(handled_net).map_err(|e| {
                 NonoError::SandboxInit(format!("Failed to handle net access: {}", e))
             })?
+        } else if caps.net_block_required {
+            return Err(NonoError::SandboxInit(
+                "Network blocking is required but the kernel doesn't support it \
+                 (Landlock ABI V4+, Linux 6.7+)"
+                    .to_string(),
+            ));
         } else {
             warn!("Network blocking requested but kernel ABI doesn't support it (requires V4+)");
             ruleset_builder
@@ -105,30 +307,49 @@ pub fn apply(caps: &CapabilitySet) -> Result<()> {
         ruleset_builder
     };
 
+    // Scope IPC to the sandbox (ABI V6+, dropped on older kernels)
+    let ruleset_builder = if !scopes.is_empty() {
+        debug!("Scoping IPC: {:?}", scopes);
+        ruleset_builder
+            .scope(scopes)
+            .map_err(|e| NonoError::SandboxInit(format!("Failed to scope IPC: {}", e)))?
+    } else {
+        ruleset_builder
+    };
+
     let mut ruleset = ruleset_builder
         .create()
         .map_err(|e| NonoError::SandboxInit(format!("Failed to create ruleset: {}", e)))?;
 
+    // Whatever can be read can be executed, unless the path is noexec or
+    // [commands] allow_only lists the only executables
+    let execute = if caps.exec_allowlist.is_some() {
+        BitFlags::EMPTY
+    } else {
+        BitFlags::from(AccessFs::Execute)
+    };
+
     // Add read+execute access to system paths needed for executables to run
     // These paths are loaded from the embedded security-lists.toml
-    let read_access = access_to_landlock(FsAccess::Read, TARGET_ABI);
-    let system_paths = config::get_system_read_paths();
-    for path_str in &system_paths {
-        let path = Path::new(path_str);
-        if !path.exists() {
-            debug!("Skipping system path {} (does not exist)", path_str);
-            continue;
-        }
+    let read_access = access_to_landlock(FsAccess::Read, TARGET_ABI) | execute;
+    for rule in super::system_read_rules() {
+        let path = rule.path.as_path();
+        let path_str = path.display();
 
         // Some distro device aliases (notably /dev/stdin|stdout|stderr) are symlinks
         // to ephemeral procfs FDs (e.g. /proc/self/fd/1), which can fail Landlock
         // rule insertion with EBADFD on some kernels. Skip symlink aliases and rely
         // on stable paths like /dev/fd and /dev/pts already in the system path list.
-        if let Ok(meta) = fs::symlink_metadata(path) {
-            if meta.file_type().is_symlink() {
+        match rule.skipped {
+            Some(SystemPathSkip::Missing) => {
+                debug!("Skipping system path {} (does not exist)", path_str);
+                continue;
+            }
+            Some(SystemPathSkip::SymlinkAlias) => {
                 warn!("Skipping system path {} (symlink alias)", path_str);
                 continue;
             }
+            None => {}
         }
 
         let path_fd = match PathFd::new(path) {
@@ -154,7 +375,10 @@ pub fn apply(caps: &CapabilitySet) -> Result<()> {
     // These MUST succeed - user explicitly requested these capabilities
     // Failing silently would violate the principle of least surprise and fail-secure design
     for cap in &caps.fs {
-        let access = access_to_landlock(cap.access, TARGET_ABI);
+        let mut access = access_to_landlock(cap.access, TARGET_ABI);
+        if cap.executable() {
+            access |= execute;
+        }
 
         debug!(
             "Adding rule: {} with access {:?}",
@@ -174,28 +398,44 @@ pub fn apply(caps: &CapabilitySet) -> Result<()> {
             })?;
     }
 
-    // Apply the ruleset - THIS IS IRREVERSIBLE
-    let status = ruleset
-        .restrict_self()
-        .map_err(|e| NonoError::SandboxInit(format!("Failed to restrict self: {}", e)))?;
+    // In allowlist mode, only the listed executables (and their interpreters)
+    // may run. Executing a file also opens it for reading.
+    for program in caps.exec_allowlist.iter().flatten() {
+        debug!("Adding execute rule: {}", program.display());
 
-    match status.ruleset {
-        landlock::RulesetStatus::FullyEnforced => {
-            info!("Landlock sandbox fully enforced");
-        }
-        landlock::RulesetStatus::PartiallyEnforced => {
-            // This is normal - the kernel supports a subset of features we requested.
-            // The sandbox is still active and enforcing restrictions.
-            debug!("Landlock sandbox enforced in best-effort mode");
-        }
-        landlock::RulesetStatus::NotEnforced => {
-            return Err(NonoError::SandboxInit(
-                "Landlock sandbox was not enforced".to_string(),
-            ));
-        }
+        let path_fd = PathFd::new(program)?;
+        ruleset = ruleset
+            .add_rule(PathBeneath::new(
+                path_fd,
+                AccessFs::Execute | AccessFs::ReadFile,
+            ))
+            .map_err(|e| {
+                NonoError::SandboxInit(format!(
+                    "Cannot add Landlock execute rule for {}: {}",
+                    program.display(),
+                    e
+                ))
+            })?;
     }
 
-    Ok(())
+    // Allow ioctls only on the listed devices; any other device file the
+    // command can open is limited to plain reads and writes
+    for dev in &caps.ioctl_devices {
+        debug!("Adding ioctl rule: {}", dev.display());
+
+        let path_fd = PathFd::new(dev)?;
+        ruleset = ruleset
+            .add_rule(PathBeneath::new(path_fd, AccessFs::IoctlDev))
+            .map_err(|e| {
+                NonoError::SandboxInit(format!(
+                    "Cannot add Landlock ioctl rule for {}: {}",
+                    dev.display(),
+                    e
+                ))
+            })?;
+    }
+
+    Ok(ruleset)
 }
 
 #[cfg(test)]
@@ -215,6 +455,23 @@ mod tests {
         assert!(!info.is_empty());
     }
 
+    #[test]
+    fn test_capabilities() {
+        let report = capabilities();
+        let abi = report.abi.unwrap();
+        assert_eq!(report.available, abi > 0);
+        assert_eq!(report.features.len(), FEATURES.len());
+        for feature in &report.features {
+            let enforced = feature.status == FeatureStatus::Enforced;
+            assert_eq!(
+                enforced,
+                abi >= feature.min_abi.unwrap(),
+                "{}",
+                feature.name
+            );
+        }
+    }
+
     #[test]
     fn test_access_conversion() {
         let abi = ABI::V3;
@@ -222,6 +479,8 @@ mod tests {
         let read = access_to_landlock(FsAccess::Read, abi);
         assert!(read.contains(AccessFs::ReadFile));
         assert!(!read.contains(AccessFs::WriteFile));
+        // Execute is granted separately, see create_ruleset
+        assert!(!read.contains(AccessFs::Execute));
 
         let write = access_to_landlock(FsAccess::Write, abi);
         assert!(write.contains(AccessFs::WriteFile));
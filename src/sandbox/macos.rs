@@ -1,6 +1,7 @@
-use crate::capability::{CapabilitySet, FsAccess};
+use crate::capability::{CapabilitySet, CapabilitySource, FsAccess};
 use crate::config;
 use crate::error::{NonoError, Result};
+use crate::sandbox::{Feature, FeatureStatus, SandboxCapabilities};
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::ptr;
@@ -33,6 +34,50 @@ pub fn support_info() -> String {
     "macOS Seatbelt sandbox available".to_string()
 }
 
+/// Report what Seatbelt enforces
+pub fn capabilities() -> SandboxCapabilities {
+    let feature = |name, status, description| Feature {
+        name,
+        status,
+        min_abi: None,
+        description,
+    };
+    SandboxCapabilities {
+        mechanism: "seatbelt",
+        available: is_supported(),
+        abi: None,
+        target_abi: None,
+        features: vec![
+            feature(
+                "filesystem",
+                FeatureStatus::Enforced,
+                "read, write and execute control",
+            ),
+            feature(
+                "refer",
+                FeatureStatus::Enforced,
+                "renames and links across directories",
+            ),
+            feature("truncate", FeatureStatus::Enforced, "truncation control"),
+            feature(
+                "net",
+                FeatureStatus::Enforced,
+                "network control (--net-block)",
+            ),
+            feature(
+                "ioctl",
+                FeatureStatus::Unavailable,
+                "device ioctl control ([devices])",
+            ),
+            feature(
+                "scoping",
+                FeatureStatus::Unavailable,
+                "abstract Unix socket and signal scoping ([ipc])",
+            ),
+        ],
+    }
+}
+
 /// Get list of sensitive paths that should be denied read access
 /// Now loaded from the embedded security-lists.toml via the config module
 fn get_sensitive_paths() -> Vec<String> {
@@ -106,7 +151,50 @@ fn generate_profile(caps: &CapabilitySet) -> String {
     // profile.push_str("(debug deny)\n");
 
     // Allow specific process operations needed for execution
-    profile.push_str("(allow process-exec*)\n"); // Execute programs
+    match &caps.exec_allowlist {
+        // Execute only the programs in [commands] allow_only
+        Some(allowlist) => {
+            for program in allowlist {
+                if let Some(path) = program.to_str() {
+                    let escaped = path.replace('\\', "\\\\").replace('"', "\\\"");
+                    profile.push_str(&format!(
+                        "(allow process-exec* (literal \"{}\"))\n",
+                        escaped
+                    ));
+                }
+            }
+        }
+        None => {
+            profile.push_str("(allow process-exec*)\n"); // Execute programs
+
+            // Deny executing under noexec paths. Later rules take precedence,
+            // so executable grants are repeated after the denials: as with
+            // Landlock, a path is executable if any grant covering it is.
+            if caps.fs.iter().any(|cap| cap.noexec) {
+                for noexec in [true, false] {
+                    let grants = caps
+                        .fs
+                        .iter()
+                        .filter(|cap| cap.access != FsAccess::Write && cap.noexec == noexec);
+                    for cap in grants {
+                        let escaped = cap
+                            .resolved
+                            .display()
+                            .to_string()
+                            .replace('\\', "\\\\")
+                            .replace('"', "\\\"");
+                        let filter = if cap.is_file { "literal" } else { "subpath" };
+                        profile.push_str(&format!(
+                            "({} process-exec* ({} \"{}\"))\n",
+                            if noexec { "deny" } else { "allow" },
+                            filter,
+                            escaped
+                        ));
+                    }
+                }
+            }
+        }
+    }
     profile.push_str("(allow process-fork)\n"); // Fork child processes
 
     // Process info: allow self-inspection (needed for dyld, code signing, etc.)
@@ -301,6 +389,19 @@ fn generate_profile(caps: &CapabilitySet) -> String {
         }
     }
 
+    // 7. Keep the capability state file read-only, although it sits in the
+    // writable system temp directory
+    for cap in &caps.fs {
+        if cap.source == CapabilitySource::StateFile {
+            let path = cap.resolved.display().to_string();
+            let escaped_path = path.replace('\\', "\\\\").replace('"', "\\\"");
+            profile.push_str(&format!(
+                "(deny file-write* (literal \"{}\"))\n",
+                escaped_path
+            ));
+        }
+    }
+
     // Network rules
     // Note: macOS Seatbelt supports some filtering (tcp/udp, local/remote, ports)
     // but not per-host filtering. For that, a proxy-based approach is needed.
@@ -374,6 +475,47 @@ mod tests {
         assert!(profile.contains("(allow network-outbound)"));
     }
 
+    #[test]
+    fn test_generate_profile_exec_allowlist() {
+        let mut caps = CapabilitySet::default();
+        assert!(generate_profile(&caps).contains("(allow process-exec*)\n"));
+
+        caps.exec_allowlist = Some(vec![PathBuf::from("/usr/bin/git")]);
+        let profile = generate_profile(&caps);
+        assert!(profile.contains("(allow process-exec* (literal \"/usr/bin/git\"))"));
+        assert!(!profile.contains("(allow process-exec*)\n"));
+    }
+
+    #[test]
+    fn test_generate_profile_noexec() {
+        let mut caps = CapabilitySet::default();
+        caps.fs.push(FsCapability {
+            original: PathBuf::from("/work"),
+            resolved: PathBuf::from("/work"),
+            access: FsAccess::ReadWrite,
+            is_file: false,
+            noexec: true,
+            source: CapabilitySource::Cli,
+        });
+        caps.fs.push(FsCapability {
+            original: PathBuf::from("/work/bin"),
+            resolved: PathBuf::from("/work/bin"),
+            access: FsAccess::Read,
+            is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
+        });
+
+        let profile = generate_profile(&caps);
+        let deny = profile
+            .find("(deny process-exec* (subpath \"/work\"))")
+            .unwrap();
+        let allow = profile
+            .find("(allow process-exec* (subpath \"/work/bin\"))")
+            .unwrap();
+        assert!(deny < allow);
+    }
+
     #[test]
     fn test_generate_profile_with_dir() {
         let mut caps = CapabilitySet::default();
@@ -382,6 +524,8 @@ mod tests {
             resolved: PathBuf::from("/test"),
             access: FsAccess::ReadWrite,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -399,6 +543,8 @@ mod tests {
             resolved: PathBuf::from("/test.txt"),
             access: FsAccess::Write,
             is_file: true,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -407,6 +553,38 @@ mod tests {
         assert!(profile.contains("literal \"/test.txt\""));
     }
 
+    #[test]
+    fn test_generate_profile_state_file_read_only() {
+        let mut caps = CapabilitySet::default();
+        caps.fs.push(FsCapability {
+            original: PathBuf::from("/tmp"),
+            resolved: PathBuf::from("/private/tmp"),
+            access: FsAccess::ReadWrite,
+            is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
+        });
+        caps.fs.push(FsCapability {
+            original: PathBuf::from("/tmp/.nono-1.json"),
+            resolved: PathBuf::from("/private/tmp/.nono-1.json"),
+            access: FsAccess::Read,
+            is_file: true,
+            noexec: false,
+            source: CapabilitySource::StateFile,
+        });
+
+        let profile = generate_profile(&caps);
+
+        // The deny comes after the write allow for the directory, so it wins
+        let allow = profile
+            .find("(allow file-write* (subpath \"/private/tmp\"))")
+            .unwrap();
+        let deny = profile
+            .find("(deny file-write* (literal \"/private/tmp/.nono-1.json\"))")
+            .unwrap();
+        assert!(allow < deny);
+    }
+
     #[test]
     fn test_generate_profile_network_allowed() {
         let caps = CapabilitySet {
@@ -535,6 +713,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/project"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -562,6 +742,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.claude"),
             access: FsAccess::ReadWrite,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let parents = collect_parent_dirs(&caps);
@@ -583,6 +765,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.claude"),
             access: FsAccess::ReadWrite,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -656,6 +840,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.sshfoo"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -678,6 +864,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.ssh"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -705,6 +893,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.ssh/keys"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -728,6 +918,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/projects"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -756,6 +948,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.awsbackup"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
         // Try to bypass ~/.gnupg with ~/.gnupg2
         caps.fs.push(FsCapability {
@@ -763,6 +957,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.gnupg2"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
@@ -790,6 +986,8 @@ mod tests {
             resolved: PathBuf::from("/Users/test/.ssh/"),
             access: FsAccess::Read,
             is_file: false,
+            noexec: false,
+            source: CapabilitySource::Cli,
         });
 
         let profile = generate_profile(&caps);
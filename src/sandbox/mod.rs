@@ -1,5 +1,7 @@
 use crate::capability::CapabilitySet;
 use crate::error::Result;
+use serde::Serialize;
+use std::path::PathBuf;
 
 #[cfg(target_os = "linux")]
 mod linux;
@@ -31,6 +33,77 @@ pub fn apply(caps: &CapabilitySet) -> Result<()> {
     }
 }
 
+/// Build the Landlock ruleset for the given capabilities without enforcing it.
+///
+/// Used by `--isolate`, where the command restricts itself only after
+/// building its root, with `landlock_restrict_self()` on the returned ruleset.
+#[cfg(target_os = "linux")]
+pub fn prepare_ruleset(caps: &CapabilitySet) -> Result<std::os::fd::OwnedFd> {
+    linux::prepare_ruleset(caps)
+}
+
+/// Build a ruleset that only scopes signals, for the command to stack on
+/// top of [`prepare_ruleset`]'s
+#[cfg(target_os = "linux")]
+pub fn prepare_signal_scope(caps: &CapabilitySet) -> Result<Option<std::os::fd::OwnedFd>> {
+    linux::prepare_signal_scope(caps)
+}
+
+/// Landlock access rights that make up read access, as raw flags
+#[cfg(target_os = "linux")]
+pub fn read_access_bits() -> u64 {
+    linux::read_access_bits()
+}
+
+/// Why the sandbox adds no rule for a system read path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemPathSkip {
+    /// The path does not exist on this system
+    Missing,
+    /// The path is a symlink; the rule would not cover its target
+    SymlinkAlias,
+}
+
+impl std::fmt::Display for SystemPathSkip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemPathSkip::Missing => write!(f, "does not exist"),
+            SystemPathSkip::SymlinkAlias => write!(f, "symlink alias"),
+        }
+    }
+}
+
+/// A system read path and whether the sandbox adds a rule for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemPathRule {
+    pub path: PathBuf,
+    pub skipped: Option<SystemPathSkip>,
+}
+
+/// The system read paths from security-lists.toml, as the sandbox applies them
+///
+/// Landlock cannot add rules for missing paths, and nono skips symlink
+/// aliases, so those grant nothing. `nono why` uses the same list so its
+/// answers match what is enforced.
+pub fn system_read_rules() -> Vec<SystemPathRule> {
+    crate::config::get_system_read_paths()
+        .into_iter()
+        .map(|path| {
+            let path = PathBuf::from(path);
+            let skipped = if cfg!(target_os = "linux") {
+                match std::fs::symlink_metadata(&path) {
+                    Ok(meta) if meta.file_type().is_symlink() => Some(SystemPathSkip::SymlinkAlias),
+                    Ok(_) => None,
+                    Err(_) => Some(SystemPathSkip::Missing),
+                }
+            } else {
+                None
+            };
+            SystemPathRule { path, skipped }
+        })
+        .collect()
+}
+
 /// Check if sandboxing is supported on this platform
 pub fn is_supported() -> bool {
     #[cfg(target_os = "linux")]
@@ -49,6 +122,104 @@ pub fn is_supported() -> bool {
     }
 }
 
+/// Check if the platform can enforce `--net-block`
+///
+/// Seatbelt always can; Landlock needs ABI V4+ (Linux 6.7+).
+pub fn supports_net_block() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        linux::supports_net_block()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        true
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        false
+    }
+}
+
+/// Check if the platform can scope `scope` to the sandbox
+///
+/// Landlock needs ABI V6+ (Linux 6.12+). Seatbelt has no equivalent.
+pub fn supports_scope(scope: crate::capability::IpcScope) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        linux::supports_scope(scope)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = scope;
+        false
+    }
+}
+
+/// How well a sandbox feature works on this system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureStatus {
+    /// Enforced as nono intends
+    Enforced,
+    /// Secure, but with reduced functionality
+    Degraded,
+    /// Not enforced: the operation is not restricted
+    Unavailable,
+}
+
+/// A sandbox feature and its status
+#[derive(Debug, Clone, Serialize)]
+pub struct Feature {
+    pub name: &'static str,
+    pub status: FeatureStatus,
+    /// Landlock ABI the feature needs (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_abi: Option<u32>,
+    pub description: &'static str,
+}
+
+/// What the sandbox can enforce on this system
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxCapabilities {
+    /// Sandbox mechanism: "landlock" or "seatbelt"
+    pub mechanism: &'static str,
+    pub available: bool,
+    /// Landlock ABI of the running kernel (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abi: Option<u32>,
+    /// Highest Landlock ABI nono uses (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_abi: Option<u32>,
+    pub features: Vec<Feature>,
+}
+
+/// Report what the sandbox can enforce on this system
+pub fn capabilities() -> SandboxCapabilities {
+    #[cfg(target_os = "linux")]
+    {
+        linux::capabilities()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::capabilities()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        SandboxCapabilities {
+            mechanism: "none",
+            available: false,
+            abi: None,
+            target_abi: None,
+            features: Vec::new(),
+        }
+    }
+}
+
 /// Get information about sandbox support on this platform
 pub fn support_info() -> String {
     #[cfg(target_os = "linux")]
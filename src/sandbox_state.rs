@@ -13,8 +13,12 @@ use tracing::debug;
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 
-use crate::capability::{CapabilitySet, FsAccess, FsCapability};
+use crate::capability::{
+    CapabilitySet, CapabilitySource, FsAccess, FsCapability, IpcScope, NetMode,
+};
+use crate::config::CommandRule;
 use crate::error::{NonoError, Result};
+use crate::limits::ResourceLimits;
 
 /// Sandbox state stored for `nono why --self`
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,10 +27,31 @@ pub struct SandboxState {
     pub fs: Vec<FsCapState>,
     /// Whether network is blocked
     pub net_blocked: bool,
+    /// Whether network blocking was requested but not enforced by the kernel
+    #[serde(default)]
+    pub net_block_degraded: bool,
+    /// Network namespace of the command
+    #[serde(default)]
+    pub net_mode: NetMode,
+    /// IPC scoped to the sandbox, as enforced by the kernel
+    #[serde(default)]
+    pub scopes: Vec<IpcScope>,
     /// Commands explicitly allowed
     pub allowed_commands: Vec<String>,
     /// Commands explicitly blocked
     pub blocked_commands: Vec<String>,
+    /// Additional command rules
+    #[serde(default)]
+    pub command_rules: Vec<CommandRule>,
+    /// The only executables the command may run
+    #[serde(default)]
+    pub exec_allowlist: Option<Vec<PathBuf>>,
+    /// Resource limits applied to the command
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    /// Whether the command runs in a minimal root (`--isolate`)
+    #[serde(default)]
+    pub isolated: bool,
 }
 
 /// Serializable filesystem capability state
@@ -40,6 +65,12 @@ pub struct FsCapState {
     pub access: String,
     /// Whether this is a single file (vs directory)
     pub is_file: bool,
+    /// Whether programs under the path may not be executed
+    #[serde(default)]
+    pub noexec: bool,
+    /// Where the capability was granted
+    #[serde(default)]
+    pub source: CapabilitySource,
 }
 
 impl SandboxState {
@@ -58,11 +89,20 @@ impl SandboxState {
                         FsAccess::ReadWrite => "readwrite".to_string(),
                     },
                     is_file: c.is_file,
+                    noexec: c.noexec,
+                    source: c.source.clone(),
                 })
                 .collect(),
             net_blocked: caps.net_block,
+            net_block_degraded: caps.net_block_degraded,
+            net_mode: caps.net_mode,
+            scopes: caps.scopes.clone(),
             allowed_commands: caps.allowed_commands.clone(),
             blocked_commands: caps.blocked_commands.clone(),
+            command_rules: caps.command_rules.clone(),
+            exec_allowlist: caps.exec_allowlist.clone(),
+            limits: caps.limits.clone(),
+            isolated: caps.isolate,
         }
     }
 
@@ -87,13 +127,22 @@ impl SandboxState {
                 resolved: PathBuf::from(&fs_cap.path),
                 access,
                 is_file: fs_cap.is_file,
+                noexec: fs_cap.noexec,
+                source: fs_cap.source.clone(),
             };
             caps.fs.push(cap);
         }
 
         caps.net_block = self.net_blocked;
+        caps.net_block_degraded = self.net_block_degraded;
+        caps.net_mode = self.net_mode;
+        caps.scopes = self.scopes.clone();
         caps.allowed_commands = self.allowed_commands.clone();
         caps.blocked_commands = self.blocked_commands.clone();
+        caps.command_rules = self.command_rules.clone();
+        caps.exec_allowlist = self.exec_allowlist.clone();
+        caps.limits = self.limits.clone();
+        caps.isolate = self.isolated;
 
         caps
     }
@@ -157,6 +206,22 @@ impl SandboxState {
 /// Maximum size for capability state files (1 MB is more than enough)
 const MAX_CAP_FILE_SIZE: u64 = 1_048_576;
 
+/// The system temp directory, where capability state files are written
+///
+/// Inside a sandbox, `TMPDIR` is the session's private temp directory
+/// (`nono-XXXXXX`), which is created in the system temp directory.
+pub fn system_temp_dir() -> PathBuf {
+    let temp_dir = std::env::temp_dir();
+    let in_session = temp_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("nono-"));
+    match temp_dir.parent() {
+        Some(parent) if in_session => parent.to_path_buf(),
+        _ => temp_dir,
+    }
+}
+
 /// Validate the NONO_CAP_FILE path for security
 ///
 /// This function implements defense-in-depth validation to prevent:
@@ -182,18 +247,18 @@ fn validate_cap_file_path(path_str: &str) -> Result<PathBuf> {
             reason: format!("failed to canonicalize path: {}", e),
         })?;
 
-    // Security check: Must be in system temp directory
-    // This prevents reading arbitrary files on the system
+    // Security check: Must be directly in the system temp directory
+    // This prevents reading arbitrary files on the system, or a file the
+    // sandboxed command wrote to its private temp directory
     // Note: On macOS, /tmp is a symlink to /private/tmp or /var/folders/...
     // so we must canonicalize the temp dir for comparison
-    let temp_dir =
-        std::env::temp_dir()
-            .canonicalize()
-            .map_err(|e| NonoError::CapFileValidation {
-                reason: format!("failed to canonicalize temp directory: {}", e),
-            })?;
+    let temp_dir = system_temp_dir()
+        .canonicalize()
+        .map_err(|e| NonoError::CapFileValidation {
+            reason: format!("failed to canonicalize temp directory: {}", e),
+        })?;
 
-    if !canonical.starts_with(&temp_dir) {
+    if canonical.parent() != Some(temp_dir.as_path()) {
         return Err(NonoError::CapFileValidation {
             reason: format!(
                 "path must be in temp directory ({}), got: {}",
@@ -348,7 +413,7 @@ fn is_process_running(_pid: u32) -> bool {
 /// Individual file deletion errors are logged but don't cause the function to fail.
 /// This ensures nono continues to work even if some cleanup fails.
 pub fn cleanup_stale_state_files() {
-    let temp_dir = std::env::temp_dir();
+    let temp_dir = system_temp_dir();
 
     // Read directory entries
     let entries = match std::fs::read_dir(&temp_dir) {
@@ -432,14 +497,19 @@ mod tests {
     fn test_sandbox_state_roundtrip() {
         let mut caps = CapabilitySet::new();
         caps.net_block = true;
+        caps.net_block_degraded = true;
+        caps.scopes = vec![IpcScope::Signal];
         caps.allowed_commands = vec!["pip".to_string()];
 
         let state = SandboxState::from_caps(&caps);
         assert!(state.net_blocked);
+        assert!(state.net_block_degraded);
         assert_eq!(state.allowed_commands, vec!["pip"]);
 
         let restored = state.to_caps();
         assert!(restored.net_block);
+        assert!(restored.net_block_degraded);
+        assert_eq!(restored.scopes, vec![IpcScope::Signal]);
         assert_eq!(restored.allowed_commands, vec!["pip"]);
     }
 
@@ -472,23 +542,36 @@ mod tests {
                     path: "/home/user/src".to_string(),
                     access: "read".to_string(),
                     is_file: false,
+                    noexec: false,
+                    source: CapabilitySource::Cli,
                 },
                 FsCapState {
                     original: "./out".to_string(),
                     path: "/home/user/out".to_string(),
                     access: "write".to_string(),
                     is_file: false,
+                    noexec: false,
+                    source: CapabilitySource::Cli,
                 },
                 FsCapState {
                     original: "./data".to_string(),
                     path: "/home/user/data".to_string(),
                     access: "readwrite".to_string(),
                     is_file: false,
+                    noexec: false,
+                    source: CapabilitySource::Cli,
                 },
             ],
             net_blocked: false,
+            net_block_degraded: false,
+            net_mode: NetMode::Host,
+            scopes: vec![],
             allowed_commands: vec![],
             blocked_commands: vec![],
+            command_rules: vec![],
+            exec_allowlist: None,
+            limits: ResourceLimits::default(),
+            isolated: false,
         };
 
         let caps = state.to_caps();
@@ -580,8 +663,15 @@ mod tests {
         let test_state = SandboxState {
             fs: vec![],
             net_blocked: true,
+            net_block_degraded: false,
+            net_mode: NetMode::Host,
+            scopes: vec![],
             allowed_commands: vec![],
             blocked_commands: vec![],
+            command_rules: vec![],
+            exec_allowlist: None,
+            limits: ResourceLimits::default(),
+            isolated: false,
         };
 
         let json = serde_json::to_string(&test_state).expect("Failed to serialize");
@@ -615,6 +705,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_cap_file_rejects_subdirectory() {
+        // A session's private temp directory is writable by the command
+        let dir = tempfile::Builder::new()
+            .prefix("nono-")
+            .tempdir_in(std::env::temp_dir())
+            .expect("Failed to create temp dir");
+        let file_path = dir.path().join(".nono-12345.json");
+        std::fs::write(&file_path, "{}").expect("Failed to write test file");
+
+        let result = validate_cap_file_path(file_path.to_str().unwrap_or_default());
+        assert!(matches!(result, Err(NonoError::CapFileValidation { .. })));
+    }
+
     // Security tests for write_to_file
 
     #[test]
@@ -628,8 +732,15 @@ mod tests {
         let state = SandboxState {
             fs: vec![],
             net_blocked: true,
+            net_block_degraded: false,
+            net_mode: NetMode::Host,
+            scopes: vec![],
             allowed_commands: vec![],
             blocked_commands: vec![],
+            command_rules: vec![],
+            exec_allowlist: None,
+            limits: ResourceLimits::default(),
+            isolated: false,
         };
 
         state
@@ -658,8 +769,15 @@ mod tests {
         let state = SandboxState {
             fs: vec![],
             net_blocked: true,
+            net_block_degraded: false,
+            net_mode: NetMode::Host,
+            scopes: vec![],
             allowed_commands: vec![],
             blocked_commands: vec![],
+            command_rules: vec![],
+            exec_allowlist: None,
+            limits: ResourceLimits::default(),
+            isolated: false,
         };
 
         // Should fail due to create_new(true)
@@ -682,8 +800,15 @@ mod tests {
         let state = SandboxState {
             fs: vec![],
             net_blocked: true,
+            net_block_degraded: false,
+            net_mode: NetMode::Host,
+            scopes: vec![],
             allowed_commands: vec![],
             blocked_commands: vec![],
+            command_rules: vec![],
+            exec_allowlist: None,
+            limits: ResourceLimits::default(),
+            isolated: false,
         };
 
         // First write succeeds
@@ -0,0 +1,67 @@
+//! Private temporary directory for a sandboxed session
+//!
+//! Instead of sharing the system temp directory with the user and other
+//! sandboxed agents, each session gets its own `nono-XXXXXX` directory inside
+//! it (mode 0700). The directory is granted read+write, exported to the
+//! command as `TMPDIR`, and used for `$TMPDIR` in profiles. It is removed
+//! once the session ends unless `--keep-tmp` is given.
+
+use crate::cleanup;
+use crate::error::{NonoError, Result};
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+
+/// A session's private temp directory
+pub struct SessionTmp {
+    path: PathBuf,
+    /// Write end of the cleanup helper's pipe (`None` when kept)
+    cleanup: Option<OwnedFd>,
+}
+
+impl SessionTmp {
+    /// Create the directory inside the system temp directory.
+    ///
+    /// Must be called BEFORE the sandbox is applied and while nono is still
+    /// single-threaded (before secrets are loaded): the cleanup helper
+    /// removes the directory tree from a forked child.
+    pub fn create(keep: bool) -> Result<Self> {
+        let template = std::env::temp_dir().join("nono-XXXXXX");
+        let path = nix::unistd::mkdtemp(&template).map_err(|e| {
+            NonoError::SandboxInit(format!(
+                "Cannot create private temp directory in {}: {}",
+                std::env::temp_dir().display(),
+                e
+            ))
+        })?;
+
+        let cleanup = if keep {
+            None
+        } else {
+            let target = path.clone();
+            match cleanup::spawn_on_exit(move || {
+                let _ = std::fs::remove_dir_all(&target);
+            }) {
+                Ok(fd) => Some(fd),
+                Err(e) => {
+                    let _ = std::fs::remove_dir(&path);
+                    return Err(NonoError::SandboxInit(format!(
+                        "Cannot start cleanup helper for the private temp directory: {}",
+                        e
+                    )));
+                }
+            }
+        };
+
+        Ok(Self { path, cleanup })
+    }
+
+    /// Path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the directory outlives the session (`--keep-tmp`)
+    pub fn is_kept(&self) -> bool {
+        self.cleanup.is_none()
+    }
+}
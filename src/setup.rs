@@ -10,6 +10,7 @@ use nix::libc;
 
 pub struct SetupRunner {
     check_only: bool,
+    json: bool,
     generate_profiles: bool,
     show_shell_integration: bool,
     #[allow(dead_code)]
@@ -20,6 +21,7 @@ impl SetupRunner {
     pub fn new(args: &SetupArgs) -> Self {
         Self {
             check_only: args.check_only,
+            json: args.json,
             generate_profiles: args.profiles,
             show_shell_integration: args.shell_integration,
             verbose: args.verbose,
@@ -27,6 +29,16 @@ impl SetupRunner {
     }
 
     pub fn run(&self) -> Result<()> {
+        // Machine-readable report only
+        if self.json {
+            let report = crate::sandbox::capabilities();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+            return Ok(());
+        }
+
         // Print ASCII art banner with random quote
         self.print_banner();
 
@@ -204,10 +216,11 @@ impl SetupRunner {
             println!("  ✓ Kernel version: {}", version);
         }
 
-        // Check LSM list
-        let lsm_list = std::fs::read_to_string("/sys/kernel/security/lsm").unwrap_or_default();
+        // Ask the kernel for its Landlock ABI (also works without securityfs)
+        let report = crate::sandbox::capabilities();
+        let abi = report.abi.unwrap_or(0);
 
-        if !lsm_list.contains("landlock") {
+        if !report.available {
             return Err(NonoError::Setup(
                 "Landlock is not enabled in kernel LSM list.\n\n\
                 To enable Landlock:\n\
@@ -219,46 +232,32 @@ impl SetupRunner {
             ));
         }
 
-        println!("  ✓ Landlock enabled in LSM list");
-
-        // We target the highest ABI and report its features
-        // The actual sandbox will use Compatible trait to handle older kernels
-        let abi = ABI::V5;
-
-        println!("  ✓ Landlock ABI: {:?}", abi);
-        println!("  ✓ Available features:");
-
-        match abi {
-            ABI::V1 => {
-                println!("      - Basic filesystem access control");
-            }
-            ABI::V2 => {
-                println!("      - Basic filesystem access control");
-                println!("      - File rename across directories");
-            }
-            ABI::V3 => {
-                println!("      - Basic filesystem access control");
-                println!("      - File rename across directories");
-                println!("      - File truncation");
-            }
-            ABI::V4 => {
-                println!("      - Basic filesystem access control");
-                println!("      - File rename across directories");
-                println!("      - File truncation");
-                println!("      - TCP network filtering");
-            }
-            ABI::V5 => {
-                println!("      - Basic filesystem access control");
-                println!("      - File rename across directories");
-                println!("      - File truncation");
-                println!("      - TCP network filtering");
-                println!("      - Advanced socket and signal scoping");
-            }
-            _ => {}
+        println!("  ✓ Landlock enabled");
+        println!(
+            "  ✓ Landlock ABI: v{} (nono targets v{})",
+            abi,
+            report.target_abi.unwrap_or(0)
+        );
+        println!("  ✓ Features:");
+
+        for feature in &report.features {
+            let mark = match feature.status {
+                crate::sandbox::FeatureStatus::Enforced => "✓",
+                crate::sandbox::FeatureStatus::Degraded => "~",
+                crate::sandbox::FeatureStatus::Unavailable => "✗",
+            };
+            let needs = match (feature.status, feature.min_abi) {
+                (crate::sandbox::FeatureStatus::Enforced, _) | (_, None) => String::new(),
+                (_, Some(min)) => format!(" (needs ABI v{})", min),
+            };
+            println!(
+                "      {} {}: {}{}",
+                mark, feature.name, feature.description, needs
+            );
         }
 
         // Try creating a test ruleset
-        let handled = AccessFs::from_all(abi);
+        let handled = AccessFs::from_all(ABI::V1);
         Ruleset::default()
             .handle_access(handled)
             .and_then(|r| r.create())
@@ -205,6 +205,25 @@ else
     run_test "write to write-only directory succeeded" 0 test -f "$TMPDIR/mixed_write/output.txt"
 fi
 
+# =============================================================================
+# Timeout
+# =============================================================================
+
+echo ""
+echo "--- Timeout ---"
+
+if is_linux && command_exists setsid; then
+    # A grandchild in its own session keeps the output pipes open after the
+    # command is killed; --timeout must still return (the outer timeout
+    # reports a hang as 137)
+    run_test "--timeout returns with a setsid grandchild holding the output open" 124 \
+        timeout -s KILL 30 "$NONO_BIN" run --allow "$TMPDIR" --timeout 1s --kill-after 1s -- \
+        sh -c 'setsid sleep 4242 & sleep 100' || true
+    pkill -f '^sleep 4242$' || true
+else
+    skip_test "--timeout returns with a setsid grandchild holding the output open" "requires Linux and setsid"
+fi
+
 # =============================================================================
 # Summary
 # =============================================================================